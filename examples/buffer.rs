@@ -1,16 +1,16 @@
-/// Example implementation of a rendezvous buffer.
-///
-/// The values are passed directly from a sender to a receiver. This example
-/// most importantly demonstrates error handling related to channels not being
-/// able to send messages after their controller has shut down. Failing to
-/// correctly handle cases like that will cause threads to panic.
+//! Example implementation of a rendezvous buffer.
+//!
+//! The values are passed directly from a sender to a receiver. This example
+//! most importantly demonstrates error handling related to channels not being
+//! able to send messages after their controller has shut down. Failing to
+//! correctly handle cases like that will cause threads to panic.
 
 use std::any::Any;
 use std::thread;
 
 use rusty_junctions::channels::{RecvChannel, SendChannel};
 use rusty_junctions::types::ControllerHandle;
-use rusty_junctions::Junction;
+use rusty_junctions::{Junction, JunctionError};
 
 // Create a new private buffer Junction and return all required channels.
 //
@@ -25,14 +25,14 @@ where
     let mut buffer = Junction::new();
 
     // Asynchronous channel to put a value into the buffer.
-    let put = buffer.send_channel::<T>();
+    let put = buffer.send_channel::<T>().unwrap();
 
     // Synchronous channel to get the value from the buffer.
-    let get = buffer.recv_channel::<T>();
+    let get = buffer.recv_channel::<T>().unwrap();
 
     // When there is a value put into the buffer and someone signalled they are
     // ready to get, return the value.
-    buffer.when(&put).and_recv(&get).then_do(|v| v);
+    buffer.when(&put).and_recv(&get).then_do(|v| v).unwrap();
 
     // Take ownership of the ControllerHandle so that the Junction does not
     // drop its resources when going out of scope.
@@ -63,19 +63,29 @@ fn main() {
 
         // Ensure that a value is received. If not, shut down the thread.
         // A reason for no value being received is that the controller
-        // running in the background has already been asked to shut down.
-        // In this case, there is nothing else to do as the channels are
-        // now invalid.
-        if let Ok(v) = get_1.recv() {
-            println!("<Thread 1> Got {}!", v);
-            println!("<Thread 1> Putting double the value in...");
-
-            let put_value = 2 * v;
-            put_1.send(put_value).unwrap();
-            println!("<Thread 1> Done putting {}!", put_value);
-        } else {
-            println!("<Thread 1> Couldn't get a value anymore...");
-            println!("<Thread 1> Shutting down...");
+        // running in the background has already been asked to shut down,
+        // reported as `JunctionClosed`, or did so while the request was
+        // pending, reported as `ReplyDropped`. In either case, there is
+        // nothing else to do as the channels are now invalid.
+        match get_1.recv() {
+            Ok(v) => {
+                println!("<Thread 1> Got {}!", v);
+                println!("<Thread 1> Putting double the value in...");
+
+                let put_value = 2 * v;
+                match put_1.send(put_value) {
+                    Ok(()) => println!("<Thread 1> Done putting {}!", put_value),
+                    Err(JunctionError::JunctionClosed) => {
+                        println!("<Thread 1> Buffer closed before putting {}...", put_value)
+                    }
+                    Err(e) => panic!("<Thread 1> Unexpected error: {}", e),
+                }
+            }
+            Err(JunctionError::JunctionClosed) | Err(JunctionError::ReplyDropped) => {
+                println!("<Thread 1> Couldn't get a value anymore...");
+                println!("<Thread 1> Shutting down...");
+            }
+            Err(e) => panic!("<Thread 1> Unexpected error: {}", e),
         }
     });
 
@@ -84,16 +94,25 @@ fn main() {
     let get_2 = get.clone();
     thread::spawn(move || {
         println!("<Thread 2> Asking for the buffer value...");
-        if let Ok(v) = get_2.recv() {
-            println!("<Thread 2> Got {}!", v);
-            println!("<Thread 2> Putting three times plus one of the value in...");
-
-            let put_value = 3 * v + 1;
-            put_2.send(put_value).unwrap();
-            println!("<Thread 2> Done putting {}!", put_value);
-        } else {
-            println!("<Thread 2> Couldn't get a value anymore...");
-            println!("<Thread 2> Shutting down...");
+        match get_2.recv() {
+            Ok(v) => {
+                println!("<Thread 2> Got {}!", v);
+                println!("<Thread 2> Putting three times plus one of the value in...");
+
+                let put_value = 3 * v + 1;
+                match put_2.send(put_value) {
+                    Ok(()) => println!("<Thread 2> Done putting {}!", put_value),
+                    Err(JunctionError::JunctionClosed) => {
+                        println!("<Thread 2> Buffer closed before putting {}...", put_value)
+                    }
+                    Err(e) => panic!("<Thread 2> Unexpected error: {}", e),
+                }
+            }
+            Err(JunctionError::JunctionClosed) | Err(JunctionError::ReplyDropped) => {
+                println!("<Thread 2> Couldn't get a value anymore...");
+                println!("<Thread 2> Shutting down...");
+            }
+            Err(e) => panic!("<Thread 2> Unexpected error: {}", e),
         }
     });
 
@@ -114,15 +133,14 @@ fn main() {
     // here. The only guarantee is that a value will eventual be received,
     // as we have as many puts as we have gets.
     println!("<Main Thread> Asking for the buffer value...");
-    if let Ok(v) = get.recv() {
-        println!("<Main Thread> Got {}!", v);
-    } else {
-        println!("<Main Thread> Couldn't get a value anymore...");
+    match get.recv() {
+        Ok(v) => println!("<Main Thread> Got {}!", v),
+        Err(e) => println!("<Main Thread> Couldn't get a value anymore: {}", e),
     }
 
     // Clean up the resources running in the background that manage the
     // private buffer Junction.
     // Note that this could happen while either of the other threads are still
     // trying to send messages to the controller that is being cleaned up here.
-    ch.stop();
+    ch.stop().unwrap();
 }
@@ -1,18 +1,18 @@
-/// Junction computing the Collatz sequence for a given number up to a
-/// maximum number of iterations.
-///
-/// This example demonstrates how finite iterations can be implemented using
-/// junctions and join patterns. It also demonstrates how user-defined types
-/// can be used with the channels that this library provides and how to return
-/// a final result of an entirely asynchronous calculation. Run the example
-/// by executing the following command in the repository root:
-///
-/// $ cargo run --example collatz -- <initiat value> <maximum iterations>
-///
-/// replacing the arguments after the "--" with the desired integer values.
-/// Refer to the following Wikipedia article for the mathematical background:
-///
-/// https://en.wikipedia.org/wiki/Collatz_conjecture
+//! Junction computing the Collatz sequence for a given number up to a
+//! maximum number of iterations.
+//!
+//! This example demonstrates how finite iterations can be implemented using
+//! junctions and join patterns. It also demonstrates how user-defined types
+//! can be used with the channels that this library provides and how to return
+//! a final result of an entirely asynchronous calculation. Run the example
+//! by executing the following command in the repository root:
+//!
+//! $ cargo run --example collatz -- <initiat value> <maximum iterations>
+//!
+//! replacing the arguments after the "--" with the desired integer values.
+//! Refer to the following Wikipedia article for the mathematical background:
+//!
+//! https://en.wikipedia.org/wiki/Collatz_conjecture
 
 use std::env;
 
@@ -28,7 +28,7 @@ struct CollatzResult {
 
 // Auxiliary function to make the code a little more readable.
 fn is_even(value: u64) -> bool {
-    value % 2 == 0
+    value.is_multiple_of(2)
 }
 
 fn main() {
@@ -43,47 +43,55 @@ fn main() {
     let collatz = Junction::new();
 
     // Asynchronous state channel to hold the current value in the sequence.
-    let value = collatz.send_channel::<u64>();
+    let value = collatz.send_channel::<u64>().unwrap();
     // Asynchronous state channel to hold the number of iterations left.
-    let iter = collatz.send_channel::<u64>();
+    let iter = collatz.send_channel::<u64>().unwrap();
 
     // Channel to signal that the calculation is either finished or that
     // the maximum number of iterations has been reached.
-    let finished = collatz.send_channel::<CollatzResult>();
+    let finished = collatz.send_channel::<CollatzResult>().unwrap();
     // Channel to receive the final result of the calculation back to the main
     // thread.
-    let result = collatz.recv_channel::<CollatzResult>();
+    let result = collatz.recv_channel::<CollatzResult>().unwrap();
 
     // Perform the iterations to compute the sequence.
     let value_clone = value.clone();
     let iter_clone = iter.clone();
     let finished_clone = finished.clone();
-    collatz.when(&iter).and(&value).then_do(move |n, v| {
-        // If there are still iterations left or the sequence has not reached
-        // 1 yet, compute the next step and print the transition.
-        if n > 0 && v != 1 {
-            iter_clone.send(n - 1).unwrap();
-
-            let new_v = if is_even(v) { v / 2 } else { 3 * v + 1 };
-
-            println!("{} -> {}", v, new_v);
-            value_clone.send(new_v).unwrap();
-        } else {
-            // The computation is finished, either by reaching 1 or by running
-            // out of available iterations. Send the result with a user-defined
-            // type to the main channel.
-            finished_clone
-                .send(CollatzResult {
-                    final_value: v,
-                    iterations_left: n,
-                })
-                .unwrap();
-        }
-    });
+    collatz
+        .when(&iter)
+        .and(&value)
+        .then_do(move |n, v| {
+            // If there are still iterations left or the sequence has not reached
+            // 1 yet, compute the next step and print the transition.
+            if n > 0 && v != 1 {
+                iter_clone.send(n - 1).unwrap();
+
+                let new_v = if is_even(v) { v / 2 } else { 3 * v + 1 };
+
+                println!("{} -> {}", v, new_v);
+                value_clone.send(new_v).unwrap();
+            } else {
+                // The computation is finished, either by reaching 1 or by running
+                // out of available iterations. Send the result with a user-defined
+                // type to the main channel.
+                finished_clone
+                    .send(CollatzResult {
+                        final_value: v,
+                        iterations_left: n,
+                    })
+                    .unwrap();
+            }
+        })
+        .unwrap();
 
     // If the computation has finished and a thread requested the result, send
     // it to that thread.
-    collatz.when(&finished).and_recv(&result).then_do(|res| res);
+    collatz
+        .when(&finished)
+        .and_recv(&result)
+        .then_do(|res| res)
+        .unwrap();
 
     // Send the initial values taken from the command line. This starts the
     // iteration, which from here on out happens completely asynchronously.
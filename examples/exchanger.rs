@@ -0,0 +1,43 @@
+//! Example implementation of an exchanger for two threads.
+//!
+//! Each thread offers a value and blocks until a partner has offered a value
+//! as well, at which point both receive the value of the other. This is the
+//! classic rendezvous of the Join Calculus, written as a single Join Pattern
+//! over two synchronous channels.
+
+use std::thread;
+
+use rusty_junctions::Junction;
+
+fn main() {
+    // Junction to set up the exchanger.
+    let exchanger = Junction::new();
+
+    // Synchronous channel for the thread on the left to offer a value.
+    let left = exchanger.bidir_channel::<String, String>().unwrap();
+
+    // Synchronous channel for the thread on the right to offer a value.
+    let right = exchanger.bidir_channel::<String, String>().unwrap();
+
+    // When both threads have offered a value, swap them. The replies are
+    // returned in the same order as the channels were declared, so the first
+    // reply goes back to the left and the second one to the right.
+    exchanger
+        .when_bidir(&left)
+        .and_bidir(&right)
+        .then_do(|(l, (r, ()))| (r, (l, ())))
+        .unwrap();
+
+    let left_clone = left.clone();
+    let handle = thread::spawn(move || {
+        println!("<Left> Offering \"ping\"...");
+        let received = left_clone.send_recv(String::from("ping")).unwrap();
+        println!("<Left> Received \"{}\"!", received);
+    });
+
+    println!("<Right> Offering \"pong\"...");
+    let received = right.send_recv(String::from("pong")).unwrap();
+    println!("<Right> Received \"{}\"!", received);
+
+    handle.join().unwrap();
+}
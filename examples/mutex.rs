@@ -1,42 +1,46 @@
-/// Example implementation of a mutually exclusive lock, or mutex.
-///
-/// This code provides evidence that concurrent code using locks can equally
-/// be implemented using junctions and join patterns.
+//! Example implementation of a mutually exclusive lock, or mutex.
+//!
+//! This code provides evidence that concurrent code using locks can equally
+//! be implemented using junctions and join patterns.
 
 use std::thread;
 
-use rusty_junctions::Junction;
+use rusty_junctions::channels::{RecvChannel, SendChannel};
 use rusty_junctions::types::ControllerHandle;
-use rusty_junctions::channels::{SendChannel, RecvChannel};
+use rusty_junctions::Junction;
 
 // Create a new mutex using a private junction and return channels to acquire
 // and release it.
-fn new_mutex() -> (
-    ControllerHandle, RecvChannel<()>, SendChannel<()>
-)
-{
+fn new_mutex() -> (ControllerHandle, RecvChannel<()>, SendChannel<()>) {
     // Private junction to set up the mutex.
     let mut mutex = Junction::new();
 
     // Channel to acquire the lock. Blocks until it is acquired.
-    let acquire = mutex.recv_channel::<()>();
+    let acquire = mutex.recv_channel::<()>().unwrap();
     // Channel to release the lock. Does not block.
-    let release = mutex.send_channel::<()>();
+    let release = mutex.send_channel::<()>().unwrap();
     // Asynchronous state channel to represent a lock that can be consumed
     // and released.
-    let lock = mutex.send_channel::<()>();
-    
+    let lock = mutex.send_channel::<()>().unwrap();
+
     // When there is a lock available and a thread wants to acquire it,
     // unblock that thread which is equivalent to acquiring the lock.
     // It is mutually exclusive as no new lock message is sent out.
-    mutex.when(&lock).and_recv(&acquire).then_do(|_| {});
+    mutex
+        .when(&lock)
+        .and_recv(&acquire)
+        .then_do(|_| {})
+        .unwrap();
 
     // If a thread calls to release the lock, send out a new lock message
     // to unblock any open acquire call.
     let lock_clone = lock.clone();
-    mutex.when(&release).then_do(move |_| {
-        let _ = lock_clone.send(());
-    });
+    mutex
+        .when(&release)
+        .then_do(move |_| {
+            let _ = lock_clone.send(());
+        })
+        .unwrap();
 
     // Create a lock to be acquired and released.
     lock.send(()).unwrap();
@@ -81,5 +85,5 @@ fn main() {
     // background.
     jh_1.join().unwrap();
     jh_2.join().unwrap();
-    ch.stop();
+    ch.stop().unwrap();
 }
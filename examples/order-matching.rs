@@ -0,0 +1,87 @@
+//! Example of matching buy and sell orders using a guarded Join Pattern.
+//!
+//! Orders are sent in any order, but a buy order is only ever matched with a
+//! sell order for the same item whose price it is willing to pay. Orders that
+//! cannot be matched yet stay with the Junction until a suitable partner
+//! arrives.
+
+use std::sync::mpsc::channel;
+
+use rusty_junctions::Junction;
+
+struct Order {
+    item: &'static str,
+    price: u32,
+}
+
+fn main() {
+    // Junction to set up the order book.
+    let order_book = Junction::new();
+
+    // Asynchronous channel to place a buy order.
+    let buy = order_book.send_channel::<Order>().unwrap();
+
+    // Asynchronous channel to place a sell order.
+    let sell = order_book.send_channel::<Order>().unwrap();
+
+    // Standard channel to report trades on.
+    let (trades_sender, trades) = channel();
+
+    // Match a buy and sell order for the same item if the buyer is willing to
+    // pay at least as much as the seller asks for.
+    order_book
+        .when(&buy)
+        .and(&sell)
+        .guard(|b, s| b.item == s.item && b.price >= s.price)
+        .then_do(move |b, s| {
+            trades_sender
+                .send(format!(
+                    "{} bought for {} (asked {})",
+                    b.item, b.price, s.price
+                ))
+                .unwrap();
+        })
+        .unwrap();
+
+    sell.send(Order {
+        item: "apple",
+        price: 5,
+    })
+    .unwrap();
+    sell.send(Order {
+        item: "pear",
+        price: 3,
+    })
+    .unwrap();
+
+    // Too cheap for the apple and no one is selling plums, so neither buy
+    // order is matched.
+    buy.send(Order {
+        item: "apple",
+        price: 4,
+    })
+    .unwrap();
+    buy.send(Order {
+        item: "plum",
+        price: 9,
+    })
+    .unwrap();
+
+    // Matched with the pear, even though the apple was offered first.
+    buy.send(Order {
+        item: "pear",
+        price: 3,
+    })
+    .unwrap();
+
+    println!("{}", trades.recv().unwrap());
+
+    // Matched with the apple.
+    buy.send(Order {
+        item: "apple",
+        price: 6,
+    })
+    .unwrap();
+
+    println!("{}", trades.recv().unwrap());
+}
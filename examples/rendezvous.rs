@@ -1,11 +1,11 @@
-/// Rendezvous implementation for n entities to meet up.
-///
-/// Implmentation is a translation of the rendezvous written in Polyphonic C#
-/// in the "Jingle bells: Solving the santa claus problem in polyphonic c#"
-/// paper by Benton.
-///
-/// Link to the paper:
-/// https://www.researchgate.net/profile/Nick_Benton2/publication/2569067_Jingle_Bells_Solving_the_Santa_Claus_Problem_in/links/0c9605264f92520a08000000/Jingle-Bells-Solving-the-Santa-Claus-Problem-in.pdf
+//! Rendezvous implementation for n entities to meet up.
+//!
+//! Implmentation is a translation of the rendezvous written in Polyphonic C#
+//! in the "Jingle bells: Solving the santa claus problem in polyphonic c#"
+//! paper by Benton.
+//!
+//! Link to the paper:
+//! https://www.researchgate.net/profile/Nick_Benton2/publication/2569067_Jingle_Bells_Solving_the_Santa_Claus_Problem_in/links/0c9605264f92520a08000000/Jingle-Bells-Solving-the-Santa-Claus-Problem-in.pdf
 
 use std::thread;
 
@@ -18,41 +18,46 @@ pub fn rendezvous() -> (ControllerHandle, BidirChannel<u32, ()>, RecvChannel<()>
     let mut j = Junction::new();
 
     // Asynchronous token channel to carry the state.
-    let token = j.send_channel::<u32>();
+    let token = j.send_channel::<u32>().unwrap();
 
     // Synchronous entry channel.
-    let entry = j.recv_channel::<()>();
+    let entry = j.recv_channel::<()>().unwrap();
 
     // Synchronous channel to set up number of available tokens.
-    let accept_n = j.bidir_channel::<u32, ()>();
+    let accept_n = j.bidir_channel::<u32, ()>().unwrap();
 
     // Synchronous wait channel.
-    let wait = j.recv_channel::<()>();
+    let wait = j.recv_channel::<()>().unwrap();
 
     // Asynchronous all_gone channel.
-    let all_gone = j.send_channel::<()>();
+    let all_gone = j.send_channel::<()>().unwrap();
 
     // Count down the arrivals.
     let token_clone = token.clone();
     let all_gone_clone = all_gone.clone();
-    j.when(&token).and_recv(&entry).then_do(move |n| {
-        if n == 1 {
-            all_gone_clone.send(()).unwrap();
-        } else {
-            token_clone.send(n - 1);
-        }
-    });
+    j.when(&token)
+        .and_recv(&entry)
+        .then_do(move |n| {
+            if n == 1 {
+                all_gone_clone.send(()).unwrap();
+            } else {
+                token_clone.send(n - 1).unwrap();
+            }
+        })
+        .unwrap();
 
     // Spawn n new token and wait for all entries to rendezvous.
     let token_clone = token.clone();
     let wait_clone = wait.clone();
-    j.when_bidir(&accept_n).then_do(move |n| {
-        token_clone.send(n).unwrap();
-        wait_clone.recv().unwrap();
-    });
+    j.when_bidir(&accept_n)
+        .then_do(move |n| {
+            token_clone.send(n).unwrap();
+            wait_clone.recv().unwrap();
+        })
+        .unwrap();
 
     // Stop waiting once all tokens are gone.
-    j.when(&all_gone).and_recv(&wait).then_do(|_| {});
+    j.when(&all_gone).and_recv(&wait).then_do(|_| {}).unwrap();
 
     // Prevent Junction from stopping control thread after return.
     let controller_handle = j.controller_handle().unwrap();
@@ -69,7 +74,7 @@ fn main() {
     let (mut ch, accept_n, entry) = rendezvous();
     println!("Done constructing rendezvous!");
 
-    for i in 0..num_entries {
+    for _ in 0..num_entries {
         let entry_clone = entry.clone();
         thread::spawn(move || {
             println!("Sending entry...");
@@ -83,5 +88,5 @@ fn main() {
     println!("All {} entrie(s) arrived!", num_entries);
 
     // Clean up controller resources running in background manually.
-    ch.stop();
+    ch.stop().unwrap();
 }
@@ -1,9 +1,9 @@
-/// Santa Claus Problem in Rusty Junctions.
-///
-/// The code below is based on the paper "Jingle Bells:  Solving the Santa Claus
-/// Problem in Polyphonic C#" by Nick Benton
-///
-/// The paper: https://www.researchgate.net/profile/Nick_Benton2/publication/2569067_Jingle_Bells_Solving_the_Santa_Claus_Problem_in/links/0c9605264f92520a08000000/Jingle-Bells-Solving-the-Santa-Claus-Problem-in.pdf
+//! Santa Claus Problem in Rusty Junctions.
+//!
+//! The code below is based on the paper "Jingle Bells:  Solving the Santa Claus
+//! Problem in Polyphonic C#" by Nick Benton
+//!
+//! The paper: https://www.researchgate.net/profile/Nick_Benton2/publication/2569067_Jingle_Bells_Solving_the_Santa_Claus_Problem_in/links/0c9605264f92520a08000000/Jingle-Bells-Solving-the-Santa-Claus-Problem-in.pdf
 
 use rand::Rng;
 
@@ -22,10 +22,10 @@ fn main() {
     let elves = Junction::new();
 
     // Synchronous channel to signal that elf wants to queue up.
-    let elf_queue = elves.recv_channel::<()>();
+    let elf_queue = elves.recv_channel::<()>().unwrap();
 
     // Asynchronous channel to carry the number of elves that are queued up.
-    let elves_waiting = elves.send_channel::<u32>();
+    let elves_waiting = elves.send_channel::<u32>().unwrap();
 
     /********************************
      * Reindeer Junction & Channels *
@@ -35,10 +35,10 @@ fn main() {
     let reindeer = Junction::new();
 
     // Synchronous channel to signal that reindeer is back from holiday.
-    let reindeer_back = reindeer.recv_channel::<()>();
+    let reindeer_back = reindeer.recv_channel::<()>().unwrap();
 
     // Asynchronous channel to carry the number of reindeer waiting in the stable.
-    let reindeer_waiting = reindeer.send_channel::<u32>();
+    let reindeer_waiting = reindeer.send_channel::<u32>().unwrap();
 
     /*****************************
      * Santa Junction & Channels *
@@ -48,33 +48,25 @@ fn main() {
     let santa = Junction::new();
 
     // Synchronous channel to wait to be woken by either reindeer or elves.
-    let wait_to_be_woken = santa.recv_channel::<()>();
+    let wait_to_be_woken = santa.recv_channel::<()>().unwrap();
 
     // Asynchronous channel to signal that enough reindeer are ready.
-    let reindeer_ready = santa.send_channel::<()>();
-
-    // Asynchronous channel to signal that not enough reindeer are ready.
-    // Used for prioritisation.
-    let reindeer_not_ready = santa.send_channel::<()>();
-
-    // Synchronous channel to match and consume a reindeer_not_ready message.
-    // Used for prioritisation.
-    let clear_reindeer_not_ready = santa.recv_channel::<()>();
+    let reindeer_ready = santa.send_channel::<()>().unwrap();
 
     // Asynchronous channel to signal that enough elves are ready.
-    let elves_ready = santa.send_channel::<()>();
+    let elves_ready = santa.send_channel::<()>().unwrap();
 
     // Rendezvous channels to let elves into room.
-    let (mut ch_1, room_in_accept_n, room_in_entry) = rendezvous();
+    let (_ch_1, room_in_accept_n, room_in_entry) = rendezvous();
 
     // Rendezvous channels to let elves out of room.
-    let (mut ch_2, room_out_accept_n, room_out_entry) = rendezvous();
+    let (_ch_2, room_out_accept_n, room_out_entry) = rendezvous();
 
     // Rendezvous channels to harness the reindeer.
-    let (mut ch_3, harness_accept_n, harness_entry) = rendezvous();
+    let (_ch_3, harness_accept_n, harness_entry) = rendezvous();
 
     // Rendezvous channels to unharness the reindeer.
-    let (mut ch_4, unharness_accept_n, unharness_entry) = rendezvous();
+    let (_ch_4, unharness_accept_n, unharness_entry) = rendezvous();
 
     /***********************
      * Elves Join Patterns *
@@ -95,7 +87,8 @@ fn main() {
                 elves_waiting_clone.send(e + 1).unwrap();
                 println!("<Elves> {} waiting!", e + 1);
             }
-        });
+        })
+        .unwrap();
 
     /**************************
      * Reindeer Join Patterns *
@@ -104,39 +97,33 @@ fn main() {
     // Count up how many reindeer are waiting and possibly send ready message.
     let reindeer_ready_clone = reindeer_ready.clone();
     let reindeer_waiting_clone = reindeer_waiting.clone();
-    let clear_reindeer_not_ready_clone = clear_reindeer_not_ready.clone();
     reindeer
         .when(&reindeer_waiting)
         .and_recv(&reindeer_back)
         .then_do(move |r| {
             if r == 8 {
                 // Last reindeer just came back.
-                clear_reindeer_not_ready_clone.recv().unwrap();
                 reindeer_ready_clone.send(()).unwrap();
                 println!("<Reindeer> All 9 assembled!");
             } else {
                 reindeer_waiting_clone.send(r + 1).unwrap();
                 println!("<Reindeer> {} waiting!", r + 1);
             }
-        });
+        })
+        .unwrap();
 
     /***********************
      * Santa Join Patterns *
      ***********************/
 
     // Enough elves are ready so let's consult with them.
-    let reindeer_not_ready_clone = reindeer_not_ready.clone();
     let elves_waiting_clone = elves_waiting.clone();
     santa
         .when(&elves_ready)
-        .and(&reindeer_not_ready)
         .and_recv(&wait_to_be_woken)
-        .then_do(move |_, _| {
+        .then_do(move |_| {
             let mut rng = rand::thread_rng();
 
-            // Reindeer will still not be ready so resend just consumed message.
-            reindeer_not_ready_clone.send(()).unwrap();
-
             // Show 3 elves into the office once all are ready.
             println!("<Santa> Woken by elves, now showing them in!");
             room_in_accept_n.send_recv(3).unwrap();
@@ -154,14 +141,16 @@ fn main() {
             println!("<Santa> Now showing out elves!");
             room_out_accept_n.send_recv(3).unwrap();
             println!("<Santa> Elf group shown out!");
-        });
+        })
+        .unwrap();
 
-    // Enough reindeer are ready so let's deliver some presents.
-    let reindeer_not_ready_clone = reindeer_not_ready.clone();
+    // Enough reindeer are ready so let's deliver some presents. Should the
+    // elves be ready as well, the reindeer take priority.
     let reindeer_waiting_clone = reindeer_waiting.clone();
     santa
         .when(&reindeer_ready)
         .and_recv(&wait_to_be_woken)
+        .priority(1)
         .then_do(move |_| {
             let mut rng = rand::thread_rng();
 
@@ -170,10 +159,6 @@ fn main() {
             harness_accept_n.send_recv(9).unwrap();
             println!("<Santa> Reindeer harnessed!");
 
-            // Reindeer are harnessed, so they are no longer ready.
-            // Used for prioritisation.
-            reindeer_not_ready_clone.send(()).unwrap();
-
             // Reset how many reindeer are waiting.
             reindeer_waiting_clone.send(0).unwrap();
 
@@ -186,20 +171,15 @@ fn main() {
             println!("<Santa> Now unharnessing reindeer!");
             unharness_accept_n.send_recv(9).unwrap();
             println!("<Santa> Reindeer unharnessed!");
-        });
-
-    // Clear the reindeer_not_ready message. Used for prioritisation.
-    santa
-        .when(&reindeer_not_ready)
-        .and_recv(&clear_reindeer_not_ready)
-        .then_do(|_| {});
+        })
+        .unwrap();
 
     /*******************************
      * Start North Pole Operations *
      *******************************/
 
     // Spawn in the 10 elves and send the initial number of waiting ones.
-    for i in 0..10 {
+    for _ in 0..10 {
         new_elf(
             elf_queue.clone(),
             room_in_entry.clone(),
@@ -208,9 +188,8 @@ fn main() {
     }
     elves_waiting.send(0).unwrap();
 
-    // Spawn in the 9 reindeer, send the initial number of waiting ones and
-    // send that they are not ready yet.
-    for i in 0..9 {
+    // Spawn in the 9 reindeer and send the initial number of waiting ones.
+    for _ in 0..9 {
         new_reindeer(
             reindeer_back.clone(),
             harness_entry.clone(),
@@ -218,21 +197,15 @@ fn main() {
         );
     }
     reindeer_waiting.send(0).unwrap();
-    reindeer_not_ready.send(()).unwrap();
 
-    // Santa keeps napping until something comes up.
+    // Santa keeps napping until something comes up, for as long as the
+    // North Pole operates, so the rendezvous Junctions are never stopped.
     println!("<North Pole> Starting operations!");
-    while true {
+    loop {
         println!("<Santa> Starting a nap, waiting to be woken...");
         wait_to_be_woken.recv().unwrap();
         println!("<Santa> Woken from nap!");
     }
-
-    // Clean up the controller resources in the background manually at the end.
-    ch_1.stop();
-    ch_2.stop();
-    ch_3.stop();
-    ch_4.stop();
 }
 
 // Create a new elf in a new thread.
@@ -246,7 +219,7 @@ fn new_elf(
         // Random number generator for working and consulting times.
         let mut rng = rand::thread_rng();
 
-        while true {
+        loop {
             // Work for 0 to 10 seconds, i.e. pause thread.
             println!("<Elf> Going to work now!");
             thread::sleep(Duration::from_secs(rng.gen_range(0, 10)));
@@ -286,7 +259,7 @@ fn new_reindeer(
         // Random number generator for holiday and delivery times.
         let mut rng = rand::thread_rng();
 
-        while true {
+        loop {
             // Go on holiday for 0 to 10 seconds, i.e. pause thread.
             println!("<Reindeer> Going on holiday now!");
             thread::sleep(Duration::from_secs(rng.gen_range(0, 10)));
@@ -320,41 +293,46 @@ pub fn rendezvous() -> (ControllerHandle, BidirChannel<u32, ()>, RecvChannel<()>
     let mut j = Junction::new();
 
     // Asynchronous token channel to carry the state.
-    let token = j.send_channel::<u32>();
+    let token = j.send_channel::<u32>().unwrap();
 
     // Synchronous entry channel.
-    let entry = j.recv_channel::<()>();
+    let entry = j.recv_channel::<()>().unwrap();
 
     // Synchronous channel to set up number of available tokens.
-    let accept_n = j.bidir_channel::<u32, ()>();
+    let accept_n = j.bidir_channel::<u32, ()>().unwrap();
 
     // Synchronous wait channel.
-    let wait = j.recv_channel::<()>();
+    let wait = j.recv_channel::<()>().unwrap();
 
     // Asynchronous all_gone channel.
-    let all_gone = j.send_channel::<()>();
+    let all_gone = j.send_channel::<()>().unwrap();
 
     // Count down the arrivals.
     let token_clone = token.clone();
     let all_gone_clone = all_gone.clone();
-    j.when(&token).and_recv(&entry).then_do(move |n| {
-        if n == 1 {
-            all_gone_clone.send(()).unwrap();
-        } else {
-            token_clone.send(n - 1);
-        }
-    });
+    j.when(&token)
+        .and_recv(&entry)
+        .then_do(move |n| {
+            if n == 1 {
+                all_gone_clone.send(()).unwrap();
+            } else {
+                token_clone.send(n - 1).unwrap();
+            }
+        })
+        .unwrap();
 
     // Spawn n new token and wait for all entries to rendezvous.
     let token_clone = token.clone();
     let wait_clone = wait.clone();
-    j.when_bidir(&accept_n).then_do(move |n| {
-        token_clone.send(n).unwrap();
-        wait_clone.recv().unwrap();
-    });
+    j.when_bidir(&accept_n)
+        .then_do(move |n| {
+            token_clone.send(n).unwrap();
+            wait_clone.recv().unwrap();
+        })
+        .unwrap();
 
     // Stop waiting once all tokens are gone.
-    j.when(&all_gone).and_recv(&wait).then_do(|_| {});
+    j.when(&all_gone).and_recv(&wait).then_do(|_| {}).unwrap();
 
     // Prevent Junction from stopping control thread after return.
     let controller_handle = j.controller_handle().unwrap();
@@ -1,5 +1,5 @@
-/// Simple storage cell implementation to demonstrate every available
-/// channel as well as join patterns with repeated channels.
+//! Simple storage cell implementation to demonstrate every available
+//! channel as well as join patterns with repeated channels.
 
 use rusty_junctions::Junction;
 
@@ -11,19 +11,19 @@ fn main() {
     let cell = Junction::new();
 
     // New channel to retrieve the value of the storage cell.
-    let get = cell.recv_channel::<i32>();
+    let get = cell.recv_channel::<i32>().unwrap();
 
     // New channel to update the value of the storage cell.
-    let put = cell.send_channel::<i32>();
+    let put = cell.send_channel::<i32>().unwrap();
 
     // New channel to swap the value of the storage cell for a new one and
     // retrieve the value that was just replaced.
-    let swap = cell.bidir_channel::<i32, i32>();
+    let swap = cell.bidir_channel::<i32, i32>().unwrap();
 
     // New channel that will actually carry the value so that at no point
     // any given thread will have possession over it so concurrency issues
     // are avoided by design.
-    let val = cell.send_channel::<i32>();
+    let val = cell.send_channel::<i32>().unwrap();
 
     // Set up some clones of the above channels we can move over to the
     // thread in which the function body of the Join Pattern will run.
@@ -41,23 +41,29 @@ fn main() {
     // requested a value update and there is a value to be updated, send
     // a new val message through one of val's clones that carries the
     // updated value.
-    cell.when(&put).and(&val).then_do(move |new, _old| {
-        println!(">> put-val pattern fired with new={}!", new);
-        put_val.send(new).unwrap();
-    });
+    cell.when(&put)
+        .and(&val)
+        .then_do(move |new, _old| {
+            println!(">> put-val pattern fired with new={}!", new);
+            put_val.send(new).unwrap();
+        })
+        .unwrap();
 
     // Declare a new Join Pattern to retrieve the storage cell value. If
     // both the get and val channel have sent a message, meaning someone
     // requested the value and there is a value to be given, return that
     // value and resend it through one of val's clones so that the value
     // is still available in future and not just consumed once.
-    cell.when(&val).and_recv(&get).then_do(move |v| {
-        println!(">> val-get pattern fired with v={}!", v);
+    cell.when(&val)
+        .and_recv(&get)
+        .then_do(move |v| {
+            println!(">> val-get pattern fired with v={}!", v);
 
-        get_val.send(v.clone()).unwrap();
+            get_val.send(v).unwrap();
 
-        v
-    });
+            v
+        })
+        .unwrap();
 
     // Declare a new Join Pattern to swap the storage cell value with a
     // new one and retrieve the old. Essentially works like a combination
@@ -67,25 +73,31 @@ fn main() {
     // a multithreaded environment with many users accessing the storage
     // cell, the value retrieved is exactly the value that has been
     // updated.
-    cell.when(&val).and_bidir(&swap).then_do(move |old, new| {
-        println!(
-            ">> val-swap pattern fired with old={} and new={}!",
-            old, new
-        );
-        swap_val.send(new).unwrap();
-
-        old
-    });
+    cell.when(&val)
+        .and_bidir(&swap)
+        .then_do(move |old, new| {
+            println!(
+                ">> val-swap pattern fired with old={} and new={}!",
+                old, new
+            );
+            swap_val.send(new).unwrap();
+
+            old
+        })
+        .unwrap();
 
     // Declare a new Join Pattern that mentions the same channel multiple
     // times, so if the val channel has sent two messages they will be
     // combined into a single messages sent by a clone of val. This ensures
     // that eventually, the storage cell will only keep a single value
     // around.
-    cell.when(&val).and(&val).then_do(move |a, b| {
-        println!(">> val-val pattern fired with a={} and b={}!", a, b);
-        val_val.send(a + b).unwrap();
-    });
+    cell.when(&val)
+        .and(&val)
+        .then_do(move |a, b| {
+            println!(">> val-val pattern fired with a={} and b={}!", a, b);
+            val_val.send(a + b).unwrap();
+        })
+        .unwrap();
 
     /* End of the Join Pattern setup. */
 
@@ -1,4 +1,4 @@
-/// Simple toy example to demonstrate the basic API of the library.
+//! Simple toy example to demonstrate the basic API of the library.
 
 // The only struct that needs to be brought into score is the Junction itself.
 use rusty_junctions::Junction;
@@ -8,13 +8,16 @@ fn main() {
     let j = Junction::new();
 
     // Create new channels on the Junction j.
-    let name = j.send_channel::<String>();
-    let value = j.send_channel::<i32>();
+    let name = j.send_channel::<String>().unwrap();
+    let value = j.send_channel::<i32>().unwrap();
 
     // Declare a new Join Pattern on the Junction using the channels above.
-    j.when(&name).and(&value).then_do(|n, v| {
-        println!("{} {}", n, v);
-    });
+    j.when(&name)
+        .and(&value)
+        .then_do(|n, v| {
+            println!("{} {}", n, v);
+        })
+        .unwrap();
 
     // Send all the required messages for the Join Pattern above to fire.
     value.send(1729).unwrap();
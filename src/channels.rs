@@ -5,11 +5,181 @@
 //! a `RecvChannel` is used to get the value generated by a Join Pattern firing
 //! asynchronously.
 
+use std::any::Any;
+use std::future::Future;
 use std::marker::PhantomData;
-use std::sync::mpsc::{channel, RecvError, SendError, Sender};
-use std::{any::Any, marker::Send};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use super::error::JunctionError;
+use super::queue::QueueSender;
+use super::reply::{reply_channel, ReplyReceiver, ReplySender};
+use super::store::{QueueRef, SharedQueue, TypedQueue};
+use super::types::{ids, Packet};
+
+/// Send the request to register the given queue of a new channel to the
+/// Junction and return the typed handle to the queue.
+///
+/// Should the request fail, the queue is closed, so that nothing can be sent
+/// on the channel.
+fn register<T, Q>(id: ids::ChannelId, sender: &QueueSender<Packet>, queue: Q) -> QueueRef<T>
+where
+    T: Any,
+    Q: SharedQueue<Value = T>,
+{
+    let queue = QueueRef::new(id, queue);
+
+    let _ = sender.send(Packet::AddChannelRequest {
+        channel_id: id,
+        queue: queue.owned(),
+    });
+
+    queue
+}
+
+/// Push the given value onto the queue of a channel and notify the Junction
+/// that it is waiting there.
+///
+/// Return the position the value is given in the queue of the channel.
+///
+/// # Errors
+///
+/// Returns `JunctionError::JunctionClosed` if the Junction has shut down, in
+/// which case the value is dropped.
+fn push<T>(
+    queue: &QueueRef<T>,
+    sender: &QueueSender<Packet>,
+    value: T,
+) -> Result<usize, JunctionError>
+where
+    T: Any,
+{
+    let position = queue
+        .push(value)
+        .map_err(|_| JunctionError::JunctionClosed)?;
+
+    sender
+        .send(Packet::Message {
+            channel_id: queue.channel_id(),
+        })
+        .map_err(|_| JunctionError::JunctionClosed)?;
+
+    Ok(position)
+}
+
+/// Receiving end of the reply to a request sent on a channel.
+///
+/// Should it be dropped while the request still waits for a Join Pattern to
+/// consume it, for instance along with a cancelled `ReplyFuture` or during
+/// the unwinding of a thread blocked on the reply, the request is withdrawn.
+/// The Junction then drops the request right away, instead of keeping it
+/// around until a Join Pattern would consume it.
+struct PendingReply<R> {
+    channel_id: ids::ChannelId,
+    /// Position of the request in the queue of its channel.
+    position: usize,
+    sender: QueueSender<Packet>,
+    receiver: ReplyReceiver<R>,
+}
+
+impl<R> PendingReply<R> {
+    /// Block the current thread until the reply has been sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `ReplyReceiver::recv`.
+    fn recv(&self) -> Result<R, JunctionError> {
+        self.receiver.recv()
+    }
+
+    /// Wait for the reply until the given deadline, giving up on it should
+    /// the deadline pass first.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `give_up` with `JunctionError::Timeout` if
+    /// the deadline passed, and the same errors as `ReplyReceiver::recv`
+    /// otherwise.
+    fn recv_deadline(&self, deadline: Instant) -> Result<R, JunctionError> {
+        match self.receiver.recv_deadline(deadline) {
+            Err(JunctionError::Timeout) => self.give_up(JunctionError::Timeout),
+            reply => reply,
+        }
+    }
+
+    /// Return the reply if it has been sent already, giving up on it
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `give_up` with `JunctionError::WouldBlock`
+    /// if there was no reply yet, and the same errors as `ReplyReceiver::recv`
+    /// otherwise.
+    fn try_recv(&self) -> Result<R, JunctionError> {
+        match self.receiver.try_recv() {
+            Some(reply) => reply,
+            None => self.give_up(JunctionError::WouldBlock),
+        }
+    }
+
+    /// Stop waiting for the reply, withdrawing the request.
+    ///
+    /// Returns right away if the request could be withdrawn, leaving it to
+    /// the Junction to drop it once it handles the withdrawal. Should a Join
+    /// Pattern have claimed the request already, its reply is on its way and
+    /// is waited for up to `REPLY_GRACE_PERIOD`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the given error if the request was withdrawn or the reply did
+    /// not arrive within `REPLY_GRACE_PERIOD`, and the same errors as
+    /// `ReplyReceiver::recv` otherwise.
+    fn give_up(&self, error: JunctionError) -> Result<R, JunctionError> {
+        if self.receiver.withdraw() {
+            self.notify_withdrawal();
+
+            return Err(error);
+        }
+
+        match deadline_after(REPLY_GRACE_PERIOD)
+            .map(|deadline| self.receiver.recv_deadline(deadline))
+        {
+            Some(Err(JunctionError::Timeout)) | None => Err(error),
+            Some(reply) => reply,
+        }
+    }
+
+    /// Tell the Junction to drop the request, now that it has been withdrawn
+    /// or abandoned.
+    fn notify_withdrawal(&self) {
+        // Should the Junction have shut down, the request was dropped with it.
+        let _ = self.sender.send(Packet::WithdrawRequest {
+            channel_id: self.channel_id,
+            position: self.position,
+        });
+    }
+}
 
-use super::types::{ids, Message, Packet};
+impl<R> Drop for PendingReply<R> {
+    fn drop(&mut self) {
+        if self.receiver.is_pending() {
+            self.receiver.abandon();
+            self.notify_withdrawal();
+        }
+    }
+}
+
+/// Time to wait for the reply to a request that a Join Pattern claimed just
+/// as its sender stopped waiting for it.
+const REPLY_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Return the point in time the given timeout elapses, `None` if it lies too
+/// far in the future to be represented.
+fn deadline_after(timeout: Duration) -> Option<Instant> {
+    Instant::now().checked_add(timeout)
+}
 
 /***************************
  * Sending Channel Structs *
@@ -27,8 +197,10 @@ use super::types::{ids, Message, Packet};
 pub struct SendChannel<T> {
     id: ids::ChannelId,
     junction_id: ids::JunctionId,
-    sender: Sender<Packet>,
-    send_type: PhantomData<T>,
+    sender: QueueSender<Packet>,
+    queue: QueueRef<T>,
+    /// Make the channel `Send` and `Sync` only if its values are `Send`.
+    value_type: PhantomData<Mutex<T>>,
 }
 
 impl<T> SendChannel<T> {
@@ -44,32 +216,47 @@ impl<T> SendChannel<T> {
 
     /// Create a stripped down representation of this channel.
     pub(crate) fn strip(&self) -> StrippedSendChannel<T> {
-        StrippedSendChannel::new(self.id)
+        StrippedSendChannel::new(self.queue.clone())
+    }
+
+    /// Return the typed handle to the queue of the channel.
+    pub(crate) fn queue(&self) -> QueueRef<T> {
+        self.queue.clone()
     }
 }
 
 impl<T> SendChannel<T>
 where
-    T: Any + Send,
+    T: Any,
 {
-    pub(crate) fn new(
+    /// Create the channel with the given ID on the `Junction` with the given
+    /// ID, whose `Controller` shares the queue of the channel as returned by
+    /// `share`.
+    pub(crate) fn new<Q>(
         id: ids::ChannelId,
         junction_id: ids::JunctionId,
-        sender: Sender<Packet>,
-    ) -> SendChannel<T> {
+        sender: QueueSender<Packet>,
+        share: fn(TypedQueue<T>) -> Q,
+    ) -> SendChannel<T>
+    where
+        Q: SharedQueue<Value = T>,
+    {
         SendChannel {
             id,
             junction_id,
+            queue: register(id, &sender, share(TypedQueue::new())),
             sender,
-            send_type: PhantomData,
+            value_type: PhantomData,
         }
     }
 
-    pub fn send(&self, value: T) -> Result<(), SendError<Packet>> {
-        self.sender.send(Packet::Message {
-            channel_id: self.id,
-            msg: Message::new(value),
-        })
+    /// Send a message to the Junction.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::JunctionClosed` if the Junction has shut down.
+    pub fn send(&self, value: T) -> Result<(), JunctionError> {
+        push(&self.queue, &self.sender, value).map(|_| ())
     }
 }
 
@@ -82,23 +269,28 @@ where
 /// the actual `SendChannel`, however, it holds the bare minimum information
 /// necessary for the creation of Join Patterns. Specifically, this channel
 /// cannot send and does not know of its Junction, but is able to provide a
-/// channel ID and type associated with it.
+/// channel ID and the queue of its messages, with their type.
 pub(crate) struct StrippedSendChannel<T> {
-    id: ids::ChannelId,
-    send_type: PhantomData<T>,
+    queue: QueueRef<T>,
+    send_type: PhantomData<Mutex<T>>,
 }
 
 impl<T> StrippedSendChannel<T> {
-    pub(crate) fn new(id: ids::ChannelId) -> StrippedSendChannel<T> {
+    pub(crate) fn new(queue: QueueRef<T>) -> StrippedSendChannel<T> {
         StrippedSendChannel {
-            id,
+            queue,
             send_type: PhantomData,
         }
     }
 
     /// Return the channel's ID.
     pub(crate) fn id(&self) -> ids::ChannelId {
-        self.id
+        self.queue.channel_id()
+    }
+
+    /// Return the typed handle to the queue of the channel.
+    pub(crate) fn queue(&self) -> QueueRef<T> {
+        self.queue.clone()
     }
 }
 
@@ -118,8 +310,10 @@ impl<T> StrippedSendChannel<T> {
 pub struct RecvChannel<R> {
     id: ids::ChannelId,
     junction_id: ids::JunctionId,
-    sender: Sender<Packet>,
-    recv_type: PhantomData<R>,
+    sender: QueueSender<Packet>,
+    queue: QueueRef<ReplySender<R>>,
+    /// Make the channel `Send` and `Sync` only if its replies are `Send`.
+    reply_type: PhantomData<Mutex<R>>,
 }
 
 impl<R> RecvChannel<R> {
@@ -135,43 +329,136 @@ impl<R> RecvChannel<R> {
 
     /// Create a stripped down representation of this channel.
     pub(crate) fn strip(&self) -> StrippedRecvChannel<R> {
-        StrippedRecvChannel::new(self.id)
+        StrippedRecvChannel::new(self.queue.clone())
+    }
+
+    /// Return the typed handle to the queue of the channel.
+    pub(crate) fn queue(&self) -> QueueRef<ReplySender<R>> {
+        self.queue.clone()
     }
 }
 
 impl<R> RecvChannel<R>
 where
-    R: Any + Send,
+    R: Any,
 {
-    pub(crate) fn new(
+    /// Create the channel with the given ID on the `Junction` with the given
+    /// ID, whose `Controller` shares the queue of the channel as returned by
+    /// `share`.
+    pub(crate) fn new<Q>(
         id: ids::ChannelId,
         junction_id: ids::JunctionId,
-        sender: Sender<Packet>,
-    ) -> RecvChannel<R> {
+        sender: QueueSender<Packet>,
+        share: fn(TypedQueue<ReplySender<R>>) -> Q,
+    ) -> RecvChannel<R>
+    where
+        Q: SharedQueue<Value = ReplySender<R>>,
+    {
         RecvChannel {
             id,
             junction_id,
+            queue: register(
+                id,
+                &sender,
+                share(TypedQueue::for_requests(ReplySender::as_request)),
+            ),
             sender,
-            recv_type: PhantomData,
+            reply_type: PhantomData,
         }
     }
 
     /// Receive value generated by fired Join Pattern.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if it was not possible to send a return `Sender` to the Junction.
-    pub fn recv(&self) -> Result<R, RecvError> {
-        let (tx, rx) = channel::<R>();
+    /// Returns `JunctionError::JunctionClosed` if the Junction has shut down
+    /// and `JunctionError::ReplyDropped` if the Join Pattern consuming the
+    /// request did not reply. Returns `JunctionError::PatternPanicked` if the
+    /// function of that Join Pattern panicked instead.
+    pub fn recv(&self) -> Result<R, JunctionError> {
+        self.request()?.recv()
+    }
 
-        self.sender
-            .send(Packet::Message {
-                channel_id: self.id,
-                msg: Message::new(tx),
-            })
-            .unwrap();
+    /// Receive value generated by fired Join Pattern, waiting for at most the
+    /// given timeout.
+    ///
+    /// Works like `recv_deadline` with the deadline set to when the timeout
+    /// elapses.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `recv_deadline`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<R, JunctionError> {
+        match deadline_after(timeout) {
+            Some(deadline) => self.recv_deadline(deadline),
+            None => self.recv(),
+        }
+    }
+
+    /// Receive value generated by fired Join Pattern, waiting until at most
+    /// the given deadline.
+    ///
+    /// Should no Join Pattern have consumed the request by the deadline, the
+    /// request is withdrawn from the Junction, so that it cannot be consumed
+    /// later on along with messages on other channels. Withdrawing does not
+    /// wait for the Junction, which drops the request once it gets to it.
+    /// Only should a Join Pattern have claimed the request just before, its
+    /// value is waited for a little longer, and returned if it arrives.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::Timeout` if the request was withdrawn, and the
+    /// same errors as `recv` otherwise.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<R, JunctionError> {
+        self.request()?.recv_deadline(deadline)
+    }
+
+    /// Receive value generated by fired Join Pattern, if there is one right
+    /// away, without blocking.
+    ///
+    /// Works like `recv_deadline` with a deadline that has already passed:
+    /// unless a Join Pattern has claimed the request by the time it is
+    /// checked, it is withdrawn and the method returns at once, leaving it to
+    /// the Junction to drop the request. As the request is only sent by this
+    /// method, the Junction rarely gets to consume it in time, so this is
+    /// mostly useful to tell that the Junction is busy or no Join Pattern can
+    /// fire with the messages at hand.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::WouldBlock` if the request was withdrawn, and
+    /// the same errors as `recv` otherwise.
+    pub fn try_recv(&self) -> Result<R, JunctionError> {
+        self.request()?.try_recv()
+    }
 
-        rx.recv()
+    /// Asynchronously receive value generated by fired Join Pattern.
+    ///
+    /// Works like `recv`, except that instead of blocking the current thread,
+    /// it returns a `ReplyFuture` that resolves to the value once a Join
+    /// Pattern that this channel is part of has fired. The request is sent to
+    /// the Junction right away, not when the `ReplyFuture` is first polled.
+    ///
+    /// The `ReplyFuture` resolves to the same errors as `recv` returns.
+    pub fn recv_async(&self) -> ReplyFuture<R> {
+        ReplyFuture {
+            receiver: self.request(),
+        }
+    }
+
+    /// Send a request for a value to the Junction and return the receiving
+    /// end of the reply.
+    fn request(&self) -> Result<PendingReply<R>, JunctionError> {
+        let (tx, rx) = reply_channel::<R>();
+
+        let position = push(&self.queue, &self.sender, tx)?;
+
+        Ok(PendingReply {
+            channel_id: self.id,
+            position,
+            sender: self.sender.clone(),
+            receiver: rx,
+        })
     }
 }
 
@@ -184,23 +471,28 @@ where
 /// the actual `RecvChannel`, however, it holds the bare minimum information
 /// necessary for the creation of Join Patterns. Specifically, this channel
 /// cannot receive and does not know of its Junction, but is able to provide a
-/// channel ID and type associated with it.
+/// channel ID and the queue of its requests, with their type.
 pub(crate) struct StrippedRecvChannel<R> {
-    id: ids::ChannelId,
-    recv_type: PhantomData<R>,
+    queue: QueueRef<ReplySender<R>>,
+    recv_type: PhantomData<Mutex<R>>,
 }
 
 impl<R> StrippedRecvChannel<R> {
-    pub(crate) fn new(id: ids::ChannelId) -> StrippedRecvChannel<R> {
+    pub(crate) fn new(queue: QueueRef<ReplySender<R>>) -> StrippedRecvChannel<R> {
         StrippedRecvChannel {
-            id,
+            queue,
             recv_type: PhantomData,
         }
     }
 
     /// Return the channel's ID.
     pub(crate) fn id(&self) -> ids::ChannelId {
-        self.id
+        self.queue.channel_id()
+    }
+
+    /// Return the typed handle to the queue of the channel.
+    pub(crate) fn queue(&self) -> QueueRef<ReplySender<R>> {
+        self.queue.clone()
     }
 }
 
@@ -228,9 +520,11 @@ impl<R> StrippedRecvChannel<R> {
 pub struct BidirChannel<T, R> {
     id: ids::ChannelId,
     junction_id: ids::JunctionId,
-    sender: Sender<Packet>,
-    send_type: PhantomData<T>,
-    recv_type: PhantomData<R>,
+    sender: QueueSender<Packet>,
+    queue: QueueRef<(T, ReplySender<R>)>,
+    /// Make the channel `Send` and `Sync` only if its values and replies
+    /// are `Send`.
+    message_type: PhantomData<Mutex<(T, R)>>,
 }
 
 impl<T, R> BidirChannel<T, R> {
@@ -246,46 +540,134 @@ impl<T, R> BidirChannel<T, R> {
 
     /// Create a stripped down representation of this channel.
     pub(crate) fn strip(&self) -> StrippedBidirChannel<T, R> {
-        StrippedBidirChannel::new(self.id)
+        StrippedBidirChannel::new(self.queue.clone())
+    }
+
+    /// Return the typed handle to the queue of the channel.
+    pub(crate) fn queue(&self) -> QueueRef<(T, ReplySender<R>)> {
+        self.queue.clone()
     }
 }
 
 impl<T, R> BidirChannel<T, R>
 where
-    T: Any + Send,
-    R: Any + Send,
+    T: Any,
+    R: Any,
 {
-    pub(crate) fn new(
+    /// Create the channel with the given ID on the `Junction` with the given
+    /// ID, whose `Controller` shares the queue of the channel as returned by
+    /// `share`.
+    pub(crate) fn new<Q>(
         id: ids::ChannelId,
         junction_id: ids::JunctionId,
-        sender: Sender<Packet>,
-    ) -> BidirChannel<T, R> {
+        sender: QueueSender<Packet>,
+        share: fn(TypedQueue<(T, ReplySender<R>)>) -> Q,
+    ) -> BidirChannel<T, R>
+    where
+        Q: SharedQueue<Value = (T, ReplySender<R>)>,
+    {
         BidirChannel {
             id,
             junction_id,
+            queue: register(
+                id,
+                &sender,
+                share(TypedQueue::for_requests(|(_, return_sender)| {
+                    return_sender.as_request()
+                })),
+            ),
             sender,
-            send_type: PhantomData,
-            recv_type: PhantomData,
+            message_type: PhantomData,
         }
     }
 
     /// Send a message and receive value generated by fired Junction.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if it was not possible to send the given message and return
-    /// `Sender` to the Junction.
-    pub fn send_recv(&self, msg: T) -> Result<R, RecvError> {
-        let (tx, rx) = channel::<R>();
+    /// Returns `JunctionError::JunctionClosed` if the Junction has shut down
+    /// and `JunctionError::ReplyDropped` if the Join Pattern consuming the
+    /// message did not reply. Returns `JunctionError::PatternPanicked` if the
+    /// function of that Join Pattern panicked instead.
+    pub fn send_recv(&self, msg: T) -> Result<R, JunctionError> {
+        self.request(msg)?.recv()
+    }
 
-        self.sender
-            .send(Packet::Message {
-                channel_id: self.id,
-                msg: Message::new((msg, tx)),
-            })
-            .unwrap();
+    /// Send a message and receive value generated by fired Junction, waiting
+    /// for at most the given timeout.
+    ///
+    /// Works like `send_recv_deadline` with the deadline set to when the
+    /// timeout elapses.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `send_recv_deadline`.
+    pub fn send_recv_timeout(&self, msg: T, timeout: Duration) -> Result<R, JunctionError> {
+        match deadline_after(timeout) {
+            Some(deadline) => self.send_recv_deadline(msg, deadline),
+            None => self.send_recv(msg),
+        }
+    }
 
-        rx.recv()
+    /// Send a message and receive value generated by fired Junction, waiting
+    /// until at most the given deadline.
+    ///
+    /// Should no Join Pattern have consumed the message by the deadline, it
+    /// is withdrawn from the Junction and dropped, as described for
+    /// `RecvChannel::recv_deadline`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::Timeout` if the message was withdrawn, and the
+    /// same errors as `send_recv` otherwise.
+    pub fn send_recv_deadline(&self, msg: T, deadline: Instant) -> Result<R, JunctionError> {
+        self.request(msg)?.recv_deadline(deadline)
+    }
+
+    /// Send a message and receive value generated by fired Join Pattern, if
+    /// there is one right away, without blocking.
+    ///
+    /// Works like `RecvChannel::try_recv`, with the message withdrawn and
+    /// dropped unless a Join Pattern has claimed it by the time it is
+    /// checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::WouldBlock` if the message was withdrawn, and
+    /// the same errors as `send_recv` otherwise.
+    pub fn try_send_recv(&self, msg: T) -> Result<R, JunctionError> {
+        self.request(msg)?.try_recv()
+    }
+
+    /// Asynchronously send a message and receive value generated by fired
+    /// Junction.
+    ///
+    /// Works like `send_recv`, except that instead of blocking the current
+    /// thread, it returns a `ReplyFuture` that resolves to the value once a
+    /// Join Pattern that this channel is part of has fired. The message is
+    /// sent to the Junction right away, not when the `ReplyFuture` is first
+    /// polled.
+    ///
+    /// The `ReplyFuture` resolves to the same errors as `send_recv` returns.
+    pub fn send_recv_async(&self, msg: T) -> ReplyFuture<R> {
+        ReplyFuture {
+            receiver: self.request(msg),
+        }
+    }
+
+    /// Send the given message alongside a request for a value to the Junction
+    /// and return the receiving end of the reply.
+    fn request(&self, msg: T) -> Result<PendingReply<R>, JunctionError> {
+        let (tx, rx) = reply_channel::<R>();
+
+        let position = push(&self.queue, &self.sender, (msg, tx))?;
+
+        Ok(PendingReply {
+            channel_id: self.id,
+            position,
+            sender: self.sender.clone(),
+            receiver: rx,
+        })
     }
 }
 
@@ -298,17 +680,17 @@ where
 /// the actual `BidirChannel`, however, it holds the bare minimum information
 /// necessary for the creation of Join Patterns. Specifically, this channel
 /// cannot send or receive and does not know of its Junction, but is able to
-/// provide a channel ID and type associated with it.
+/// provide a channel ID and the queue of its messages, with their type.
 pub(crate) struct StrippedBidirChannel<T, R> {
-    id: ids::ChannelId,
-    send_type: PhantomData<T>,
-    recv_type: PhantomData<R>,
+    queue: QueueRef<(T, ReplySender<R>)>,
+    send_type: PhantomData<Mutex<T>>,
+    recv_type: PhantomData<Mutex<R>>,
 }
 
 impl<T, R> StrippedBidirChannel<T, R> {
-    pub(crate) fn new(id: ids::ChannelId) -> StrippedBidirChannel<T, R> {
+    pub(crate) fn new(queue: QueueRef<(T, ReplySender<R>)>) -> StrippedBidirChannel<T, R> {
         StrippedBidirChannel {
-            id,
+            queue,
             send_type: PhantomData,
             recv_type: PhantomData,
         }
@@ -316,6 +698,42 @@ impl<T, R> StrippedBidirChannel<T, R> {
 
     /// Return the channel's ID.
     pub(crate) fn id(&self) -> ids::ChannelId {
-        self.id
+        self.queue.channel_id()
+    }
+
+    /// Return the typed handle to the queue of the channel.
+    pub(crate) fn queue(&self) -> QueueRef<(T, ReplySender<R>)> {
+        self.queue.clone()
+    }
+}
+
+/*****************
+ * Reply Futures *
+ *****************/
+
+/// `Future` resolving to the value generated by a fired Join Pattern.
+///
+/// Returned by `RecvChannel::recv_async` and `BidirChannel::send_recv_async`.
+/// The `Future` does not depend on any particular async runtime: the task
+/// polling it is woken as soon as the Join Pattern has replied, without
+/// blocking any thread in the meantime. It resolves to an error if the Join
+/// Pattern did not reply, including `JunctionError::PatternPanicked` if its
+/// function panicked, or if the request could not be sent in the first place.
+///
+/// Dropping the `Future` before a Join Pattern has consumed the request
+/// withdraws the request, so that it no longer holds up Join Patterns that
+/// require its channel to be empty.
+pub struct ReplyFuture<R> {
+    receiver: Result<PendingReply<R>, JunctionError>,
+}
+
+impl<R> Future for ReplyFuture<R> {
+    type Output = Result<R, JunctionError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.get_mut().receiver {
+            Ok(reply) => Pin::new(&mut reply.receiver).poll(cx),
+            Err(error) => Poll::Ready(Err(*error)),
+        }
     }
 }
@@ -1,15 +1,21 @@
 //! Control structure started by any new `Junction`, running in a background thread
 //! to handle the coordination of Join Pattern creation and execution.
 
-use std::sync::mpsc::{Receiver, Sender};
+use std::collections::{HashMap, HashSet};
 use std::thread;
-use std::{cmp::Ordering, collections::HashMap, collections::LinkedList, vec::Vec};
+use std::vec::Vec;
 
-use super::bag::Bag;
-use super::counter::Counter;
+use super::executor::{Executor, Spawner};
 use super::inverted_index::InvertedIndex;
+use super::key_index::KeyIndex;
+use super::patterns::JoinPattern;
+use super::queue::{QueueReceiver, QueueSender};
+use super::readiness::ReadinessIndex;
+use super::reply::RequestStatus;
+use super::selection::{LeastRecentlyFired, SelectionPolicy};
+use super::store::{MessageStore, OwnedQueue, Retrieval};
 use super::types::ids::{ChannelId, JoinPatternId};
-use super::types::{ControllerHandle, JoinPattern, Message, Packet};
+use super::types::{functions, ControllerHandle, Packet};
 
 /// Struct to handle `Packet`s sent from the user in the background.
 ///
@@ -18,36 +24,54 @@ use super::types::{ControllerHandle, JoinPattern, Message, Packet};
 /// `Junction` in a separate control thread, where it continuously listens
 /// for `Packet`s sent by user code and reacts accordingly.
 pub(crate) struct Controller {
-    latest_channel_id: ChannelId,
-    latest_join_pattern_id: JoinPatternId,
-    /// Counter for how many messages have arrived since creation.
-    message_counter: Counter,
-    /// Collection of all currently available messages.
-    messages: Bag<ChannelId, Message>,
+    /// Typed queues of all channels, holding the currently available messages.
+    store: MessageStore,
     /// Collection of all available Join Patterns for the `Junction` associated with
     /// this `Controller`.
     join_patterns: HashMap<JoinPatternId, JoinPattern>,
-    /// Map of `JoinPatternId`s to the message count at which they were last
-    /// fired, `None` if the Join Pattern has never been fired. Used to
-    /// determine precedence of Join Patterns that have not been fired in a
-    /// while when needing to choose which of the alive Join Patterns to fire.
-    join_pattern_last_fired: HashMap<JoinPatternId, Option<Counter>>,
-    /// `InvertedIndex` matching `ChannelId`s to all Join Patterns they appear in.
-    /// Used to easily determine which Join Patterns are relevant any time a new
-    /// message comes in.
-    join_pattern_index: InvertedIndex<ChannelId, JoinPatternId>,
+    /// `InvertedIndex` matching `ChannelId`s to all keyed Join Patterns they
+    /// appear in. Used to keep the `KeyIndex` of these Join Patterns up to date
+    /// any time a message comes in or is consumed.
+    keyed_join_pattern_index: InvertedIndex<ChannelId, JoinPatternId>,
+    /// `ReadinessIndex` tracking which Join Patterns have enough messages
+    /// available on their channels to fire. Updated incrementally, so that
+    /// only the Join Patterns that are ready need to be considered any time
+    /// a message comes in or the last message of a channel is consumed.
+    readiness_index: ReadinessIndex<ChannelId, JoinPatternId>,
+    /// `JoinPatternId`s of Join Patterns with serialized firings whose function
+    /// is currently running. Such Join Patterns are not considered alive until
+    /// their firing has completed.
+    running_join_patterns: HashSet<JoinPatternId>,
+    /// Map of `JoinPatternId`s of keyed Join Patterns to the `KeyIndex` of the
+    /// positions of the messages available for each of their channels. Used
+    /// to find messages with matching keys without scanning all available
+    /// messages.
+    key_indices: HashMap<JoinPatternId, KeyIndex<usize>>,
+    /// Default `Executor` to run the functions of fired Join Patterns.
+    executor: Box<dyn Executor>,
+    /// `SelectionPolicy` choosing which of the alive Join Patterns to fire.
+    selection_policy: Box<dyn SelectionPolicy>,
+    /// Function to report panicking function bodies of fired Join Patterns
+    /// to, if any.
+    panic_hook: Option<functions::PanicHook>,
+    /// Whether the set of channels and Join Patterns has been fixed, in which
+    /// case no further Join Patterns are added.
+    sealed: bool,
 }
 
 impl Controller {
-    pub(crate) fn new() -> Controller {
+    pub(crate) fn new(executor: Box<dyn Executor>) -> Controller {
         Controller {
-            latest_channel_id: ChannelId::default(),
-            latest_join_pattern_id: JoinPatternId::default(),
-            message_counter: Counter::default(),
-            messages: Bag::new(),
+            store: MessageStore::new(),
             join_patterns: HashMap::new(),
-            join_pattern_last_fired: HashMap::new(),
-            join_pattern_index: InvertedIndex::new(),
+            keyed_join_pattern_index: InvertedIndex::new(),
+            readiness_index: ReadinessIndex::new(),
+            key_indices: HashMap::new(),
+            running_join_patterns: HashSet::new(),
+            executor,
+            selection_policy: Box::new(LeastRecentlyFired::new()),
+            panic_hook: None,
+            sealed: false,
         }
     }
 
@@ -59,456 +83,990 @@ impl Controller {
     /// point.
     pub(crate) fn start(
         mut self,
-        sender: Sender<Packet>,
-        receiver: Receiver<Packet>,
+        sender: QueueSender<Packet>,
+        receiver: QueueReceiver<Packet>,
     ) -> ControllerHandle {
         ControllerHandle::new(sender, thread::spawn(move || self.handle_packets(receiver)))
     }
 
+    /// Start task to handle incoming `Packet`s from `Junction` user.
+    ///
+    /// Works like `start`, except that the `Packet`s are handled by a task
+    /// spawned with the given `Spawner` rather than in a new thread. The task
+    /// waits for new `Packet`s without blocking the thread it is polled on.
+    pub(crate) fn start_task<S>(
+        mut self,
+        sender: QueueSender<Packet>,
+        receiver: QueueReceiver<Packet>,
+        spawner: &S,
+    ) -> ControllerHandle
+    where
+        S: Spawner + ?Sized,
+    {
+        spawner.spawn(Box::pin(async move {
+            while let Ok(packet) = receiver.recv_async().await {
+                if !self.handle_packet(packet) {
+                    break;
+                }
+            }
+        }));
+
+        ControllerHandle::new_task(sender)
+    }
+
     /// Handle incoming `Packet` from associated `Junction`.
     ///
     /// This function will continuously receive `Packet`s sent from structs
     /// associated with the `Junction` that created and started this `Controller`
     /// until a `Packet::ShutDownRequest` has been sent.
-    fn handle_packets(&mut self, receiver: Receiver<Packet>) {
+    fn handle_packets(&mut self, receiver: QueueReceiver<Packet>) {
+        while let Ok(packet) = receiver.recv() {
+            if !self.handle_packet(packet) {
+                break;
+            }
+        }
+    }
+
+    /// Handle a single `Packet`, returning `false` if it was a
+    /// `Packet::ShutDownRequest` and no further `Packet`s should be handled.
+    pub(crate) fn handle_packet(&mut self, packet: Packet) -> bool {
         use Packet::*;
 
-        while let Ok(packet) = receiver.recv() {
-            match packet {
-                Message { channel_id, msg } => self.handle_message(channel_id, msg),
-                NewChannelIdRequest { return_sender } => {
-                    self.handle_new_channel_id_request(return_sender)
-                }
-                AddJoinPatternRequest { join_pattern } => {
-                    self.handle_add_join_pattern_request(join_pattern)
-                }
-                ShutDownRequest => break,
+        match packet {
+            Message { channel_id } => self.handle_message(channel_id),
+            AddChannelRequest { channel_id, queue } => {
+                self.handle_add_channel_request(channel_id, queue)
+            }
+            WithdrawRequest {
+                channel_id,
+                position,
+            } => self.handle_withdraw_request(channel_id, position),
+            AddJoinPatternRequest {
+                join_pattern_id,
+                join_pattern,
+            } => self.handle_add_join_pattern_request(join_pattern_id, *join_pattern),
+            RemoveJoinPatternRequest { join_pattern_id } => {
+                self.handle_remove_join_pattern_request(join_pattern_id)
             }
+            FiringCompleted { join_pattern_id } => self.handle_firing_completed(join_pattern_id),
+            SetSelectionPolicy { policy } => self.set_selection_policy(policy),
+            SetPanicHook { hook } => self.set_panic_hook(hook),
+            SealRequest => self.seal(),
+            ShutDownRequest => return false,
         }
+
+        true
     }
 
-    /// Handle a received `Message` from a given channel.
+    /// Handle a message received on a given channel.
     ///
-    /// The first action taken in handling a `Message` is storing the received
-    /// message in the `Message` bag of the `Controller`.
+    /// The first action taken in handling a message is moving it from the
+    /// `Inbox` of its channel into the `MessageStore` of the `Controller`.
     ///
     /// The second action is to start determining if any of the Join Patterns stored
     /// with the `Controller` are alive and if so, which of these to fire.
-    fn handle_message(&mut self, channel_id: ChannelId, msg: Message) {
-        self.messages.add(channel_id, msg);
-        self.message_counter.increment();
+    fn handle_message(&mut self, channel_id: ChannelId) {
+        let position = match self.store.receive(&channel_id) {
+            Some(position) => position,
+            None => return,
+        };
+
+        self.index_message(channel_id, position);
+        self.readiness_index
+            .increased(&channel_id, self.store.count(&channel_id));
+
+        self.handle_join_pattern_firing(channel_id, position);
+    }
+
+    /// Register the queue holding the messages of a new channel.
+    fn handle_add_channel_request(&mut self, channel_id: ChannelId, queue: OwnedQueue) {
+        self.store.register(channel_id, queue);
+    }
+
+    /// Drop the request at the given position on the given channel, whose
+    /// withdrawal has been requested, or whose caller stopped waiting for
+    /// the reply.
+    ///
+    /// A request that a Join Pattern has claimed before is no longer stored,
+    /// so its caller still receives the reply. A request that has not been
+    /// moved into the queue of its channel yet is dropped once it is. Should the
+    /// channel be empty afterwards, Join Patterns requiring it to be empty
+    /// may have become alive.
+    fn handle_withdraw_request(&mut self, channel_id: ChannelId, position: usize) {
+        if self.store.request_status(&channel_id, position) == RequestStatus::Pending {
+            return;
+        }
 
-        self.handle_join_pattern_firing(channel_id);
+        self.drop_request(channel_id, position);
+
+        self.handle_negated_join_pattern_firing(&[channel_id]);
+    }
+
+    /// Drop the request at the given position on the given channel.
+    fn drop_request(&mut self, channel_id: ChannelId, position: usize) {
+        self.unindex_message(channel_id, position);
+        self.store.remove(&channel_id, position);
+        self.readiness_index
+            .decreased(&channel_id, self.store.count(&channel_id));
     }
 
-    /// Handle the firing of a `JoinPattern`, if possible.
+    /// Claim the requests among the messages that would be consumed by
+    /// firing the given `JoinPattern`, so that their callers can no longer
+    /// withdraw them.
     ///
-    /// Determine which `JoinPattern`s contain the channel with the given
-    /// `ChannelId`. For these, check which ones have at least one `Message`
-    /// available for each of their channels, i.e. are alive, then select
-    /// one `JoinPattern` to be fired. If at any point during this process
-    /// no more `JoinPattern`s remain, nothing will be done.
-    fn handle_join_pattern_firing(&mut self, channel_id: ChannelId) {
-        let mut alive_join_patterns: Vec<JoinPatternId> = Vec::new();
+    /// A request that has been withdrawn or abandoned in the meantime cannot
+    /// be claimed. No one waits for its reply, so consuming it would waste
+    /// the messages consumed along with it. It is dropped instead, the
+    /// requests claimed before are released again and `false` is returned,
+    /// as a new match needs to be found afterwards.
+    fn claim_requests(&mut self, join_pattern_id: JoinPatternId, positions: &[usize]) -> bool {
+        let channel_ids = self.join_patterns[&join_pattern_id].channel_ids().to_vec();
+
+        for (i, (&ch_id, &position)) in channel_ids.iter().zip(positions).enumerate() {
+            if self.store.claim(&ch_id, position) {
+                continue;
+            }
+
+            for (claimed_ch_id, &claimed_position) in channel_ids[..i].iter().zip(positions) {
+                self.store.release(claimed_ch_id, claimed_position);
+            }
 
-        if let Some(jp_ids) = self.relevant_join_patterns(channel_id) {
-            alive_join_patterns = self.alive_join_patterns(jp_ids);
+            self.drop_request(ch_id, position);
+            self.handle_negated_join_pattern_firing(&[ch_id]);
+
+            return false;
         }
 
-        if let Some(jp_id_to_fire) = self.select_to_fire(&mut alive_join_patterns) {
-            self.fire_join_pattern(*jp_id_to_fire);
-            self.reset_last_fired(*jp_id_to_fire);
+        true
+    }
+
+    /// Handle the firing of `JoinPattern`s, if possible.
+    ///
+    /// Determine which ready `JoinPattern`s contain the channel with the
+    /// given `ChannelId` and keep firing those of them that are alive until
+    /// none are left. If at any point during this process no more
+    /// `JoinPattern`s remain, nothing will be done.
+    ///
+    /// None of these `JoinPattern`s were alive before the `Message` at the
+    /// given position arrived on the channel, so only the combinations of
+    /// `Message`s including it need to be searched.
+    fn handle_join_pattern_firing(&mut self, channel_id: ChannelId, position: usize) {
+        let jp_ids = self.readiness_index.ready_requiring(&channel_id);
+
+        self.fire_alive_join_patterns(&jp_ids, Some((channel_id, position)));
+    }
+
+    /// Keep firing the given `JoinPattern`s until none of them are alive.
+    ///
+    /// Among the `JoinPattern`s that are alive at the same time, the one to
+    /// be fired next is selected anew by the `SelectionPolicy` after every
+    /// firing. Once this function returns, none of the given `JoinPattern`s
+    /// are alive, no matter how many of their `Message`s had accumulated.
+    ///
+    /// Should the messages matched for the selected `JoinPattern` include a
+    /// request that cannot be claimed, as it has been withdrawn or abandoned,
+    /// that request is dropped and the selection starts over instead of
+    /// firing it.
+    ///
+    /// If the `JoinPattern`s can only have become alive through the arrival
+    /// of a `Message`, given by its `ChannelId` and position, only matches
+    /// including it are searched for. Firing only consumes `Message`s, so
+    /// this remains true until none of them are alive.
+    fn fire_alive_join_patterns(
+        &mut self,
+        join_pattern_ids: &[JoinPatternId],
+        arrived: Option<(ChannelId, usize)>,
+    ) {
+        loop {
+            let (alive_jp_ids, mut matches): (Vec<JoinPatternId>, Vec<Vec<usize>>) = self
+                .alive_join_patterns(join_pattern_ids, arrived)
+                .into_iter()
+                .unzip();
+
+            match self.select_to_fire(&alive_jp_ids) {
+                Some(jp_id_to_fire) => {
+                    let i = alive_jp_ids.iter().position(|&id| id == jp_id_to_fire);
+                    let positions = matches.swap_remove(i.unwrap());
+
+                    if self.claim_requests(jp_id_to_fire, &positions) {
+                        self.handle_selected_join_pattern_firing(jp_id_to_fire, positions);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Handle the firing of the selected `JoinPattern` and its consequences.
+    ///
+    /// Fire the `JoinPattern` with the given `JoinPatternId` on the messages
+    /// at the given positions, remove it should it have reached its limit of
+    /// firings and handle the firing of those `JoinPattern`s that may have
+    /// become alive because it emptied some of its channels.
+    fn handle_selected_join_pattern_firing(
+        &mut self,
+        join_pattern_id: JoinPatternId,
+        positions: Vec<usize>,
+    ) {
+        let channel_ids = self.join_patterns[&join_pattern_id].channel_ids().to_vec();
+
+        self.fire_join_pattern(join_pattern_id, positions);
+        self.selection_policy.fired(join_pattern_id);
+
+        if self.join_patterns[&join_pattern_id].is_exhausted() {
+            self.handle_remove_join_pattern_request(join_pattern_id);
         }
+
+        self.handle_negated_join_pattern_firing(&channel_ids);
     }
 
-    /// Return the `JoinPatternId`s of relevant Join Patterns for given `ChannelId`.
+    /// Handle the firing of Join Patterns that require now empty channels to
+    /// hold no messages, if possible.
     ///
-    /// A Join Pattern is considered relevant for a given `ChannelId` if at least
-    /// one of its channels has the `ChannelId`.
-    fn relevant_join_patterns(&self, channel_id: ChannelId) -> Option<&LinkedList<JoinPatternId>> {
-        self.join_pattern_index.peek_all(&channel_id)
+    /// Firing a Join Pattern may have consumed the last `Message`s of some of
+    /// the channels with the given `ChannelId`s. Join Patterns that require
+    /// one of these channels to hold no messages may have become alive as a
+    /// result, so keep firing them for each such channel until none are left.
+    fn handle_negated_join_pattern_firing(&mut self, channel_ids: &[ChannelId]) {
+        for (i, ch_id) in channel_ids.iter().enumerate() {
+            if channel_ids[..i].contains(ch_id) || self.store.count(ch_id) > 0 {
+                continue;
+            }
+
+            let jp_ids = self.readiness_index.ready_negating(ch_id);
+
+            self.fire_alive_join_patterns(&jp_ids, None);
+        }
     }
 
-    /// Return the `JoinPatternId`s of all alive `JoinPattern`s.
+    /// Return the `JoinPatternId`s of all alive `JoinPattern`s among the given
+    /// ones, each with the positions of the `Message`s it would consume.
     ///
-    /// A `JoinPattern` is considered alive if for each of the channels
-    /// involved in it, there is at least one `Message` available.
+    /// Only `JoinPattern`s that are ready according to the `ReadinessIndex`
+    /// are checked in detail, since no other `JoinPattern` can be alive.
     fn alive_join_patterns(
         &self,
-        join_pattern_ids: &LinkedList<JoinPatternId>,
-    ) -> Vec<JoinPatternId> {
-        // We need clone the `JoinPatternId`s at this point to avoid
-        // because need to avoid the issue of `peek_all` borrowing mutably,
-        // but then needing to mutably borrow again later to update the
-        // latest fired `JoinPatternId`.
+        join_pattern_ids: &[JoinPatternId],
+        arrived: Option<(ChannelId, usize)>,
+    ) -> Vec<(JoinPatternId, Vec<usize>)> {
         join_pattern_ids
             .iter()
-            .filter(|&jp_id| self.is_alive(*jp_id))
-            .cloned()
+            .filter(|&jp_id| self.readiness_index.is_ready(jp_id))
+            .filter_map(|&jp_id| Some((jp_id, self.find_match(jp_id, arrived)?)))
             .collect()
     }
 
     /// Select which `JoinPattern` should be fired.
     ///
-    /// In order to avoid certain scenarious in which one `JoinPattern` would
-    /// block the execution of another, because it for instance has a subset of
-    /// the other's channels, we need to ensure that from the `JoinPattern`s
-    /// that are alive simultaneously, we select the one to be fired that has
-    /// been waiting for the longest time.
-    ///
-    /// Specifically, we record the `Counter` of the `Message` that each
-    /// `JoinPattern` has last been fired at. We use this as a pseudo-time and
-    /// simply order the `JoinPattern`s by their `Counter` values, then take
-    /// the one with the smallest.
-    ///
-    /// Note that this procedure should ensure a certain form of *fairness*,
-    /// by which if a `JoinPattern` has been alive an infinite amount of times,
-    /// it will fire at least once. In practice, this should amount to each
-    /// `JoinPattern` being incapable of getting deadlocked by others.
-    fn select_to_fire<'a>(
-        &self,
-        alive_jp_ids: &'a mut Vec<JoinPatternId>,
-    ) -> Option<&'a JoinPatternId> {
-        alive_jp_ids
-            .sort_unstable_by(|&jp_id_1, &jp_id_2| self.compare_last_fired(jp_id_1, jp_id_2));
+    /// Of the `JoinPattern`s that are alive simultaneously, only those with
+    /// the highest priority are considered. The choice among these is left to
+    /// the `SelectionPolicy` of the `Controller`. Return `None` if there is no
+    /// alive `JoinPattern` to choose from.
+    fn select_to_fire(&mut self, alive_jp_ids: &[JoinPatternId]) -> Option<JoinPatternId> {
+        let highest_priority = alive_jp_ids
+            .iter()
+            .map(|jp_id| self.join_patterns[jp_id].priority())
+            .max()?;
 
-        alive_jp_ids.first()
+        let candidates: Vec<JoinPatternId> = alive_jp_ids
+            .iter()
+            .filter(|&jp_id| self.join_patterns[jp_id].priority() == highest_priority)
+            .cloned()
+            .collect();
+
+        let selected = self.selection_policy.select(&candidates);
+
+        candidates
+            .get(selected)
+            .or_else(|| candidates.first())
+            .cloned()
     }
 
-    /// Return `true` if Join Pattern with given `JoinPatternId` is alive.
+    /// Find the `Message`s the given Join Pattern would consume if fired now.
     ///
-    /// A Join Pattern is considered alive if there is at least one `Message` for
-    /// each of the channels involved in it.
-    fn is_alive(&self, join_pattern_id: JoinPatternId) -> bool {
-        use JoinPattern::*;
+    /// Return `Some` of the position of a `Message` in the queue of its channel
+    /// for each of the channels in the Join Pattern, in order, or `None` if
+    /// the Join Pattern is not alive. The same channel may appear multiple
+    /// times in a Join Pattern, in which case each occurrence is matched with
+    /// a different `Message`.
+    ///
+    /// A Join Pattern is considered alive if it has not reached its limit of
+    /// firings, is not waiting for a serialized firing to complete, there is
+    /// at least one `Message` for each of the channels involved in it, there
+    /// is no `Message` for any of the channels it requires to be empty and,
+    /// should it have a guard, some combination of the available `Message`s
+    /// satisfies the guard.
+    ///
+    /// Without a guard, the Join Pattern simply takes the least recently sent
+    /// `Message`s of each channel. With a guard, the combinations of available
+    /// `Message`s are searched in FIFO order for the first one that satisfies
+    /// the guard, only considering those including the arrived `Message`, if
+    /// given. Keyed Join Patterns only search the combinations of `Message`s
+    /// that share a key, one key at a time, or only the keys of the arrived
+    /// `Message`.
+    fn find_match(
+        &self,
+        join_pattern_id: JoinPatternId,
+        arrived: Option<(ChannelId, usize)>,
+    ) -> Option<Vec<usize>> {
+        let join_pattern = self.join_patterns.get(&join_pattern_id)?;
+        let channel_ids = join_pattern.channel_ids();
+
+        if join_pattern.is_exhausted() || self.running_join_patterns.contains(&join_pattern_id) {
+            return None;
+        }
+
+        if join_pattern
+            .negated_channel_ids()
+            .iter()
+            .any(|ch_id| self.store.count(ch_id) > 0)
+        {
+            return None;
+        }
+
+        if let Some(key_index) = self.key_indices.get(&join_pattern_id) {
+            let guard = join_pattern.guard()?;
+            let search_key = |key| {
+                let candidates: Vec<Vec<usize>> = (0..channel_ids.len())
+                    .map(|i| {
+                        key_index
+                            .items(i, key)
+                            .into_iter()
+                            .flatten()
+                            .cloned()
+                            .collect()
+                    })
+                    .collect();
 
-        if let Some(join_pattern) = self.join_patterns.get(&join_pattern_id) {
-            match join_pattern {
-                UnarySend(jp) => self.is_unary_alive(jp.channel_id()),
-                UnaryRecv(jp) => self.is_unary_alive(jp.channel_id()),
-                UnaryBidir(jp) => self.is_unary_alive(jp.channel_id()),
-                BinarySend(jp) => {
-                    self.is_binary_alive(jp.first_send_channel_id(), jp.second_send_channel_id())
+                self.search_match(channel_ids, guard, &candidates, arrived)
+            };
+
+            return match arrived {
+                // The arrived `Message` may have been consumed already.
+                Some((arrived_ch_id, arrived_position))
+                    if !self.store.contains(&arrived_ch_id, arrived_position) =>
+                {
+                    None
                 }
-                BinaryRecv(jp) => self.is_binary_alive(jp.send_channel_id(), jp.recv_channel_id()),
-                BinaryBidir(jp) => {
-                    self.is_binary_alive(jp.send_channel_id(), jp.bidir_channel_id())
+                Some((arrived_ch_id, arrived_position)) => {
+                    let key = join_pattern.key().unwrap();
+                    let mut keys: Vec<u64> = Vec::new();
+
+                    for (i, &ch_id) in channel_ids.iter().enumerate() {
+                        if ch_id != arrived_ch_id {
+                            continue;
+                        }
+
+                        let arrived_key = key(i, arrived_position);
+
+                        if !keys.contains(&arrived_key) {
+                            keys.push(arrived_key);
+                        }
+                    }
+
+                    keys.into_iter().find_map(search_key)
                 }
-                TernarySend(jp) => self.is_ternary_alive(
-                    jp.first_send_channel_id(),
-                    jp.second_send_channel_id(),
-                    jp.third_send_channel_id(),
-                ),
-                TernaryRecv(jp) => self.is_ternary_alive(
-                    jp.first_send_channel_id(),
-                    jp.second_send_channel_id(),
-                    jp.recv_channel_id(),
-                ),
-                TernaryBidir(jp) => self.is_ternary_alive(
-                    jp.first_send_channel_id(),
-                    jp.second_send_channel_id(),
-                    jp.bidir_channel_id(),
-                ),
+                None => key_index.ready_keys().find_map(search_key),
+            };
+        }
+
+        match join_pattern.guard() {
+            None => {
+                let mut positions = Vec::with_capacity(channel_ids.len());
+
+                for (i, ch_id) in channel_ids.iter().enumerate() {
+                    let occurrence = channel_ids[..i].iter().filter(|&id| id == ch_id).count();
+
+                    positions.push(self.store.nth_position(ch_id, occurrence)?);
+                }
+
+                Some(positions)
+            }
+            Some(guard) => {
+                let candidates: Vec<Vec<usize>> = channel_ids
+                    .iter()
+                    .map(|ch_id| self.store.positions(ch_id))
+                    .collect();
+
+                self.search_match(channel_ids, guard, &candidates, arrived)
             }
-        } else {
-            false
         }
     }
 
-    /// Return `true` if *unary* Join Pattern with given `JoinPatternId` is alive.
+    /// Search for a combination of candidate `Message`s satisfying the guard.
     ///
-    /// A Join Pattern is considered alive if there is at least one `Message` for
-    /// each of the channels involved in it.
-    fn is_unary_alive(&self, channel_id: ChannelId) -> bool {
-        self.messages.count_items(&channel_id) >= 1
-    }
+    /// The candidates are given as positions of `Message`s in the queue of
+    /// their channel, in increasing order, for each channel of the Join
+    /// Pattern. Return `Some` of the first combination of positions in the
+    /// order of the candidates that satisfies the guard and includes the
+    /// arrived `Message`, if given, `None` if there is no such combination.
+    fn search_match(
+        &self,
+        channel_ids: &[ChannelId],
+        guard: &functions::GuardBox,
+        candidates: &[Vec<usize>],
+        arrived: Option<(ChannelId, usize)>,
+    ) -> Option<Vec<usize>> {
+        if candidates.iter().any(Vec::is_empty) {
+            return None;
+        }
 
-    /// Return `true` if *binary* Join Pattern with given `JoinPatternId` is alive.
-    ///
-    /// A Join Pattern is considered alive if there is at least one `Message` for
-    /// each of the channels involved in it.
-    ///
-    /// For binary Join Patterns, we need to ensure that should both channels
-    /// involved have the same `ChannelId`, we actually have at least two
-    /// `Message`s available.
-    fn is_binary_alive(&self, first_ch_id: ChannelId, second_ch_id: ChannelId) -> bool {
-        if first_ch_id == second_ch_id {
-            self.messages.count_items(&first_ch_id) >= 2
+        // The arrived `Message` may have been consumed already, or not carry
+        // the key searched for.
+        if let Some((arrived_ch_id, arrived_position)) = arrived {
+            let is_candidate = channel_ids
+                .iter()
+                .zip(candidates)
+                .any(|(&ch_id, positions)| {
+                    ch_id == arrived_ch_id && positions.binary_search(&arrived_position).is_ok()
+                });
+
+            if !is_candidate {
+                return None;
+            }
+        }
+
+        let mut positions = Vec::with_capacity(channel_ids.len());
+
+        if self.search_guarded_match(channel_ids, guard, candidates, arrived, &mut positions) {
+            Some(positions)
         } else {
-            self.is_unary_alive(first_ch_id) && self.is_unary_alive(second_ch_id)
+            None
         }
     }
 
-    /// Return `true` if *ternary* Join Pattern with given `JoinPatternId` is alive.
+    /// Extend a combination of candidate `Message`s satisfying the guard.
     ///
-    /// A Join Pattern is considered alive if there is at least one `Message` for
-    /// each of the channels involved in it.
+    /// Extend the given positions of `Message`s, one per channel already
+    /// matched, by trying every candidate `Message` for the next channel in
+    /// turn. Return `true` once positions for all channels have been found
+    /// that satisfy the guard, `false` if no such combination exists.
     ///
-    /// For ternary Join Patterns, we need to ensure that should more than one
-    /// channel involved have the same `ChannelId`, we actually have enough
-    /// `Message`s available.
-    fn is_ternary_alive(
+    /// Should the arrived `Message` not have been used for an earlier
+    /// occurrence of its channel, it is the only candidate for the last one,
+    /// so that only the combinations including it are tried.
+    fn search_guarded_match(
         &self,
-        first_ch_id: ChannelId,
-        second_ch_id: ChannelId,
-        third_ch_id: ChannelId,
+        channel_ids: &[ChannelId],
+        guard: &functions::GuardBox,
+        candidates: &[Vec<usize>],
+        arrived: Option<(ChannelId, usize)>,
+        positions: &mut Vec<usize>,
     ) -> bool {
-        if first_ch_id == second_ch_id && second_ch_id == third_ch_id {
-            self.messages.count_items(&first_ch_id) >= 3
-        } else {
-            self.is_binary_alive(first_ch_id, second_ch_id)
-                && self.is_binary_alive(first_ch_id, third_ch_id)
-                && self.is_binary_alive(second_ch_id, third_ch_id)
+        let i = positions.len();
+
+        if i == channel_ids.len() {
+            return guard(positions);
         }
-    }
 
-    /// Compare when the Join Patterns with given `JoinPatternId`s were last alive at.
-    ///
-    /// Rules for Order:
-    /// 1. If neither `JoinPatternId` has a last alive `Counter`, then neither
-    /// has been fired yet, so they can be viewed as equal in this ordering.
-    /// 2. If only one `JoinPatternId` has no last alive `Counter`, then that
-    /// one has to be ordered as less than the other since having been fired
-    /// at least once will always be a later point of firing than not having
-    /// been fired yet.
-    /// 3. If both `JoinPatternId`s have last alive `Counter`s, use the ordering
-    /// of these.
-    ///
-    /// # Panics
-    ///
-    /// For simplicity, this function panics if the either of the given
-    /// `JoinPatternId`s is not registered in the internal map of `JoinPatternId`s
-    /// to `Instant`s that describe the last `Instant` at which a particular Join
-    /// Pattern was alive. That is to say, this function should only be called on
-    /// `JoinPatternId`s which are definitely stored in the calling `Controller`.
-    fn compare_last_fired(&self, jp_id_1: JoinPatternId, jp_id_2: JoinPatternId) -> Ordering {
-        // TODO: Can we sensibly use `Option::flatten` here?
-        let last_fired_1 = self.join_pattern_last_fired.get(&jp_id_1).unwrap();
-        let last_fired_2 = self.join_pattern_last_fired.get(&jp_id_2).unwrap();
-
-        if last_fired_1.is_none() && last_fired_2.is_none() {
-            Ordering::Equal
-        } else if last_fired_1.is_none() && last_fired_2.is_some() {
-            Ordering::Less
-        } else if last_fired_1.is_some() && last_fired_2.is_none() {
-            Ordering::Greater
-        } else {
-            last_fired_1.cmp(last_fired_2)
+        let ch_id = channel_ids[i];
+        // Each `Message` may only be used once per match.
+        let is_used = |positions: &[usize], position| {
+            channel_ids[..i]
+                .iter()
+                .zip(positions)
+                .any(|(&id, &p)| id == ch_id && p == position)
+        };
+        let mut slot_candidates = candidates[i].as_slice();
+
+        if let Some((arrived_ch_id, arrived_position)) = arrived {
+            if arrived_ch_id == ch_id
+                && !channel_ids[i + 1..].contains(&ch_id)
+                && !is_used(positions, arrived_position)
+            {
+                slot_candidates = match slot_candidates.binary_search(&arrived_position) {
+                    Ok(j) => &slot_candidates[j..=j],
+                    Err(_) => &[],
+                };
+            }
         }
+
+        for &position in slot_candidates {
+            if !is_used(positions, position) {
+                positions.push(position);
+
+                if self.search_guarded_match(channel_ids, guard, candidates, arrived, positions) {
+                    return true;
+                }
+
+                positions.pop();
+            }
+        }
+
+        false
     }
 
     /// Fire the `JoinPattern` corresponding to the given `JoinPatternId`.
     ///
-    /// The processs of firing a `JoinPattern` consists of first retrieving
-    /// a `Message` for each of the channels involved in the `JoinPattern`,
-    /// then passing these `Messages`s to the `JoinPattern` to handle the
-    /// firing.
+    /// The processs of firing a `JoinPattern` consists of removing the
+    /// messages at the given positions, one for each of the channels
+    /// involved in the `JoinPattern`, from the indices of the `Controller`,
+    /// then letting the `JoinPattern` retrieve these messages from the
+    /// `MessageStore` to handle the firing.
     ///
     /// # Panics
     ///
     /// Panics when there is no `JoinPattern` stored for the given
     /// `JoinPatternId`.
-    fn fire_join_pattern(&mut self, join_pattern_id: JoinPatternId) {
-        use JoinPattern::*;
+    fn fire_join_pattern(&mut self, join_pattern_id: JoinPatternId, positions: Vec<usize>) {
+        let channel_ids = self.join_patterns[&join_pattern_id].channel_ids().to_vec();
 
-        let join_pattern = self.join_patterns.get(&join_pattern_id).unwrap();
+        for (i, (ch_id, &position)) in channel_ids.iter().zip(positions.iter()).enumerate() {
+            self.unindex_message(*ch_id, position);
 
-        match join_pattern {
-            UnarySend(jp) => {
-                let arg = self.messages.retrieve(&jp.channel_id()).unwrap();
+            // The channel may appear multiple times in the Join Pattern, in
+            // which case it is left with one message less for each of them.
+            let consumed = channel_ids[..=i].iter().filter(|&id| id == ch_id).count();
+            self.readiness_index
+                .decreased(ch_id, self.store.count(ch_id) - consumed);
+        }
 
-                jp.fire(arg);
-            }
-            UnaryRecv(jp) => {
-                let return_sender = self.messages.retrieve(&jp.channel_id()).unwrap();
+        let join_pattern = self.join_patterns.get_mut(&join_pattern_id).unwrap();
 
-                jp.fire(return_sender);
-            }
-            UnaryBidir(jp) => {
-                let arg_and_sender = self.messages.retrieve(&jp.channel_id()).unwrap();
+        join_pattern.fire(
+            join_pattern_id,
+            &mut Retrieval::new(join_pattern_id, &positions, self.panic_hook.as_ref()),
+            self.executor.as_ref(),
+        );
+        join_pattern.record_firing();
 
-                jp.fire(arg_and_sender);
-            }
-            BinarySend(jp) => {
-                let arg_1 = self.messages.retrieve(&jp.first_send_channel_id()).unwrap();
-                let arg_2 = self
-                    .messages
-                    .retrieve(&jp.second_send_channel_id())
-                    .unwrap();
-
-                jp.fire(arg_1, arg_2);
-            }
-            BinaryRecv(jp) => {
-                let arg = self.messages.retrieve(&jp.send_channel_id()).unwrap();
-                let return_sender = self.messages.retrieve(&jp.recv_channel_id()).unwrap();
+        if join_pattern.is_serialized() {
+            self.running_join_patterns.insert(join_pattern_id);
+        }
+    }
 
-                jp.fire(arg, return_sender);
-            }
-            BinaryBidir(jp) => {
-                let arg_1 = self.messages.retrieve(&jp.send_channel_id()).unwrap();
-                let arg_2_and_sender = self.messages.retrieve(&jp.bidir_channel_id()).unwrap();
+    /// Add the message at the given position of the channel with the given
+    /// `ChannelId` to the `KeyIndex` of every keyed Join Pattern that the
+    /// channel is in.
+    fn index_message(&mut self, channel_id: ChannelId, position: usize) {
+        if let Some(jp_ids) = self.keyed_join_pattern_index.peek_all(&channel_id) {
+            for jp_id in jp_ids {
+                if let Some(key_index) = self.key_indices.get_mut(jp_id) {
+                    let join_pattern = &self.join_patterns[jp_id];
+                    let key = join_pattern.key().unwrap();
 
-                jp.fire(arg_1, arg_2_and_sender);
-            }
-            TernarySend(jp) => {
-                let arg_1 = self.messages.retrieve(&jp.first_send_channel_id()).unwrap();
-                let arg_2 = self
-                    .messages
-                    .retrieve(&jp.second_send_channel_id())
-                    .unwrap();
-                let arg_3 = self.messages.retrieve(&jp.third_send_channel_id()).unwrap();
-
-                jp.fire(arg_1, arg_2, arg_3);
-            }
-            TernaryRecv(jp) => {
-                let arg_1 = self.messages.retrieve(&jp.first_send_channel_id()).unwrap();
-                let arg_2 = self
-                    .messages
-                    .retrieve(&jp.second_send_channel_id())
-                    .unwrap();
-                let return_sender = self.messages.retrieve(&jp.recv_channel_id()).unwrap();
-
-                jp.fire(arg_1, arg_2, return_sender);
-            }
-            TernaryBidir(jp) => {
-                let arg_1 = self.messages.retrieve(&jp.first_send_channel_id()).unwrap();
-                let arg_2 = self
-                    .messages
-                    .retrieve(&jp.second_send_channel_id())
-                    .unwrap();
-                let arg_3_and_sender = self.messages.retrieve(&jp.bidir_channel_id()).unwrap();
-
-                jp.fire(arg_1, arg_2, arg_3_and_sender);
+                    for (i, &ch_id) in join_pattern.channel_ids().iter().enumerate() {
+                        if ch_id == channel_id {
+                            key_index.insert(i, key(i, position), position);
+                        }
+                    }
+                }
             }
         }
     }
 
-    /// Reset the `Counter` at which the given Join Pattern has last been fired.
-    fn reset_last_fired(&mut self, join_pattern_id: JoinPatternId) {
-        self.join_pattern_last_fired
-            .insert(join_pattern_id, Some(self.message_counter.clone()));
+    /// Remove the message at the given position of the channel with the
+    /// given `ChannelId` from the `KeyIndex` of every keyed Join Pattern that
+    /// the channel is in.
+    fn unindex_message(&mut self, channel_id: ChannelId, position: usize) {
+        if let Some(jp_ids) = self.keyed_join_pattern_index.peek_all(&channel_id) {
+            for jp_id in jp_ids {
+                if let Some(key_index) = self.key_indices.get_mut(jp_id) {
+                    let join_pattern = &self.join_patterns[jp_id];
+                    let key = join_pattern.key().unwrap();
+
+                    for (i, &ch_id) in join_pattern.channel_ids().iter().enumerate() {
+                        if ch_id == channel_id {
+                            key_index.remove(i, key(i, position), position);
+                        }
+                    }
+                }
+            }
+        }
     }
 
-    /// Send new, *unique* `ChannelId` back to the requesting `Junction`.
+    /// Add new Join Pattern to `Controller` storage under the given
+    /// `JoinPatternId`.
     ///
-    /// # Panics
+    /// `Message`s sent before the Join Pattern was added may already make it
+    /// alive, in which case it is fired right away, as often as they allow.
     ///
-    /// Panics if the new `ChannelId` could not be sent to the requesting `Junction`.
-    fn handle_new_channel_id_request(&mut self, return_sender: Sender<ChannelId>) {
-        return_sender.send(self.new_channel_id()).unwrap();
+    /// The `Junction` rejects Join Patterns once it has been sealed, so the
+    /// request always arrives before the `Controller` is sealed.
+    fn handle_add_join_pattern_request(
+        &mut self,
+        join_pattern_id: JoinPatternId,
+        join_pattern: JoinPattern,
+    ) {
+        debug_assert!(!self.sealed, "Join Pattern added to sealed Controller");
+
+        self.insert_join_pattern(join_pattern_id, join_pattern);
+
+        self.fire_alive_join_patterns(&[join_pattern_id], None);
     }
 
-    /// Add new Join Pattern to `Controller` storage.
-    fn handle_add_join_pattern_request(&mut self, join_pattern: JoinPattern) {
-        let jp_id = self.new_join_pattern_id();
+    /// Handle the completion of a firing of the given Join Pattern.
+    ///
+    /// A Join Pattern with serialized firings may have become alive again
+    /// once its previous firing has completed, in which case it is fired
+    /// right away.
+    fn handle_firing_completed(&mut self, join_pattern_id: JoinPatternId) {
+        self.running_join_patterns.remove(&join_pattern_id);
 
-        self.initialize_last_fired(jp_id);
+        self.fire_alive_join_patterns(&[join_pattern_id], None);
+    }
 
-        self.insert_join_pattern(jp_id, join_pattern);
+    /// Select which of the alive Join Patterns to fire with the given
+    /// `SelectionPolicy` from now on.
+    pub(crate) fn set_selection_policy(&mut self, selection_policy: Box<dyn SelectionPolicy>) {
+        self.selection_policy = selection_policy;
     }
 
-    /// Initialize the `Instant` at which Join Pattern was last alive.
-    fn initialize_last_fired(&mut self, join_pattern_id: JoinPatternId) {
-        self.join_pattern_last_fired.insert(join_pattern_id, None);
+    /// Report panicking function bodies of fired Join Patterns to the given
+    /// `PanicHook` from now on.
+    pub(crate) fn set_panic_hook(&mut self, panic_hook: functions::PanicHook) {
+        self.panic_hook = Some(panic_hook);
+    }
+
+    /// Fix the set of channels and Join Patterns of the `Controller`.
+    ///
+    /// The message queues and the `ReadinessIndex` are moved into dense arrays
+    /// indexed by `ChannelId`, which is possible since `ChannelId`s are handed
+    /// out in increasing order from zero and no further ones are handed out.
+    /// The `Junction` makes sure that no further channels or Join Patterns
+    /// are added from now on.
+    ///
+    /// The Join Patterns and their `KeyIndex`es stay in `HashMap`s, since
+    /// `JoinPatternId`s are drawn from a counter shared by all `Junction`s and
+    /// are not dense. The `InvertedIndex` of keyed Join Patterns is left as a
+    /// `HashMap` by channel as well.
+    pub(crate) fn seal(&mut self) {
+        self.store.seal();
+        self.readiness_index.seal();
+        self.sealed = true;
+    }
+
+    /// Remove Join Pattern from all internal storage.
+    ///
+    /// Messages available on the channels of the Join Pattern are left
+    /// untouched. Nothing will be done if there is no Join Pattern stored
+    /// for the given `JoinPatternId`, e.g. because it has already been
+    /// removed.
+    fn handle_remove_join_pattern_request(&mut self, join_pattern_id: JoinPatternId) {
+        if let Some(join_pattern) = self.join_patterns.remove(&join_pattern_id) {
+            for ch_id in join_pattern.channel_ids() {
+                self.keyed_join_pattern_index
+                    .remove_single(ch_id, &join_pattern_id);
+            }
+
+            self.readiness_index.remove(&join_pattern_id);
+            self.key_indices.remove(&join_pattern_id);
+            self.selection_policy.removed(join_pattern_id);
+        }
     }
 
     /// Insert Join Pattern into relevant internal storage.
     ///
     /// The given Join Pattern needs to be registered within the internal
-    /// `InvertedIndex` for future look-up operations and then stored in the
-    /// Join Pattern collection.
+    /// `InvertedIndex` and `ReadinessIndex` for future look-up operations and
+    /// then stored in the Join Pattern collection.
     fn insert_join_pattern(&mut self, join_pattern_id: JoinPatternId, join_pattern: JoinPattern) {
-        use JoinPattern::*;
+        let store = &self.store;
 
-        match join_pattern {
-            UnarySend(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.channel_id(), join_pattern_id);
+        self.readiness_index.insert(
+            join_pattern_id,
+            join_pattern.channel_ids(),
+            join_pattern.negated_channel_ids(),
+            |ch_id| store.count(ch_id),
+        );
 
-                self.join_patterns.insert(join_pattern_id, UnarySend(jp));
-            }
-            UnaryRecv(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.channel_id(), join_pattern_id);
+        // Keyed Join Patterns need to index all messages that are already
+        // available on their channels.
+        if let Some(key) = join_pattern.key() {
+            // Only register each channel once, even if it appears multiple
+            // times in the Join Pattern.
+            let mut indexed_channel_ids: Vec<ChannelId> = Vec::new();
 
-                self.join_patterns.insert(join_pattern_id, UnaryRecv(jp));
+            for &ch_id in join_pattern.channel_ids() {
+                if !indexed_channel_ids.contains(&ch_id) {
+                    self.keyed_join_pattern_index
+                        .insert_single(ch_id, join_pattern_id);
+                    indexed_channel_ids.push(ch_id);
+                }
             }
-            UnaryBidir(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.channel_id(), join_pattern_id);
 
-                self.join_patterns.insert(join_pattern_id, UnaryBidir(jp));
-            }
-            BinarySend(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.first_send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.second_send_channel_id(), join_pattern_id);
+            let mut key_index = KeyIndex::new(join_pattern.channel_ids().len());
 
-                self.join_patterns.insert(join_pattern_id, BinarySend(jp));
+            for (i, ch_id) in join_pattern.channel_ids().iter().enumerate() {
+                for position in self.store.positions(ch_id) {
+                    key_index.insert(i, key(i, position), position);
+                }
             }
-            BinaryRecv(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.recv_channel_id(), join_pattern_id);
 
-                self.join_patterns.insert(join_pattern_id, BinaryRecv(jp));
-            }
-            BinaryBidir(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.bidir_channel_id(), join_pattern_id);
+            self.key_indices.insert(join_pattern_id, key_index);
+        }
 
-                self.join_patterns.insert(join_pattern_id, BinaryBidir(jp));
-            }
-            TernarySend(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.first_send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.second_send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.third_send_channel_id(), join_pattern_id);
-
-                self.join_patterns.insert(join_pattern_id, TernarySend(jp));
-            }
-            TernaryRecv(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.first_send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.second_send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.recv_channel_id(), join_pattern_id);
-
-                self.join_patterns.insert(join_pattern_id, TernaryRecv(jp));
-            }
-            TernaryBidir(jp) => {
-                self.join_pattern_index
-                    .insert_single(jp.first_send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.second_send_channel_id(), join_pattern_id);
-                self.join_pattern_index
-                    .insert_single(jp.bidir_channel_id(), join_pattern_id);
-
-                self.join_patterns.insert(join_pattern_id, TernaryBidir(jp));
-            }
+        self.join_patterns.insert(join_pattern_id, join_pattern);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    use crate::channels::RecvChannel;
+    use crate::executor::InlineExecutor;
+    use crate::{Junction, LocalJunction};
+
+    /// Create a `RecvChannel` on the given `Junction`, whose `recv` returns
+    /// once the `Junction` has handled all messages sent before it.
+    fn flush_channel(j: &Junction) -> RecvChannel<()> {
+        let flush = j.recv_channel::<()>().unwrap();
+        j.when_recv(&flush).then_do(|| ()).unwrap();
+
+        flush
+    }
+
+    #[test]
+    fn test_guard_only_tried_with_arrived_message() {
+        // Given:
+        let j = LocalJunction::new();
+        let left = j.send_channel::<u32>().unwrap();
+        let right = j.send_channel::<u32>().unwrap();
+        let guard_calls = Arc::new(AtomicUsize::new(0));
+        let guard_calls_clone = guard_calls.clone();
+        let (sender, receiver) = channel();
+        j.when(&left)
+            .and(&right)
+            .guard(move |x, y| {
+                guard_calls_clone.fetch_add(1, Ordering::SeqCst);
+                x + y == 20
+            })
+            .then_do(move |x, y| sender.send((x, y)).unwrap())
+            .unwrap();
+        for value in 0..10 {
+            left.send(value).unwrap();
         }
+        for value in 0..10 {
+            right.send(value).unwrap();
+        }
+        j.run_until_idle();
+        assert_eq!(100, guard_calls.load(Ordering::SeqCst));
+
+        // When:
+        right.send(11).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(110, guard_calls.load(Ordering::SeqCst));
+        assert_eq!(vec![(9, 11)], receiver.try_iter().collect::<Vec<_>>());
     }
 
-    /// Generate new, *unique* `ChannelId`.
-    fn new_channel_id(&mut self) -> ChannelId {
-        let ch_id = self.latest_channel_id;
-        self.latest_channel_id.increment();
+    #[test]
+    fn test_selected_match_fired_without_searching_again() {
+        // Given:
+        let j = LocalJunction::new();
+        let left = j.send_channel::<u32>().unwrap();
+        let right = j.send_channel::<u32>().unwrap();
+        let guard_calls = Arc::new(AtomicUsize::new(0));
+        let guard_calls_clone = guard_calls.clone();
+        let (sender, receiver) = channel();
+        j.when(&left)
+            .and(&right)
+            .guard(move |x, y| {
+                guard_calls_clone.fetch_add(1, Ordering::SeqCst);
+                x == y
+            })
+            .then_do(move |x, y| sender.send((x, y)).unwrap())
+            .unwrap();
 
-        ch_id
+        // When:
+        left.send(1).unwrap();
+        right.send(1).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(1, guard_calls.load(Ordering::SeqCst));
+        assert_eq!(vec![(1, 1)], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_keyed_search_after_arrived_message_consumed() {
+        // Given:
+        let j = LocalJunction::new();
+        let requests = j.send_channel::<(u32, &str)>().unwrap();
+        let responses = j.send_channel::<(u32, u32)>().unwrap();
+        let (sender, receiver) = channel();
+        j.when_keyed(&requests, |request| request.0)
+            .and_keyed(&responses, |response| response.0)
+            .then_do(move |(request, (response, ()))| sender.send((request.1, response.1)).unwrap())
+            .unwrap();
+        requests.send((1, "1 + 1")).unwrap();
+        requests.send((2, "2 + 2")).unwrap();
+
+        // When:
+        responses.send((2, 4)).unwrap();
+        responses.send((1, 2)).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(
+            vec![("2 + 2", 4), ("1 + 1", 2)],
+            receiver.try_iter().collect::<Vec<_>>()
+        );
     }
 
-    /// Generate new, *unique* `JoinPatternId`.
-    fn new_join_pattern_id(&mut self) -> JoinPatternId {
-        let jp_id = self.latest_join_pattern_id;
-        self.latest_join_pattern_id.increment();
+    #[test]
+    fn test_negated_channel_emptied_on_junction() {
+        // Given:
+        let j = Junction::with_executor(InlineExecutor);
+        let flush = flush_channel(&j);
+        let urgent = j.send_channel::<i32>().unwrap();
+        let go = j.send_channel::<()>().unwrap();
+        let normal = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        let sender_clone = sender.clone();
+        j.when(&urgent)
+            .and(&go)
+            .then_do(move |v, ()| sender_clone.send(("urgent", v)).unwrap())
+            .unwrap();
+        j.when(&normal)
+            .and_not(&urgent)
+            .then_do(move |v| sender.send(("normal", v)).unwrap())
+            .unwrap();
+        urgent.send(1).unwrap();
+        normal.send(2).unwrap();
+        flush.recv().unwrap();
+        assert_eq!(None, receiver.try_iter().next());
+
+        // When:
+        go.send(()).unwrap();
+        flush.recv().unwrap();
+
+        // Then:
+        assert_eq!(
+            vec![("urgent", 1), ("normal", 2)],
+            receiver.try_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_negated_channel_emptied_on_local_junction() {
+        // Given:
+        let j = LocalJunction::new();
+        let urgent = j.send_channel::<i32>().unwrap();
+        let go = j.send_channel::<()>().unwrap();
+        let normal = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        let sender_clone = sender.clone();
+        j.when(&urgent)
+            .and(&go)
+            .then_do(move |v, ()| sender_clone.send(("urgent", v)).unwrap())
+            .unwrap();
+        j.when(&normal)
+            .and_not(&urgent)
+            .then_do(move |v| sender.send(("normal", v)).unwrap())
+            .unwrap();
+        urgent.send(1).unwrap();
+        normal.send(2).unwrap();
+        j.run_until_idle();
+        assert_eq!(None, receiver.try_iter().next());
+
+        // When:
+        go.send(()).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(
+            vec![("urgent", 1), ("normal", 2)],
+            receiver.try_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_fire_on_registration_on_junction() {
+        // Given:
+        let j = Junction::with_executor(InlineExecutor);
+        let flush = flush_channel(&j);
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        for v in 1..=3 {
+            values.send(v).unwrap();
+        }
+
+        // When:
+        j.when(&values)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+        flush.recv().unwrap();
+
+        // Then:
+        assert_eq!(vec![1, 2, 3], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_fire_on_registration_on_local_junction() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        for v in 1..=3 {
+            values.send(v).unwrap();
+        }
+        j.run_until_idle();
+
+        // When:
+        j.when(&values)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(vec![1, 2, 3], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_priority_on_junction() {
+        // Given:
+        let j = Junction::with_executor(InlineExecutor);
+        let flush = flush_channel(&j);
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        for (name, priority) in [("low", 0), ("high", 1)] {
+            let sender = sender.clone();
+            j.when(&values)
+                .priority(priority)
+                .then_do(move |_| sender.send(name).unwrap())
+                .unwrap();
+        }
+
+        // When:
+        values.send(1).unwrap();
+        values.send(2).unwrap();
+        flush.recv().unwrap();
+
+        // Then:
+        assert_eq!(
+            vec!["high", "high"],
+            receiver.try_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_priority_on_local_junction() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        for (name, priority) in [("high", 1), ("low", 0)] {
+            let sender = sender.clone();
+            j.when(&values)
+                .priority(priority)
+                .then_do(move |_| sender.send(name).unwrap())
+                .unwrap();
+        }
+
+        // When:
+        values.send(1).unwrap();
+        values.send(2).unwrap();
+        j.run_until_idle();
 
-        jp_id
+        // Then:
+        assert_eq!(
+            vec!["high", "high"],
+            receiver.try_iter().collect::<Vec<_>>()
+        );
     }
 }
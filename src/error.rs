@@ -0,0 +1,50 @@
+//! Errors reported by `Junction`s, their channels and Join Patterns.
+
+use std::error::Error;
+use std::fmt;
+
+use super::types::ids::JoinPatternId;
+
+/// Error returned by the channel and Join Pattern construction APIs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JunctionError {
+    /// The `Junction` has shut down, so it accepts no more messages or Join
+    /// Patterns.
+    JunctionClosed,
+    /// The `Junction` has been sealed, so no new channels or Join Patterns can
+    /// be created on it.
+    Sealed,
+    /// A channel was used in a Join Pattern of a `Junction` other than the
+    /// one that created it.
+    ForeignChannel,
+    /// The Join Pattern that consumed a request did not reply to it.
+    ReplyDropped,
+    /// No reply arrived within the given time.
+    Timeout,
+    /// No reply was available right away.
+    WouldBlock,
+    /// The function body of the Join Pattern that consumed a request
+    /// panicked before replying to it.
+    PatternPanicked { pattern_id: JoinPatternId },
+}
+
+impl fmt::Display for JunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JunctionError::JunctionClosed => write!(f, "Junction has shut down"),
+            JunctionError::Sealed => write!(f, "Junction has been sealed"),
+            JunctionError::ForeignChannel => {
+                write!(f, "channel is not associated with this Junction")
+            }
+            JunctionError::ReplyDropped => write!(f, "Join Pattern did not reply"),
+            JunctionError::Timeout => write!(f, "timed out waiting for a reply"),
+            JunctionError::WouldBlock => write!(f, "no reply available right away"),
+            JunctionError::PatternPanicked { pattern_id } => {
+                write!(f, "Join Pattern {:?} panicked", pattern_id)
+            }
+        }
+    }
+}
+
+impl Error for JunctionError {}
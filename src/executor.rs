@@ -0,0 +1,213 @@
+//! Strategies to run the function bodies of fired Join Patterns.
+//!
+//! Every `Junction` hands the function body of a fired Join Pattern to an
+//! `Executor`, which decides where and when it runs. By default, a new thread
+//! is spawned for every firing, but a `Junction` or an individual Join Pattern
+//! can be set up with any other implementation of the trait, such as the
+//! `ThreadPool` of this crate or ones defined outside of it.
+//!
+//! Join Patterns declared with `then_do_async` have function bodies returning
+//! a `Future`, which is handed to a `Spawner` to be driven by an async runtime.
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::thread;
+
+/// Function body of a fired Join Pattern, together with the messages it is
+/// run on, ready to be executed.
+pub type Job = Box<dyn FnOnce() + Send>;
+
+/// Trait for strategies to run the function bodies of fired Join Patterns.
+///
+/// The control thread of a `Junction` calls `execute` once for every firing,
+/// so implementations should return quickly: no further messages are handled
+/// by the `Junction` until `execute` has returned.
+pub trait Executor: Send + Sync {
+    /// Run the given `Job`, either right away or at some point in the future.
+    fn execute(&self, job: Job);
+}
+
+impl<E> Executor for Arc<E>
+where
+    E: Executor + ?Sized,
+{
+    fn execute(&self, job: Job) {
+        (**self).execute(job)
+    }
+}
+
+/// `Executor` spawning a new thread for every `Job`.
+///
+/// This is the `Executor` used by a `Junction` unless another one is given.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SpawnExecutor;
+
+impl Executor for SpawnExecutor {
+    fn execute(&self, job: Job) {
+        thread::spawn(job);
+    }
+}
+
+/// `Executor` running every `Job` directly on the control thread of the
+/// `Junction`.
+///
+/// Avoids any thread creation or hand-over, which makes it a good fit for
+/// short function bodies. However, the `Junction` does not handle any further
+/// messages while a `Job` is running, so a function body must never wait on
+/// a synchronous channel of its own `Junction`, as it would wait forever.
+///
+/// A `Job` that panics does not take the control thread down with it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InlineExecutor;
+
+impl Executor for InlineExecutor {
+    fn execute(&self, job: Job) {
+        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+    }
+}
+
+/// `Future` returned by the function body of a fired asynchronous Join
+/// Pattern, ready to be spawned.
+pub type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Trait for async runtimes driving the `Future`s returned by the function
+/// bodies of Join Patterns declared with `then_do_async`.
+///
+/// Implementations typically hand the `Task` to the spawn function of the
+/// runtime they wrap.
+pub trait Spawner: Send + Sync {
+    /// Spawn the given `Task` to be polled to completion.
+    fn spawn(&self, task: Task);
+}
+
+impl<S> Spawner for Arc<S>
+where
+    S: Spawner + ?Sized,
+{
+    fn spawn(&self, task: Task) {
+        (**self).spawn(task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    use crate::Junction;
+
+    /// `Executor` running every `Job` directly, counting the `Job`s it ran.
+    #[derive(Clone, Default)]
+    struct CountingExecutor(Arc<AtomicUsize>);
+
+    impl CountingExecutor {
+        fn executed(&self) -> usize {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Executor for CountingExecutor {
+        fn execute(&self, job: Job) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            job();
+        }
+    }
+
+    #[test]
+    fn test_junction_with_executor() {
+        // Given:
+        let executor = CountingExecutor::default();
+        let j = Junction::with_executor(executor.clone());
+        let value = j.send_channel::<u32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&value)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+
+        // When:
+        value.send(1).unwrap();
+
+        // Then:
+        assert_eq!(Ok(1), receiver.recv_timeout(Duration::from_secs(5)));
+        assert_eq!(1, executor.executed());
+    }
+
+    #[test]
+    fn test_pattern_executor_overrides_junction_executor() {
+        // Given:
+        let junction_executor = CountingExecutor::default();
+        let pattern_executor = CountingExecutor::default();
+        let j = Junction::with_executor(junction_executor.clone());
+        let overridden = j.send_channel::<u32>().unwrap();
+        let default = j.send_channel::<u32>().unwrap();
+        let (sender, receiver) = channel();
+        let sender_clone = sender.clone();
+        j.when(&overridden)
+            .executor(pattern_executor.clone())
+            .then_do(move |v| sender_clone.send(v).unwrap())
+            .unwrap();
+        j.when(&default)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+
+        // When:
+        overridden.send(1).unwrap();
+        default.send(2).unwrap();
+
+        // Then:
+        assert_eq!(Ok(1), receiver.recv_timeout(Duration::from_secs(5)));
+        assert_eq!(Ok(2), receiver.recv_timeout(Duration::from_secs(5)));
+        assert_eq!(1, pattern_executor.executed());
+        assert_eq!(1, junction_executor.executed());
+    }
+
+    #[test]
+    fn test_inline_executor_runs_on_control_thread() {
+        // Given:
+        let mut j = Junction::with_executor(InlineExecutor);
+        let mut controller_handle = j.controller_handle().unwrap();
+        let value = j.send_channel::<u32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&value)
+            .then_do(move |_| sender.send(thread::current().id()).unwrap())
+            .unwrap();
+
+        // When:
+        value.send(1).unwrap();
+
+        // Then:
+        let control_thread_id = controller_handle.thread().unwrap().id();
+        assert_eq!(
+            Ok(control_thread_id),
+            receiver.recv_timeout(Duration::from_secs(5))
+        );
+        assert_eq!(Ok(()), controller_handle.stop());
+    }
+
+    #[test]
+    fn test_inline_executor_survives_panic() {
+        // Given:
+        let j = Junction::with_executor(InlineExecutor);
+        let fail = j.send_channel::<()>().unwrap();
+        let value = j.send_channel::<u32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&fail)
+            .then_do(|()| panic!("pattern panicked"))
+            .unwrap();
+        j.when(&value)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+
+        // When:
+        fail.send(()).unwrap();
+        value.send(1).unwrap();
+
+        // Then:
+        assert_eq!(Ok(1), receiver.recv_timeout(Duration::from_secs(5)));
+    }
+}
@@ -1,50 +1,270 @@
 //! Function transformers used to hide actual type signatures of functions stored
 //! with a Join Pattern and instead expose a generic interface that is easily stored.
+//!
+//! Every transformer is handed the typed `QueueRef`s of the channels of the
+//! Join Pattern, in order, which the transformed function keeps. Every
+//! function body is transformed to take the messages consumed by the Join
+//! Pattern out of these queues and to return the `Job` running the function
+//! on them. Every guard is transformed to take the positions of the messages
+//! under consideration in these queues, in the same order. The messages are
+//! thus always accessed with their actual type, without any downcast.
+//!
+//! Functions of asynchronous Join Patterns return a `Future`, which is handed
+//! to a `Spawner` and replies to any synchronous channels once it completes.
+//! Panics while polling the `Future` are caught and reported like those of
+//! synchronous functions.
 
 use std::any::Any;
-use std::sync::mpsc::Sender;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 
-use crate::types::{functions, Message};
+use crate::executor::Job;
+use crate::executor::Spawner;
+use crate::patterns::nary::ChannelList;
+use crate::reply::ReplySender;
+use crate::store::{AssertSend, QueueRef, Retrieval};
+use crate::types::functions;
+use crate::types::ids::JoinPatternId;
+
+/// ID of a firing Join Pattern together with the `PanicHook` that panics of
+/// its asynchronous function body are reported to, if any.
+struct PanicReport {
+    join_pattern_id: JoinPatternId,
+    panic_hook: Option<functions::PanicHook>,
+}
+
+impl PanicReport {
+    /// Create the `PanicReport` for the Join Pattern firing on the given
+    /// messages.
+    fn new(messages: &Retrieval) -> PanicReport {
+        PanicReport {
+            join_pattern_id: messages.join_pattern_id(),
+            panic_hook: messages.panic_hook().cloned(),
+        }
+    }
+}
+
+/// `Future` polling the `Future` of an asynchronous function body under
+/// `catch_unwind`.
+///
+/// Should polling the inner `Future` panic, the panic is reported like that
+/// of a synchronous function body and the `Future` completes. Any
+/// `ReplySender`s held by the inner `Future` are dropped while unwinding, so
+/// whoever waits on a reply from it is handed `JunctionError::PatternPanicked`.
+struct CatchPanic<Fut> {
+    future: Pin<Box<Fut>>,
+    panic_report: PanicReport,
+}
+
+impl<Fut> Future for CatchPanic<Fut>
+where
+    Fut: Future<Output = ()>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        let future = &mut this.future;
+
+        match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(poll) => poll,
+            Err(payload) => {
+                if let Some(hook) = &this.panic_report.panic_hook {
+                    hook(this.panic_report.join_pattern_id, &*payload);
+                }
+
+                Poll::Ready(())
+            }
+        }
+    }
+}
+
+/// Spawn the given `Future` of an asynchronous function body with the given
+/// `Spawner`, catching and reporting any panic while it is polled.
+fn spawn<S, Fut>(spawner: &S, panic_report: PanicReport, future: Fut)
+where
+    S: Spawner,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    spawner.spawn(Box::pin(CatchPanic {
+        future: Box::pin(future),
+        panic_report,
+    }));
+}
+
+/// Spawn the given `Future` with the given `Spawner`, sending its output
+/// through the `ReplySender` once it has completed.
+fn spawn_reply<S, Fut, R>(
+    spawner: &S,
+    panic_report: PanicReport,
+    future: Fut,
+    return_sender: AssertSend<ReplySender<R>>,
+) where
+    S: Spawner,
+    Fut: Future<Output = R> + Send + 'static,
+    R: 'static,
+{
+    spawn(spawner, panic_report, async move {
+        let reply = future.await;
+
+        // There is no one left to panic to if the receiver is gone.
+        let _ = return_sender.into_inner().send(reply);
+    });
+}
 
 /// Function transformers for functions stored with unary Join Patterns.
 pub(crate) mod unary {
     use super::*;
 
-    /// Transform function of `SendJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_send<F, T>(f: F) -> Box<impl functions::unary::FnBoxClone>
+    /// Transform function of `SendPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_send<F, T>(queue: QueueRef<T>, f: F) -> functions::FnBox
     where
-        F: Fn(T) -> () + Send + Clone + 'static,
-        T: Any + Send + 'static,
+        F: Fn(T) + Send + Clone + 'static,
+        T: Any + 'static,
     {
-        Box::new(move |arg: Message| {
-            f(*arg.downcast::<T>().unwrap());
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg = messages.take(&queue);
+            let f = f.clone();
+
+            Box::new(move || f(arg.into_inner()))
         })
     }
 
-    /// Transform function of `RecvJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_recv<F, R>(f: F) -> Box<impl functions::unary::FnBoxClone>
+    /// Transform function of `RecvPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_recv<F, R>(queue: QueueRef<ReplySender<R>>, f: F) -> functions::FnBox
     where
         F: Fn() -> R + Send + Clone + 'static,
-        R: Any + Send + 'static,
+        R: Any + 'static,
     {
-        Box::new(move |return_sender: Message| {
-            let return_sender = *return_sender.downcast::<Sender<R>>().unwrap();
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let return_sender = messages.take_reply(&queue);
+            let f = f.clone();
 
-            return_sender.send(f()).unwrap();
+            Box::new(move || {
+                // The caller may have stopped waiting for the reply in the meantime.
+                let _ = return_sender.into_inner().send(f());
+            })
         })
     }
 
-    /// Transform function of `BidirJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_bidir<F, T, R>(f: F) -> Box<impl functions::unary::FnBoxClone>
+    /// Transform function of `BidirPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_bidir<F, T, R>(
+        queue: QueueRef<(T, ReplySender<R>)>,
+        f: F,
+    ) -> functions::FnBox
     where
         F: Fn(T) -> R + Send + Clone + 'static,
-        T: Any + Send + 'static,
-        R: Any + Send + 'static,
+        T: Any + 'static,
+        R: Any + 'static,
+    {
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let (arg, return_sender) = messages.take_request(&queue).unzip();
+            let f = f.clone();
+
+            Box::new(move || {
+                // The caller may have stopped waiting for the reply in the meantime.
+                let _ = return_sender.into_inner().send(f(arg.into_inner()));
+            })
+        })
+    }
+
+    /// Transform guard of `SendPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_send_guard<G, T>(queue: QueueRef<T>, g: G) -> functions::GuardBox
+    where
+        G: Fn(&T) -> bool + Send + 'static,
+        T: Any + 'static,
+    {
+        Box::new(move |positions: &[usize]| queue.with(positions[0], &g))
+    }
+
+    /// Transform guard of `BidirPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_bidir_guard<G, T, R>(
+        queue: QueueRef<(T, ReplySender<R>)>,
+        g: G,
+    ) -> functions::GuardBox
+    where
+        G: Fn(&T) -> bool + Send + 'static,
+        T: Any + 'static,
+        R: Any + 'static,
+    {
+        Box::new(move |positions: &[usize]| queue.with(positions[0], |(arg, _)| g(arg)))
+    }
+
+    /// Transform asynchronous function of `SendPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_send_async<S, F, Fut, T>(
+        queue: QueueRef<T>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+        T: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg = messages.take(&queue);
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || spawn(&*spawner, panic_report, f(arg.into_inner())))
+        })
+    }
+
+    /// Transform asynchronous function of `RecvPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_recv_async<S, F, Fut, R>(
+        queue: QueueRef<ReplySender<R>>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn() -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        R: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let return_sender = messages.take_reply(&queue);
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || spawn_reply(&*spawner, panic_report, f(), return_sender))
+        })
+    }
+
+    /// Transform asynchronous function of `BidirPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_bidir_async<S, F, Fut, T, R>(
+        queue: QueueRef<(T, ReplySender<R>)>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        T: Any + 'static,
+        R: Any + 'static,
     {
-        Box::new(move |arg_and_sender: Message| {
-            let (arg, return_sender) = *arg_and_sender.downcast::<(T, Sender<R>)>().unwrap();
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let (arg, return_sender) = messages.take_request(&queue).unzip();
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
 
-            return_sender.send(f(arg)).unwrap();
+            Box::new(move || {
+                spawn_reply(&*spawner, panic_report, f(arg.into_inner()), return_sender)
+            })
         })
     }
 }
@@ -53,49 +273,215 @@ pub(crate) mod unary {
 pub(crate) mod binary {
     use super::*;
 
-    /// Transform function of `SendJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_send<F, T, U>(f: F) -> Box<impl functions::binary::FnBoxClone>
+    /// Transform function of `SendPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_send<F, T, U>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        f: F,
+    ) -> functions::FnBox
     where
-        F: Fn(T, U) -> () + Send + Clone + 'static,
-        T: Any + Send + 'static,
-        U: Any + Send + 'static,
+        F: Fn(T, U) + Send + Clone + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
     {
-        Box::new(move |arg_1: Message, arg_2: Message| {
-            f(
-                *arg_1.downcast::<T>().unwrap(),
-                *arg_2.downcast::<U>().unwrap(),
-            );
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let f = f.clone();
+
+            Box::new(move || f(arg_1.into_inner(), arg_2.into_inner()))
         })
     }
 
-    /// Transform function of `RecvJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_recv<F, T, R>(f: F) -> Box<impl functions::binary::FnBoxClone>
+    /// Transform function of `RecvPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_recv<F, T, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<ReplySender<R>>,
+        f: F,
+    ) -> functions::FnBox
     where
         F: Fn(T) -> R + Send + Clone + 'static,
-        T: Any + Send + 'static,
-        R: Any + Send + 'static,
+        T: Any + 'static,
+        R: Any + 'static,
     {
-        Box::new(move |arg: Message, return_sender: Message| {
-            let return_sender = *return_sender.downcast::<Sender<R>>().unwrap();
-            let arg = *arg.downcast::<T>().unwrap();
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg = messages.take(&queue_1);
+            let return_sender = messages.take_reply(&queue_2);
+            let f = f.clone();
 
-            return_sender.send(f(arg)).unwrap();
+            Box::new(move || {
+                // The caller may have stopped waiting for the reply in the meantime.
+                let _ = return_sender.into_inner().send(f(arg.into_inner()));
+            })
         })
     }
 
-    /// Transform function of `BidirJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_bidir<F, T, U, R>(f: F) -> Box<impl functions::binary::FnBoxClone>
+    /// Transform function of `BidirPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_bidir<F, T, U, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<(U, ReplySender<R>)>,
+        f: F,
+    ) -> functions::FnBox
     where
         F: Fn(T, U) -> R + Send + Clone + 'static,
-        T: Any + Send + 'static,
-        U: Any + Send + 'static,
-        R: Any + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        R: Any + 'static,
+    {
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let (arg_2, return_sender) = messages.take_request(&queue_2).unzip();
+            let f = f.clone();
+
+            Box::new(move || {
+                // The caller may have stopped waiting for the reply in the meantime.
+                let _ = return_sender
+                    .into_inner()
+                    .send(f(arg_1.into_inner(), arg_2.into_inner()));
+            })
+        })
+    }
+
+    /// Transform guard of `SendPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_send_guard<G, T, U>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        g: G,
+    ) -> functions::GuardBox
+    where
+        G: Fn(&T, &U) -> bool + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+    {
+        Box::new(move |positions: &[usize]| {
+            queue_1.with(positions[0], |arg_1| {
+                queue_2.with(positions[1], |arg_2| g(arg_1, arg_2))
+            })
+        })
+    }
+
+    /// Transform guard of `RecvPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_recv_guard<G, T>(queue: QueueRef<T>, g: G) -> functions::GuardBox
+    where
+        G: Fn(&T) -> bool + Send + 'static,
+        T: Any + 'static,
     {
-        Box::new(move |arg_1: Message, arg_2_and_sender: Message| {
-            let arg_1 = *arg_1.downcast::<T>().unwrap();
-            let (arg_2, return_sender) = *arg_2_and_sender.downcast::<(U, Sender<R>)>().unwrap();
+        Box::new(move |positions: &[usize]| queue.with(positions[0], &g))
+    }
 
-            return_sender.send(f(arg_1, arg_2)).unwrap();
+    /// Transform guard of `BidirPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_bidir_guard<G, T, U, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<(U, ReplySender<R>)>,
+        g: G,
+    ) -> functions::GuardBox
+    where
+        G: Fn(&T, &U) -> bool + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        R: Any + 'static,
+    {
+        Box::new(move |positions: &[usize]| {
+            queue_1.with(positions[0], |arg_1| {
+                queue_2.with(positions[1], |(arg_2, _)| g(arg_1, arg_2))
+            })
+        })
+    }
+
+    /// Transform asynchronous function of `SendPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_send_async<S, F, Fut, T, U>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T, U) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || {
+                spawn(
+                    &*spawner,
+                    panic_report,
+                    f(arg_1.into_inner(), arg_2.into_inner()),
+                )
+            })
+        })
+    }
+
+    /// Transform asynchronous function of `RecvPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_recv_async<S, F, Fut, T, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<ReplySender<R>>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        T: Any + 'static,
+        R: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg = messages.take(&queue_1);
+            let return_sender = messages.take_reply(&queue_2);
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || {
+                spawn_reply(&*spawner, panic_report, f(arg.into_inner()), return_sender)
+            })
+        })
+    }
+
+    /// Transform asynchronous function of `BidirPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_bidir_async<S, F, Fut, T, U, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<(U, ReplySender<R>)>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T, U) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        R: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let (arg_2, return_sender) = messages.take_request(&queue_2).unzip();
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || {
+                spawn_reply(
+                    &*spawner,
+                    panic_report,
+                    f(arg_1.into_inner(), arg_2.into_inner()),
+                    return_sender,
+                )
+            })
         })
     }
 }
@@ -104,60 +490,339 @@ pub(crate) mod binary {
 pub(crate) mod ternary {
     use super::*;
 
-    /// Transform function of `SendJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_send<F, T, U, V>(f: F) -> Box<impl functions::ternary::FnBoxClone>
+    /// Transform function of `SendPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_send<F, T, U, V>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<V>,
+        f: F,
+    ) -> functions::FnBox
     where
-        F: Fn(T, U, V) -> () + Send + Clone + 'static,
-        T: Any + Send + 'static,
-        U: Any + Send + 'static,
-        V: Any + Send + 'static,
+        F: Fn(T, U, V) + Send + Clone + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        V: Any + 'static,
     {
-        Box::new(move |arg_1: Message, arg_2: Message, arg_3: Message| {
-            f(
-                *arg_1.downcast::<T>().unwrap(),
-                *arg_2.downcast::<U>().unwrap(),
-                *arg_3.downcast::<V>().unwrap(),
-            );
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let arg_3 = messages.take(&queue_3);
+            let f = f.clone();
+
+            Box::new(move || f(arg_1.into_inner(), arg_2.into_inner(), arg_3.into_inner()))
         })
     }
 
-    /// Transform function of `RecvJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_recv<F, T, U, R>(f: F) -> Box<impl functions::ternary::FnBoxClone>
+    /// Transform function of `RecvPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_recv<F, T, U, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<ReplySender<R>>,
+        f: F,
+    ) -> functions::FnBox
     where
         F: Fn(T, U) -> R + Send + Clone + 'static,
-        T: Any + Send + 'static,
-        U: Any + Send + 'static,
-        R: Any + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        R: Any + 'static,
     {
-        Box::new(
-            move |arg_1: Message, arg_2: Message, return_sender: Message| {
-                let return_sender = *return_sender.downcast::<Sender<R>>().unwrap();
-                let arg_1 = *arg_1.downcast::<T>().unwrap();
-                let arg_2 = *arg_2.downcast::<U>().unwrap();
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let return_sender = messages.take_reply(&queue_3);
+            let f = f.clone();
 
-                return_sender.send(f(arg_1, arg_2)).unwrap();
-            },
-        )
+            Box::new(move || {
+                // The caller may have stopped waiting for the reply in the meantime.
+                let _ = return_sender
+                    .into_inner()
+                    .send(f(arg_1.into_inner(), arg_2.into_inner()));
+            })
+        })
     }
 
-    /// Transform function of `BidirJoinPattern` to use `Message` arguments.
-    pub(crate) fn transform_bidir<F, T, U, V, R>(f: F) -> Box<impl functions::ternary::FnBoxClone>
+    /// Transform function of `BidirPartialPattern` to retrieve stored messages.
+    pub(crate) fn transform_bidir<F, T, U, V, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<(V, ReplySender<R>)>,
+        f: F,
+    ) -> functions::FnBox
     where
         F: Fn(T, U, V) -> R + Send + Clone + 'static,
-        T: Any + Send + 'static,
-        U: Any + Send + 'static,
-        V: Any + Send + 'static,
-        R: Any + Send + 'static,
-    {
-        Box::new(
-            move |arg_1: Message, arg_2: Message, arg_3_and_sender: Message| {
-                let arg_1 = *arg_1.downcast::<T>().unwrap();
-                let arg_2 = *arg_2.downcast::<U>().unwrap();
-                let (arg_3, return_sender) =
-                    *arg_3_and_sender.downcast::<(V, Sender<R>)>().unwrap();
-
-                return_sender.send(f(arg_1, arg_2, arg_3)).unwrap();
-            },
-        )
+        T: Any + 'static,
+        U: Any + 'static,
+        V: Any + 'static,
+        R: Any + 'static,
+    {
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let (arg_3, return_sender) = messages.take_request(&queue_3).unzip();
+            let f = f.clone();
+
+            Box::new(move || {
+                // The caller may have stopped waiting for the reply in the meantime.
+                let _ = return_sender.into_inner().send(f(
+                    arg_1.into_inner(),
+                    arg_2.into_inner(),
+                    arg_3.into_inner(),
+                ));
+            })
+        })
+    }
+
+    /// Transform guard of `SendPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_send_guard<G, T, U, V>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<V>,
+        g: G,
+    ) -> functions::GuardBox
+    where
+        G: Fn(&T, &U, &V) -> bool + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        V: Any + 'static,
+    {
+        Box::new(move |positions: &[usize]| {
+            queue_1.with(positions[0], |arg_1| {
+                queue_2.with(positions[1], |arg_2| {
+                    queue_3.with(positions[2], |arg_3| g(arg_1, arg_2, arg_3))
+                })
+            })
+        })
+    }
+
+    /// Transform guard of `RecvPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_recv_guard<G, T, U>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        g: G,
+    ) -> functions::GuardBox
+    where
+        G: Fn(&T, &U) -> bool + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+    {
+        Box::new(move |positions: &[usize]| {
+            queue_1.with(positions[0], |arg_1| {
+                queue_2.with(positions[1], |arg_2| g(arg_1, arg_2))
+            })
+        })
+    }
+
+    /// Transform guard of `BidirPartialPattern` to inspect stored messages.
+    pub(crate) fn transform_bidir_guard<G, T, U, V, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<(V, ReplySender<R>)>,
+        g: G,
+    ) -> functions::GuardBox
+    where
+        G: Fn(&T, &U, &V) -> bool + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        V: Any + 'static,
+        R: Any + 'static,
+    {
+        Box::new(move |positions: &[usize]| {
+            queue_1.with(positions[0], |arg_1| {
+                queue_2.with(positions[1], |arg_2| {
+                    queue_3.with(positions[2], |(arg_3, _)| g(arg_1, arg_2, arg_3))
+                })
+            })
+        })
+    }
+
+    /// Transform asynchronous function of `SendPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_send_async<S, F, Fut, T, U, V>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<V>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T, U, V) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        V: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let arg_3 = messages.take(&queue_3);
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || {
+                spawn(
+                    &*spawner,
+                    panic_report,
+                    f(arg_1.into_inner(), arg_2.into_inner(), arg_3.into_inner()),
+                )
+            })
+        })
+    }
+
+    /// Transform asynchronous function of `RecvPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_recv_async<S, F, Fut, T, U, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<ReplySender<R>>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T, U) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        R: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let return_sender = messages.take_reply(&queue_3);
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || {
+                spawn_reply(
+                    &*spawner,
+                    panic_report,
+                    f(arg_1.into_inner(), arg_2.into_inner()),
+                    return_sender,
+                )
+            })
+        })
+    }
+
+    /// Transform asynchronous function of `BidirPartialPattern` to retrieve
+    /// stored messages.
+    pub(crate) fn transform_bidir_async<S, F, Fut, T, U, V, R>(
+        queue_1: QueueRef<T>,
+        queue_2: QueueRef<U>,
+        queue_3: QueueRef<(V, ReplySender<R>)>,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(T, U, V) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        T: Any + 'static,
+        U: Any + 'static,
+        V: Any + 'static,
+        R: Any + 'static,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let arg_1 = messages.take(&queue_1);
+            let arg_2 = messages.take(&queue_2);
+            let (arg_3, return_sender) = messages.take_request(&queue_3).unzip();
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || {
+                spawn_reply(
+                    &*spawner,
+                    panic_report,
+                    f(arg_1.into_inner(), arg_2.into_inner(), arg_3.into_inner()),
+                    return_sender,
+                )
+            })
+        })
+    }
+}
+
+/// Function transformers for functions stored with n-ary `JoinPattern`s.
+pub(crate) mod nary {
+    use super::*;
+
+    /// Transform function of `PartialPattern` to retrieve stored messages.
+    ///
+    /// The messages are retrieved in the same order as the channels of the
+    /// Join Pattern, which is also the order of the channels in the
+    /// `ChannelList`.
+    pub(crate) fn transform<F, C>(queues: C::Queues, f: F) -> functions::FnBox
+    where
+        F: Fn(C::Args) -> C::Replies + Send + Clone + 'static,
+        C: ChannelList,
+    {
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let messages = C::split_messages(&queues, messages);
+            let f = f.clone();
+
+            Box::new(move || {
+                let (args, return_senders) = messages.into_inner();
+
+                C::send_replies(return_senders, f(args));
+            })
+        })
+    }
+
+    /// Transform guard of `PartialPattern` to inspect stored messages.
+    pub(crate) fn transform_guard<G, C>(queues: C::Queues, g: G) -> functions::GuardBox
+    where
+        G: for<'a> Fn(C::ArgRefs<'a>) -> bool + Send + 'static,
+        C: ChannelList,
+    {
+        Box::new(move |positions: &[usize]| {
+            let inspected = C::take_inspected(&queues, &mut positions.iter());
+            let satisfied = panic::catch_unwind(AssertUnwindSafe(|| g(C::arg_refs(&inspected))));
+
+            // The messages need to be back in their queues before the panic
+            // of the guard, if any, is passed on.
+            C::restore_inspected(&queues, &mut positions.iter(), inspected);
+
+            satisfied.unwrap_or_else(|payload| panic::resume_unwind(payload))
+        })
+    }
+
+    /// Transform asynchronous function of `PartialPattern` to retrieve stored
+    /// messages.
+    ///
+    /// The replies are sent once the `Future` returned by the function has
+    /// completed.
+    pub(crate) fn transform_async<S, F, Fut, C>(
+        queues: C::Queues,
+        spawner: S,
+        f: F,
+    ) -> functions::FnBox
+    where
+        S: Spawner + 'static,
+        F: Fn(C::Args) -> Fut + Send + Clone + 'static,
+        Fut: Future<Output = C::Replies> + Send + 'static,
+        C: ChannelList,
+    {
+        let spawner = Arc::new(spawner);
+
+        Box::new(move |messages: &mut Retrieval| -> Job {
+            let (args, return_senders) = C::split_messages(&queues, messages).unzip();
+            let panic_report = PanicReport::new(messages);
+            let (f, spawner) = (f.clone(), Arc::clone(&spawner));
+
+            Box::new(move || {
+                let future = f(args.into_inner());
+
+                spawn(&*spawner, panic_report, async move {
+                    let replies = future.await;
+
+                    C::send_replies(return_senders.into_inner(), replies);
+                });
+            })
+        })
     }
 }
@@ -40,32 +40,26 @@ where
         }
     }
 
-    /// Insert multiple values for the given key.
+    /// Remove all occurrences of the given value for the given key.
     ///
-    /// If the key does not yet exist in the collection, it is added with
-    /// the values, in order. Otherwise, the given values are added in
-    /// order at the end of the list of values already associated with the
-    /// given key.
-    pub(crate) fn insert_multiple(&mut self, key: K, values: impl IntoIterator<Item = V>) {
-        match self.look_up_table.get_mut(&key) {
-            Some(stored_values) => {
-                values.into_iter().for_each(|v| stored_values.push_back(v));
-            }
-            None => {
-                self.look_up_table.insert(key, values.into_iter().collect());
+    /// If no values remain for the given key afterwards, the key is removed
+    /// from the collection as well.
+    pub(crate) fn remove_single(&mut self, key: &K, value: &V)
+    where
+        V: PartialEq,
+    {
+        if let Some(values) = self.look_up_table.get_mut(key) {
+            *values = std::mem::take(values)
+                .into_iter()
+                .filter(|v| v != value)
+                .collect();
+
+            if values.is_empty() {
+                self.look_up_table.remove(key);
             }
         }
     }
 
-    /// Retrieve an immutable reference to the first value for the given key.
-    ///
-    /// Retrieve an immutable reference to the first value added for the
-    /// given key, if the key is available in the collection. Otherwise,
-    /// return `None`.
-    pub(crate) fn peek_first(&self, key: &K) -> Option<&V> {
-        self.look_up_table.get(key)?.front()
-    }
-
     /// Retrieve an immutable reference to all values for the given key.
     ///
     /// Retrieve an immutable reference to all values, in order of
@@ -94,99 +88,92 @@ mod tests {
     }
 
     #[test]
-    fn test_insert_single_peek_first() {
+    fn test_insert_single_peek_all_first() {
         // Given:
         let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
 
         // When:
         index.insert_single('A', 65);
-        let actual = index.peek_first(&'A');
+        let actual = index.peek_all(&'A').unwrap().front();
 
         // Then:
         assert_eq!(65, *actual.unwrap());
     }
 
     #[test]
-    fn test_new_key_insert_multiple_peek_first() {
-        // Given:
-        let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
-
-        // When:
-        index.insert_multiple('A', vec![65, 66, 67]);
-        let actual = index.peek_first(&'A');
-
-        // Given:
-        assert_eq!(65, *actual.unwrap());
-    }
-
-    #[test]
-    fn test_new_key_insert_multiple_peek_all() {
+    fn test_multiple_same_key_insert_single_peek_all() {
         // Given:
         let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
 
         // When:
-        index.insert_multiple('A', vec![65, 66, 67]);
+        index.insert_single('A', 65);
+        index.insert_single('A', 66);
+        index.insert_single('A', 67);
         let actual = index.peek_all(&'A');
 
-        // Given:
+        // Then:
         assert_matching!([65, 66, 67], *actual.unwrap());
     }
 
     #[test]
-    fn test_existing_key_insert_multiple_peek_first() {
+    fn test_multiple_different_key_insert_single_peek_all() {
         // Given:
         let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
 
         // When:
-        index.insert_single('A', 64);
-        index.insert_multiple('A', vec![65, 66, 67]);
-        let actual = index.peek_first(&'A');
+        index.insert_single('A', 65);
+        index.insert_single('B', 66);
+        index.insert_single('C', 67);
+        let actual = index.peek_all(&'A');
 
-        // Given:
-        assert_eq!(64, *actual.unwrap());
+        // Then:
+        assert_matching!([65], *actual.unwrap());
     }
 
     #[test]
-    fn test_existing_key_insert_multiple_peek_all() {
+    fn test_remove_single_keeps_other_values() {
         // Given:
         let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
 
+        index.insert_single('A', 65);
+        index.insert_single('A', 66);
+        index.insert_single('A', 67);
+
         // When:
-        index.insert_single('A', 64);
-        index.insert_multiple('A', vec![65, 66, 67]);
+        index.remove_single(&'A', &66);
         let actual = index.peek_all(&'A');
 
-        // Given:
-        assert_matching!([64, 65, 66, 67], *actual.unwrap());
+        // Then:
+        assert_matching!([65, 67], *actual.unwrap());
     }
 
     #[test]
-    fn test_multiple_same_key_insert_single_peek_all() {
+    fn test_remove_single_last_value_removes_key() {
         // Given:
         let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
 
-        // When:
         index.insert_single('A', 65);
-        index.insert_single('A', 66);
-        index.insert_single('A', 67);
+
+        // When:
+        index.remove_single(&'A', &65);
         let actual = index.peek_all(&'A');
 
         // Then:
-        assert_matching!([65, 66, 67], *actual.unwrap());
+        assert!(actual.is_none());
     }
 
     #[test]
-    fn test_multiple_different_key_insert_single_peek_all() {
+    fn test_remove_single_unknown_key() {
         // Given:
         let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
 
-        // When:
         index.insert_single('A', 65);
-        index.insert_single('B', 66);
-        index.insert_single('C', 67);
-        let actual = index.peek_all(&'A');
+
+        // When:
+        index.remove_single(&'B', &65);
+        let actual = index.peek_all(&'A').unwrap().front();
 
         // Then:
-        assert_matching!([65], *actual.unwrap());
+        assert_eq!(65, *actual.unwrap());
     }
 }
@@ -3,13 +3,22 @@
 //! channels and construct `JoinPattern`s based on them.
 
 use std::any::Any;
+use std::convert;
+use std::hash::Hash;
 use std::ops::Drop;
-use std::sync::mpsc::{channel, RecvError, Sender};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use super::channels::{BidirChannel, RecvChannel, SendChannel};
 use super::controller::Controller;
+use super::error::JunctionError;
+use super::executor::{Executor, SpawnExecutor, Spawner};
 use super::patterns::unary::{BidirPartialPattern, RecvPartialPattern, SendPartialPattern};
-use super::types::{ids, ControllerHandle, Packet};
+use super::patterns::{keyed, PatternAttributes};
+use super::queue::{queue, QueueSender};
+use super::selection::SelectionPolicy;
+use super::thread_pool::ThreadPool;
+use super::types::{ids, ControllerHandle, Packet, SealState};
 
 /// Struct managing the creation of new channels and Join Patterns.
 ///
@@ -22,7 +31,11 @@ use super::types::{ids, ControllerHandle, Packet};
 pub struct Junction {
     id: ids::JunctionId,
     controller_handle: Option<ControllerHandle>,
-    sender: Sender<Packet>,
+    sender: QueueSender<Packet>,
+    /// Counter to hand out a new, *unique* `ChannelId` for every channel
+    /// created on this `Junction`.
+    latest_channel_id: AtomicUsize,
+    seal_state: SealState,
 }
 
 #[allow(clippy::new_without_default)]
@@ -33,17 +46,96 @@ impl Junction {
     /// that will handle all incoming `Packet`s for this `Junction`. A
     /// `JoinHandle` to this control thread is stored alongside the `Junction`.
     pub fn new() -> Junction {
-        let (sender, receiver) = channel::<Packet>();
+        Junction::with_executor(SpawnExecutor)
+    }
 
-        let controller = Controller::new();
+    /// Create a new `Junction` running the functions of fired Join Patterns
+    /// with the given `Executor`.
+    ///
+    /// Works like `new`, except that the function of a fired Join Pattern is
+    /// handed to the given `Executor` instead of being run in a newly spawned
+    /// thread. Individual Join Patterns can still override this choice.
+    pub fn with_executor<E>(executor: E) -> Junction
+    where
+        E: Executor + 'static,
+    {
+        Junction::start(Controller::new(Box::new(executor)))
+    }
+
+    /// Create a new `Junction` running the functions of fired Join Patterns
+    /// on the given `ThreadPool`.
+    ///
+    /// Works like `new`, except that the function of a fired Join Pattern is
+    /// run by one of the worker threads of the `ThreadPool` instead of a newly
+    /// spawned thread. A clone of the same `ThreadPool` can be given to
+    /// multiple `Junction`s to share its worker threads between them.
+    pub fn with_thread_pool(thread_pool: ThreadPool) -> Junction {
+        Junction::with_executor(thread_pool)
+    }
+
+    /// Create a new `Junction` with its `Controller` running as a task on an
+    /// async runtime.
+    ///
+    /// Works like `new`, except that instead of spawning a control thread, the
+    /// `Controller` handling the incoming `Packet`s is spawned as a task with
+    /// the given `Spawner`. The task only occupies a thread of the runtime
+    /// while it is handling `Packet`s, which allows for large numbers of
+    /// `Junction`s to be created cheaply. The functions of fired Join Patterns
+    /// are still run in newly spawned threads, unless an `Executor` or
+    /// `then_do_async` is used for the Join Pattern.
+    pub fn with_spawner<S>(spawner: S) -> Junction
+    where
+        S: Spawner,
+    {
+        let (sender, receiver) = queue::<Packet>();
+        let controller = Controller::new(Box::new(SpawnExecutor));
+
+        Junction {
+            id: ids::JunctionId::new(),
+            controller_handle: Some(controller.start_task(sender.clone(), receiver, &spawner)),
+            sender,
+            latest_channel_id: AtomicUsize::new(0),
+            seal_state: SealState::default(),
+        }
+    }
+
+    /// Start the given `Controller` in a control thread for a new `Junction`.
+    fn start(controller: Controller) -> Junction {
+        let (sender, receiver) = queue::<Packet>();
 
         Junction {
             id: ids::JunctionId::new(),
             controller_handle: Some(controller.start(sender.clone(), receiver)),
             sender,
+            latest_channel_id: AtomicUsize::new(0),
+            seal_state: SealState::default(),
         }
     }
 
+    /// Seal the `Junction`, fixing its channels and Join Patterns.
+    ///
+    /// Once sealed, no new channels or Join Patterns can be created on the
+    /// `Junction`. In return, its `Controller` keeps the message queues of the
+    /// channels and the tracking of which Join Patterns are ready to fire in
+    /// dense arrays indexed by channel rather than hash maps, saving a hash
+    /// look-up or two for every message. The Join Patterns themselves are
+    /// still looked up by hash. Existing channels keep working as before.
+    ///
+    /// Partial Join Patterns started before sealing the `Junction` and
+    /// completed afterwards fail with `JunctionError::Sealed`. Sealing a
+    /// `Junction` that has been sealed before has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::JunctionClosed` if the `Junction` has shut down.
+    pub fn seal(&self) -> Result<(), JunctionError> {
+        self.seal_state.seal(|| {
+            self.sender
+                .send(Packet::SealRequest)
+                .map_err(|_| JunctionError::JunctionClosed)
+        })
+    }
+
     /// Return handle to internal `Controller` if available.
     ///
     /// Each `Junction` has an associated control thread with a `Controller`
@@ -61,20 +153,72 @@ impl Junction {
         self.controller_handle.take()
     }
 
+    /// Select which Join Pattern to fire with the given `SelectionPolicy`
+    /// whenever several are alive at the same time.
+    ///
+    /// The `SelectionPolicy` replaces the previous one once the `Junction`
+    /// has handled all messages sent before. By default, `LeastRecentlyFired`
+    /// is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::JunctionClosed` if the `Junction` has shut down.
+    pub fn set_selection_policy<P>(&self, policy: P) -> Result<(), JunctionError>
+    where
+        P: SelectionPolicy + 'static,
+    {
+        self.sender
+            .send(Packet::SetSelectionPolicy {
+                policy: Box::new(policy),
+            })
+            .map_err(|_| JunctionError::JunctionClosed)
+    }
+
+    /// Report panics of the functions of fired Join Patterns to the given
+    /// hook.
+    ///
+    /// The hook is called with the ID of the Join Pattern and the payload of
+    /// the panic, on the thread the function ran on. For functions passed to
+    /// `then_do_async`, this includes panics while the returned `Future` is
+    /// polled, in which case the hook is called on the thread polling it.
+    /// Independent of the hook, a caller waiting on a reply from the function
+    /// is handed `JunctionError::PatternPanicked`. The hook replaces the
+    /// previous one once the `Junction` has handled all messages sent before.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::JunctionClosed` if the `Junction` has shut down.
+    pub fn set_panic_hook<H>(&self, hook: H) -> Result<(), JunctionError>
+    where
+        H: Fn(ids::JoinPatternId, &(dyn Any + Send)) + Send + Sync + 'static,
+    {
+        self.sender
+            .send(Packet::SetPanicHook {
+                hook: Arc::new(hook),
+            })
+            .map_err(|_| JunctionError::JunctionClosed)
+    }
+
     /// Create and return a new `SendChannel` on this `Junction`.
     ///
     /// The generic parameter `T` is used to determine the type of values
     /// that can be sent on this channel.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if it received an error while trying to receive a new
-    /// channel ID from the control thread.
-    pub fn send_channel<T>(&self) -> SendChannel<T>
+    /// Returns `JunctionError::Sealed` if the `Junction` has been sealed.
+    pub fn send_channel<T>(&self) -> Result<SendChannel<T>, JunctionError>
     where
         T: Any + Send,
     {
-        SendChannel::new(self.new_channel_id().unwrap(), self.id, self.sender.clone())
+        self.seal_state.unless_sealed(|| {
+            Ok(SendChannel::new(
+                self.new_channel_id(),
+                self.id,
+                self.sender.clone(),
+                convert::identity,
+            ))
+        })
     }
 
     /// Create and return a new `RecvChannel` on this `Junction`.
@@ -82,15 +226,21 @@ impl Junction {
     /// The generic parameter `R` is used to determine the type of values
     /// that can be received on this channel.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if it received an error while trying to receive a new
-    /// channel ID from the control thread.
-    pub fn recv_channel<R>(&self) -> RecvChannel<R>
+    /// Returns `JunctionError::Sealed` if the `Junction` has been sealed.
+    pub fn recv_channel<R>(&self) -> Result<RecvChannel<R>, JunctionError>
     where
         R: Any + Send,
     {
-        RecvChannel::new(self.new_channel_id().unwrap(), self.id, self.sender.clone())
+        self.seal_state.unless_sealed(|| {
+            Ok(RecvChannel::new(
+                self.new_channel_id(),
+                self.id,
+                self.sender.clone(),
+                convert::identity,
+            ))
+        })
     }
 
     /// Create and return a new `BidirChannel` on this `Junction`.
@@ -99,101 +249,128 @@ impl Junction {
     /// that can be sent on this channel while `R` is used to determine
     /// the type of values that can be received on this channel.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if it received an error while trying to receive the new
-    /// channel IDs from the control thread.
-    pub fn bidir_channel<T, R>(&self) -> BidirChannel<T, R>
+    /// Returns `JunctionError::Sealed` if the `Junction` has been sealed.
+    pub fn bidir_channel<T, R>(&self) -> Result<BidirChannel<T, R>, JunctionError>
     where
         T: Any + Send,
         R: Any + Send,
     {
-        BidirChannel::new(self.new_channel_id().unwrap(), self.id, self.sender.clone())
+        self.seal_state.unless_sealed(|| {
+            Ok(BidirChannel::new(
+                self.new_channel_id(),
+                self.id,
+                self.sender.clone(),
+                convert::identity,
+            ))
+        })
     }
 
-    /// Request ID for a new channel from control thread.
-    ///
-    /// # Panics
+    /// Generate ID for a new channel.
     ///
-    /// Panics if request for new channel id could not be sent to
-    /// control thread.
-    fn new_channel_id(&self) -> Result<ids::ChannelId, RecvError> {
-        let (id_sender, id_receiver) = channel::<ids::ChannelId>();
-
-        self.sender
-            .send(Packet::NewChannelIdRequest {
-                return_sender: id_sender,
-            })
-            .unwrap();
-
-        id_receiver.recv()
+    /// The ID is handed out by the `Junction` itself rather than its control
+    /// thread, so creating a channel never waits on the `Controller`. This
+    /// also allows channels to be created from the function bodies of Join
+    /// Patterns, regardless of where these run.
+    fn new_channel_id(&self) -> ids::ChannelId {
+        ids::ChannelId::new(self.latest_channel_id.fetch_add(1, Ordering::Relaxed))
     }
 
     /// Create new partial Join Pattern starting with a `SendChannel`.
     ///
-    /// # Panics
-    ///
-    /// Panics if the supplied `SendChannel` does not carry the same
-    /// `JunctionID` as this `Junction`, i.e. has not been created by and is
-    /// associated with this `Junction`.
+    /// Should the supplied `SendChannel` not have been created by this
+    /// `Junction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `Junction` have been sealed
+    /// by then, it fails with `JunctionError::Sealed`.
     pub fn when<T>(&self, send_channel: &SendChannel<T>) -> SendPartialPattern<T>
     where
         T: Any + Send,
     {
-        if send_channel.junction_id() == self.id {
-            SendPartialPattern::new(self.id, send_channel.strip(), self.sender.clone())
-        } else {
-            panic!(
-                "SendChannel is not associated with Junction! Please use \
-                 a SendChannel created using the same Junction calling \
-                 this function!"
-            );
-        }
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(send_channel.junction_id(), self.id);
+
+        SendPartialPattern::new(
+            self.id,
+            send_channel.strip(),
+            self.sender.clone(),
+            attributes,
+        )
     }
 
     /// Create new partial Join Pattern starting with a `RecvChannel`.
     ///
-    /// # Panics
-    ///
-    /// Panics if the supplied `RecvChannel` does not carry the same
-    /// `JunctionID` as this `Junction`, i.e. has not been created by and is
-    /// associated with this `Junction`.
+    /// Should the supplied `RecvChannel` not have been created by this
+    /// `Junction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `Junction` have been sealed
+    /// by then, it fails with `JunctionError::Sealed`.
     pub fn when_recv<R>(&self, recv_channel: &RecvChannel<R>) -> RecvPartialPattern<R>
     where
         R: Any + Send,
     {
-        if recv_channel.junction_id() == self.id {
-            RecvPartialPattern::new(recv_channel.strip(), self.sender.clone())
-        } else {
-            panic!(
-                "RecvChannel is not associated with Junction! Please use \
-                 a RecvChannel created using the same Junction calling \
-                 this function!"
-            );
-        }
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(recv_channel.junction_id(), self.id);
+
+        RecvPartialPattern::new(
+            self.id,
+            recv_channel.strip(),
+            self.sender.clone(),
+            attributes,
+        )
     }
 
     /// Create a new partial Join Pattern starting with a `BidirChannel`.
     ///
-    /// # Panics
-    ///
-    /// Panics if the supplied `BidirChannel` does not carry the same
-    /// `JunctionID` as this `Junction`, i.e. has not been created by and is
-    /// associated with this `Junction`.
+    /// Should the supplied `BidirChannel` not have been created by this
+    /// `Junction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `Junction` have been sealed
+    /// by then, it fails with `JunctionError::Sealed`.
     pub fn when_bidir<T, R>(&self, bidir_channel: &BidirChannel<T, R>) -> BidirPartialPattern<T, R>
     where
         T: Any + Send,
         R: Any + Send,
     {
-        if bidir_channel.junction_id() == self.id {
-            BidirPartialPattern::new(bidir_channel.strip(), self.sender.clone())
-        } else {
-            panic!(
-                "BidirChannel is not associated with Junction! Please use \
-                 a BidirChannel created using the same Junction calling \
-                 this function!"
-            );
-        }
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(bidir_channel.junction_id(), self.id);
+
+        BidirPartialPattern::new(
+            self.id,
+            bidir_channel.strip(),
+            self.sender.clone(),
+            attributes,
+        )
+    }
+
+    /// Create new keyed partial Join Pattern starting with a `SendChannel`.
+    ///
+    /// The messages of the `SendChannel` are correlated with those of the
+    /// channels added to the pattern later on by the key extracted with `key`.
+    /// See the `keyed` module for details.
+    ///
+    /// Should the supplied `SendChannel` not have been created by this
+    /// `Junction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `Junction` have been sealed
+    /// by then, it fails with `JunctionError::Sealed`.
+    pub fn when_keyed<T, K, F>(
+        &self,
+        send_channel: &SendChannel<T>,
+        key: F,
+    ) -> keyed::PartialPattern<K, (SendChannel<T>, ())>
+    where
+        T: Any + Send,
+        K: Hash + Eq + 'static,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(send_channel.junction_id(), self.id);
+
+        keyed::PartialPattern::new(
+            self.id,
+            (send_channel.queue(), ()),
+            vec![keyed::send_key_fn(send_channel.queue(), key)],
+            self.sender.clone(),
+            attributes,
+        )
     }
 }
 
@@ -204,8 +381,407 @@ impl Drop for Junction {
     /// associated `Controller` and join the control thread. Otherwise, no
     /// action is needed.
     fn drop(&mut self) {
-        if self.controller_handle.is_some() {
-            self.controller_handle.take().unwrap().stop();
+        if let Some(mut controller_handle) = self.controller_handle.take() {
+            // The `Controller` having stopped already is no reason to panic
+            // while dropping the `Junction`.
+            let _ = controller_handle.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+    use std::future::Future;
+    use std::sync::mpsc::{channel, Sender};
+    use std::sync::{Mutex, Weak};
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::executor::{InlineExecutor, Task};
+    use crate::selection::LeastRecentlyFired;
+
+    /// `Waker` that does nothing when woken.
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// `Waker` unparking the thread polling its `Future`.
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
         }
     }
+
+    /// Poll the given `Future` to completion on the current thread, parking
+    /// it while the `Future` is pending.
+    fn block_on<F>(future: F) -> F::Output
+    where
+        F: Future,
+    {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// `Spawner` polling every `Task` to completion in a new thread and
+    /// reporting each completed `Task` to the given `Sender`.
+    struct ThreadSpawner(Mutex<Sender<()>>);
+
+    impl Spawner for ThreadSpawner {
+        fn spawn(&self, task: Task) {
+            let completed = self.0.lock().unwrap().clone();
+
+            thread::spawn(move || {
+                block_on(task);
+                completed.send(()).unwrap();
+            });
+        }
+    }
+
+    /// `Spawner` polling every `Task` once, in a new thread.
+    struct PollOnceSpawner;
+
+    impl Spawner for PollOnceSpawner {
+        fn spawn(&self, mut task: Task) {
+            thread::spawn(move || {
+                let waker = Waker::from(Arc::new(NoopWaker));
+
+                let _ = task.as_mut().poll(&mut Context::from_waker(&waker));
+            });
+        }
+    }
+
+    #[test]
+    fn test_stop_twice() {
+        // Given:
+        let mut j = Junction::new();
+        let mut controller_handle = j.controller_handle().unwrap();
+
+        // When:
+        controller_handle.stop().unwrap();
+
+        // Then:
+        assert_eq!(Err(JunctionError::JunctionClosed), controller_handle.stop());
+    }
+
+    #[test]
+    fn test_configure_after_stop() {
+        // Given:
+        let mut j = Junction::new();
+
+        // When:
+        j.controller_handle().unwrap().stop().unwrap();
+
+        // Then:
+        assert_eq!(Err(JunctionError::JunctionClosed), j.seal());
+        assert_eq!(
+            Err(JunctionError::JunctionClosed),
+            j.set_selection_policy(LeastRecentlyFired::new())
+        );
+        assert_eq!(
+            Err(JunctionError::JunctionClosed),
+            j.set_panic_hook(|_, _| {})
+        );
+    }
+
+    #[test]
+    fn test_recv_timeout_without_partner() {
+        // Given:
+        let j = Junction::new();
+        let get = j.recv_channel::<i32>().unwrap();
+        let put = j.send_channel::<i32>().unwrap();
+        j.when_recv(&get).and(&put).then_do(|v| v).unwrap();
+
+        // When:
+        let result = get.recv_timeout(Duration::from_millis(10));
+
+        // Then:
+        assert_eq!(Err(JunctionError::Timeout), result);
+    }
+
+    #[test]
+    fn test_message_kept_after_withdrawal() {
+        // Given:
+        let j = Junction::new();
+        let get = j.recv_channel::<i32>().unwrap();
+        let put = j.send_channel::<i32>().unwrap();
+        j.when_recv(&get).and(&put).then_do(|v| v).unwrap();
+        assert_eq!(Err(JunctionError::WouldBlock), get.try_recv());
+
+        // When:
+        put.send(1).unwrap();
+
+        // Then:
+        assert_eq!(Ok(1), get.recv_timeout(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_try_recv_while_controller_busy() {
+        // Given:
+        let j = Junction::new();
+        let get = j.recv_channel::<i32>().unwrap();
+        let add = j.bidir_channel::<i32, i32>().unwrap();
+        let value = j.send_channel::<i32>().unwrap();
+        let busy = j.send_channel::<()>().unwrap();
+        j.when_recv(&get).and(&value).then_do(|v| v).unwrap();
+        j.when_bidir(&add)
+            .and(&value)
+            .then_do(|(a, (b, ()))| (a + b, ()))
+            .unwrap();
+
+        let (started_tx, started_rx) = channel();
+        let (release_tx, release_rx) = channel::<()>();
+        let release_rx = Arc::new(Mutex::new(release_rx));
+        j.when(&busy)
+            .executor(InlineExecutor)
+            .then_do(move |()| {
+                started_tx.send(()).unwrap();
+                release_rx.lock().unwrap().recv().unwrap();
+            })
+            .unwrap();
+
+        busy.send(()).unwrap();
+        started_rx.recv().unwrap();
+        value.send(1).unwrap();
+
+        // When:
+        let start = Instant::now();
+        let results = (get.try_recv(), add.try_send_recv(2));
+        let elapsed = start.elapsed();
+        release_tx.send(()).unwrap();
+
+        // Then:
+        assert_eq!(
+            (
+                Err(JunctionError::WouldBlock),
+                Err(JunctionError::WouldBlock)
+            ),
+            results
+        );
+        assert!(elapsed < Duration::from_secs(1));
+        assert_eq!(Ok(3), add.send_recv_timeout(2, Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_negated_channel_after_dropped_request() {
+        // Given:
+        let j = Junction::new();
+        let get = j.recv_channel::<i32>().unwrap();
+        let value = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&value)
+            .and_not_recv(&get)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+        let request = get.recv_async();
+        value.send(1).unwrap();
+
+        // When:
+        drop(request);
+
+        // Then:
+        assert_eq!(Ok(1), receiver.recv_timeout(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_recv_after_pattern_panicked() {
+        // Given:
+        let j = Junction::new();
+        let get = j.recv_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.set_panic_hook(move |pattern_id, _| sender.send(pattern_id).unwrap())
+            .unwrap();
+        let handle = j
+            .when_recv(&get)
+            .then_do(|| -> i32 { panic!("pattern panicked") })
+            .unwrap();
+
+        // When:
+        let result = get.recv();
+
+        // Then:
+        let pattern_id = handle.id();
+        assert_eq!(Err(JunctionError::PatternPanicked { pattern_id }), result);
+        assert_eq!(
+            Ok(pattern_id),
+            receiver.recv_timeout(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn test_recv_after_async_pattern_panicked() {
+        // Given:
+        let j = Junction::new();
+        let get = j.recv_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.set_panic_hook(move |pattern_id, _| sender.send(pattern_id).unwrap())
+            .unwrap();
+        let handle = j
+            .when_recv(&get)
+            .then_do_async(PollOnceSpawner, || async { panic!("pattern panicked") })
+            .unwrap();
+
+        // When:
+        let result = get.recv();
+
+        // Then:
+        let pattern_id = handle.id();
+        assert_eq!(Err(JunctionError::PatternPanicked { pattern_id }), result);
+        assert_eq!(
+            Ok(pattern_id),
+            receiver.recv_timeout(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn test_mut_pattern_serialized() {
+        // Given:
+        let j = Junction::new();
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        let running = AtomicUsize::new(0);
+        let mut sum = 0;
+        j.when(&values)
+            .then_do_mut(move |v| {
+                let overlapping = running.fetch_add(1, Ordering::SeqCst) > 0;
+                thread::sleep(Duration::from_millis(1));
+                sum += v;
+                running.fetch_sub(1, Ordering::SeqCst);
+
+                sender.send((overlapping, sum)).unwrap();
+            })
+            .unwrap();
+
+        // When:
+        for v in 1..=10 {
+            values.send(v).unwrap();
+        }
+
+        // Then:
+        let results: Vec<(bool, i32)> = (0..10)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        assert!(results.iter().all(|&(overlapping, _)| !overlapping));
+        assert_eq!(Some(&(false, 55)), results.last());
+    }
+
+    #[test]
+    fn test_controller_task_on_spawner() {
+        // Given:
+        let (sender, receiver) = channel();
+        let mut j = Junction::with_spawner(ThreadSpawner(Mutex::new(sender)));
+        let value = j.send_channel::<u32>().unwrap();
+        let get = j.recv_channel::<u32>().unwrap();
+        j.when_recv(&get)
+            .and(&value)
+            .then_do(|(v, ())| (v + 1, ()))
+            .unwrap();
+
+        // When:
+        value.send(1).unwrap();
+        let reply = block_on(get.recv_async());
+        let mut controller_handle = j.controller_handle().unwrap();
+        let stopped = controller_handle.stop();
+
+        // Then:
+        assert_eq!(Ok(2), reply);
+        assert_eq!(Ok(()), stopped);
+        assert!(controller_handle.thread().is_none());
+        assert_eq!(Ok(()), receiver.recv_timeout(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_channels_created_concurrently() {
+        // Given:
+        let j = Junction::new();
+        let (sender, receiver) = channel();
+
+        // When:
+        let channels: Vec<SendChannel<usize>> = thread::scope(|scope| {
+            let creators: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..50)
+                            .map(|_| j.send_channel::<usize>().unwrap())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            creators
+                .into_iter()
+                .flat_map(|creator| creator.join().unwrap())
+                .collect()
+        });
+        for (i, values) in channels.iter().enumerate() {
+            let sender = sender.clone();
+            j.when(values)
+                .then_do(move |v| sender.send(v).unwrap())
+                .unwrap();
+            values.send(i).unwrap();
+        }
+
+        // Then:
+        let ids: HashSet<ids::ChannelId> = channels.iter().map(SendChannel::id).collect();
+        assert_eq!(400, ids.len());
+        let mut received: Vec<usize> = (0..400)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        received.sort_unstable();
+        assert_eq!((0..400).collect::<Vec<_>>(), received);
+    }
+
+    #[test]
+    fn test_channels_created_in_pattern_body() {
+        // Given:
+        let j = Arc::new(Junction::new());
+        let create = j.send_channel::<usize>().unwrap();
+        let (sender, receiver) = channel();
+        let junction: Weak<Junction> = Arc::downgrade(&j);
+        j.when(&create)
+            .then_do(move |v| {
+                let j = junction.upgrade().unwrap();
+                let echo = j.send_channel::<usize>().unwrap();
+                let echo_id = echo.id();
+                let sender = sender.clone();
+                j.when(&echo)
+                    .then_do(move |v| sender.send((echo_id, v)).unwrap())
+                    .unwrap();
+                echo.send(v).unwrap();
+            })
+            .unwrap();
+
+        // When:
+        for v in 0..4 {
+            create.send(v).unwrap();
+        }
+
+        // Then:
+        let mut received: Vec<(ids::ChannelId, usize)> = (0..4)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        received.sort_unstable_by_key(|&(_, v)| v);
+        let ids: HashSet<ids::ChannelId> = received.iter().map(|&(id, _)| id).collect();
+        assert_eq!(4, ids.len());
+        assert!(!ids.contains(&create.id()));
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            received.iter().map(|&(_, v)| v).collect::<Vec<_>>()
+        );
+    }
 }
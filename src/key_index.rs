@@ -0,0 +1,290 @@
+//! Collection indexing items by key for each position of a keyed Join Pattern.
+//!
+//! Provides a new collection struct that, for every key, stores the items
+//! carrying this key separately for each position and keeps track of which
+//! keys have items available at every position, so that matching items can
+//! be found without inspecting items of any other key.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Items carrying the same key, one `VecDeque` for each position.
+struct Bucket<I> {
+    items: Vec<VecDeque<I>>,
+    /// Number of positions with at least one item.
+    filled: usize,
+    /// Sequence number under which the key became ready, if it is ready.
+    ready_since: Option<u64>,
+}
+
+/// Per-position key index.
+///
+/// Stores a `HashMap` of keys to one `VecDeque` of items for each position,
+/// in the order in which they were inserted. Items are expected to be
+/// inserted in increasing order for any given position, which allows them
+/// to be found in their `VecDeque` by binary search.
+///
+/// Whether a key is ready, i.e. has items at every position, is kept track of
+/// with the number of filled positions of its bucket, so that it is updated
+/// in constant time whenever an item is inserted or removed.
+pub(crate) struct KeyIndex<I> {
+    positions: usize,
+    buckets: HashMap<u64, Bucket<I>>,
+    /// Keys for which there is at least one item at every position, by the
+    /// sequence number under which they became ready.
+    ready: BTreeMap<u64, u64>,
+    next_sequence_number: u64,
+}
+
+impl<I> KeyIndex<I>
+where
+    I: Ord + Copy,
+{
+    pub(crate) fn new(positions: usize) -> KeyIndex<I> {
+        KeyIndex {
+            positions,
+            buckets: HashMap::new(),
+            ready: BTreeMap::new(),
+            next_sequence_number: 0,
+        }
+    }
+
+    /// Insert an item carrying the given key at the given position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is not less than the number of positions this
+    /// `KeyIndex` was created with.
+    pub(crate) fn insert(&mut self, position: usize, key: u64, item: I) {
+        let positions = self.positions;
+        let bucket = self.buckets.entry(key).or_insert_with(|| Bucket {
+            items: vec![VecDeque::new(); positions],
+            filled: 0,
+            ready_since: None,
+        });
+
+        if bucket.items[position].is_empty() {
+            bucket.filled += 1;
+        }
+
+        bucket.items[position].push_back(item);
+
+        if bucket.filled == positions && bucket.ready_since.is_none() {
+            bucket.ready_since = Some(self.next_sequence_number);
+            self.ready.insert(self.next_sequence_number, key);
+            self.next_sequence_number += 1;
+        }
+    }
+
+    /// Remove an item carrying the given key from the given position.
+    ///
+    /// Return `true` if the item was found and removed, `false` otherwise.
+    pub(crate) fn remove(&mut self, position: usize, key: u64, item: I) -> bool {
+        let bucket = match self.buckets.get_mut(&key) {
+            Some(bucket) => bucket,
+            None => return false,
+        };
+
+        let items = &mut bucket.items[position];
+
+        // Items are mostly consumed in the order they were inserted, so the
+        // item to remove is usually the first one.
+        let removed = if items.front() == Some(&item) {
+            items.pop_front().is_some()
+        } else {
+            match items.binary_search(&item) {
+                Ok(index) => items.remove(index).is_some(),
+                Err(_) => false,
+            }
+        };
+
+        if removed && items.is_empty() {
+            bucket.filled -= 1;
+
+            if let Some(sequence_number) = bucket.ready_since.take() {
+                self.ready.remove(&sequence_number);
+            }
+
+            if bucket.filled == 0 {
+                self.buckets.remove(&key);
+            }
+        }
+
+        removed
+    }
+
+    /// Return the keys that have items at every position, in the order in
+    /// which they became ready.
+    pub(crate) fn ready_keys(&self) -> impl Iterator<Item = u64> + '_ {
+        self.ready.values().cloned()
+    }
+
+    /// Return the items carrying the given key at the given position, in the
+    /// order in which they were inserted.
+    pub(crate) fn items(&self, position: usize, key: u64) -> Option<&VecDeque<I>> {
+        self.buckets.get(&key).map(|bucket| &bucket.items[position])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        // When:
+        let index: KeyIndex<usize> = KeyIndex::new(2);
+
+        // Then:
+        assert_eq!(None, index.ready_keys().next());
+    }
+
+    #[test]
+    fn test_not_ready_with_single_position() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(2);
+
+        // When:
+        index.insert(0, 42, 1);
+        index.insert(0, 42, 2);
+
+        // Then:
+        assert_eq!(None, index.ready_keys().next());
+    }
+
+    #[test]
+    fn test_ready_with_all_positions() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(2);
+
+        // When:
+        index.insert(0, 42, 1);
+        index.insert(1, 7, 2);
+        index.insert(1, 42, 3);
+
+        // Then:
+        assert_eq!(vec![42], index.ready_keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ready_keys_in_order() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(2);
+
+        // When:
+        index.insert(0, 42, 1);
+        index.insert(0, 7, 2);
+        index.insert(1, 7, 3);
+        index.insert(1, 42, 4);
+
+        // Then:
+        assert_eq!(vec![7, 42], index.ready_keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ready_again_after_other_keys() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(1);
+
+        index.insert(0, 42, 1);
+        index.insert(0, 7, 2);
+
+        // When:
+        index.remove(0, 42, 1);
+        index.insert(0, 42, 3);
+
+        // Then:
+        assert_eq!(vec![7, 42], index.ready_keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_items_in_order() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(1);
+
+        // When:
+        index.insert(0, 42, 1);
+        index.insert(0, 42, 5);
+
+        // Then:
+        let items: Vec<usize> = index.items(0, 42).unwrap().iter().cloned().collect();
+        assert_eq!(vec![1, 5], items);
+    }
+
+    #[test]
+    fn test_items_with_unknown_key() {
+        // Given:
+        let index: KeyIndex<usize> = KeyIndex::new(1);
+
+        // When:
+        let actual = index.items(0, 42);
+
+        // Then:
+        assert!(actual.is_none());
+    }
+
+    #[test]
+    fn test_remove_from_middle() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(1);
+
+        index.insert(0, 42, 1);
+        index.insert(0, 42, 3);
+        index.insert(0, 42, 5);
+
+        // When:
+        let removed = index.remove(0, 42, 3);
+
+        // Then:
+        assert!(removed);
+        let items: Vec<usize> = index.items(0, 42).unwrap().iter().cloned().collect();
+        assert_eq!(vec![1, 5], items);
+    }
+
+    #[test]
+    fn test_remove_unknown_item() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(1);
+
+        index.insert(0, 42, 1);
+
+        // When:
+        let removed_item = index.remove(0, 42, 2);
+        let removed_key = index.remove(0, 7, 1);
+
+        // Then:
+        assert!(!removed_item);
+        assert!(!removed_key);
+    }
+
+    #[test]
+    fn test_remove_last_item_unreadies_key() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(2);
+
+        index.insert(0, 42, 1);
+        index.insert(1, 42, 2);
+
+        // When:
+        index.remove(1, 42, 2);
+
+        // Then:
+        assert_eq!(None, index.ready_keys().next());
+        assert!(index.items(0, 42).is_some());
+    }
+
+    #[test]
+    fn test_remove_all_items_forgets_key() {
+        // Given:
+        let mut index: KeyIndex<usize> = KeyIndex::new(2);
+
+        index.insert(0, 42, 1);
+        index.insert(1, 42, 2);
+
+        // When:
+        index.remove(0, 42, 1);
+        index.remove(1, 42, 2);
+
+        // Then:
+        assert!(index.items(0, 42).is_none());
+    }
+}
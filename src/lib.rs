@@ -18,19 +18,19 @@
 //!     let cell = Junction::new();
 //!
 //!     // New channel to retrieve the value of the storage cell.
-//!     let get = cell.recv_channel::<i32>();
+//!     let get = cell.recv_channel::<i32>().unwrap();
 //!
 //!     // New channel to update the value of the storage cell.
-//!     let put = cell.send_channel::<i32>();
+//!     let put = cell.send_channel::<i32>().unwrap();
 //!
 //!     // New channel to swap the value of the storage cell for a new one and
 //!     // retrieve the value that was just replaced.
-//!     let swap = cell.bidir_channel::<i32, i32>();
+//!     let swap = cell.bidir_channel::<i32, i32>().unwrap();
 //!
 //!     // New channel that will actually carry the value so that at no point
 //!     // any given thread will have possession over it so concurrency issues
 //!     // are avoided by design.
-//!     let val = cell.send_channel::<i32>();
+//!     let val = cell.send_channel::<i32>().unwrap();
 //!
 //!     // Set up some clones of the above channels we can move over to the
 //!     // thread in which the function body of the Join Pattern will run.
@@ -51,7 +51,8 @@
 //!     cell.when(&put).and(&val).then_do(move |new, _old| {
 //!         println!(">> put-val pattern fired with new={}!", new);
 //!         put_val.send(new).unwrap();
-//!     });
+//!     })
+//!     .unwrap();
 //!
 //!     // Declare a new Join Pattern to retrieve the storage cell value. If
 //!     // both the get and val channel have sent a message, meaning someone
@@ -64,7 +65,8 @@
 //!         get_val.send(v.clone()).unwrap();
 //!
 //!         v
-//!     });
+//!     })
+//!     .unwrap();
 //!
 //!     // Declare a new Join Pattern to swap the storage cell value with a
 //!     // new one and retrieve the old. Essentially works like a combination
@@ -82,7 +84,8 @@
 //!         swap_val.send(new).unwrap();
 //!
 //!         old
-//!     });
+//!     })
+//!     .unwrap();
 //!
 //!     // Declare a new Join Pattern that mentions the same channel multiple
 //!     // times, so if the val channel has sent two messages they will be
@@ -92,7 +95,8 @@
 //!     cell.when(&val).and(&val).then_do(move |a, b| {
 //!         println!(">> val-val pattern fired with a={} and b={}!", a, b);
 //!         val_val.send(a + b).unwrap();
-//!     });
+//!     })
+//!     .unwrap();
 //!
 //!     /* End of the Join Pattern setup. */
 //!
@@ -132,14 +136,27 @@
 //! For more examples, visit the [`examples`](https://github.com/smueksch/rusty_junctions/tree/master/examples) folder in the [Rusty Junctions GitHub
 //! repository](https://github.com/smueksch/rusty_junctions).
 
-mod bag;
 pub mod channels;
 mod controller;
 mod counter;
+pub mod error;
+pub mod executor;
 mod function_transforms;
 mod inverted_index;
 mod junction;
+mod key_index;
+mod local_junction;
 pub mod patterns;
+mod queue;
+mod readiness;
+mod reply;
+mod sealable_map;
+pub mod selection;
+mod store;
+mod thread_pool;
 pub mod types;
 
+pub use error::JunctionError;
 pub use junction::Junction;
+pub use local_junction::LocalJunction;
+pub use thread_pool::ThreadPool;
@@ -0,0 +1,484 @@
+//! Single-threaded variant of the `Junction` that is driven manually.
+//!
+//! A `LocalJunction` has no control thread of its own. Instead, the
+//! `Packet`s sent on its channels are queued until the user calls `step` or
+//! `run_until_idle`, which hand them to the same `Controller` logic a
+//! `Junction` uses and run the functions of fired Join Patterns on the
+//! calling thread. This makes the order in which Join Patterns fire fully
+//! deterministic and allows a `LocalJunction` to be embedded in an existing
+//! event loop. As its messages never leave that thread either, the channels
+//! of a `LocalJunction` may carry messages that are not `Send`.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use super::channels::{BidirChannel, RecvChannel, SendChannel};
+use super::controller::Controller;
+use super::error::JunctionError;
+use super::executor::InlineExecutor;
+use super::patterns::unary::{BidirPartialPattern, RecvPartialPattern, SendPartialPattern};
+use super::patterns::{keyed, PatternAttributes};
+use super::queue::{queue, QueueReceiver, QueueSender};
+use super::selection::SelectionPolicy;
+use super::store::{AssertSend, SharedQueue, TypedQueue};
+use super::types::{ids, Packet, SealState};
+
+/// Struct managing the creation of new channels and Join Patterns, without a
+/// control thread in the background.
+///
+/// Works like a `Junction`, except that nothing happens until `step` or
+/// `run_until_idle` is called. The functions of fired Join Patterns are run
+/// directly on the thread calling these methods, unless an `Executor` was set
+/// for the Join Pattern.
+///
+/// Since the reply to a `RecvChannel` or `BidirChannel` only arrives once the
+/// `LocalJunction` has been driven, the blocking `recv` and `send_recv` must
+/// not be called on the thread driving it. Use `recv_async` and
+/// `send_recv_async` instead, and poll the returned `ReplyFuture` after
+/// driving the `LocalJunction`.
+///
+/// Unlike those of a `Junction`, the channels of a `LocalJunction` may carry
+/// messages that are not `Send`, such as `Rc`s. In turn, the `LocalJunction`
+/// is neither `Send` nor `Sync`, and a Join Pattern can only be given an
+/// `Executor` or declared with `then_do_async` if the messages of all of its
+/// channels are `Send`.
+pub struct LocalJunction {
+    id: ids::JunctionId,
+    controller: RefCell<Controller>,
+    sender: QueueSender<Packet>,
+    receiver: QueueReceiver<Packet>,
+    latest_channel_id: Cell<usize>,
+    seal_state: SealState,
+    /// Keeps the `LocalJunction`, and with it the messages of its channels,
+    /// on the thread it was created on.
+    thread_bound: PhantomData<*const ()>,
+}
+
+#[allow(clippy::new_without_default)]
+impl LocalJunction {
+    /// Create a new `LocalJunction`.
+    pub fn new() -> LocalJunction {
+        let (sender, receiver) = queue::<Packet>();
+
+        LocalJunction {
+            id: ids::JunctionId::new(),
+            controller: RefCell::new(Controller::new(Box::new(InlineExecutor))),
+            sender,
+            receiver,
+            latest_channel_id: Cell::new(0),
+            seal_state: SealState::default(),
+            thread_bound: PhantomData,
+        }
+    }
+
+    /// Handle the next pending `Packet`, if any.
+    ///
+    /// Handling a `Packet` may cause a Join Pattern to fire, in which case its
+    /// function is run before this method returns. Return `true` if a `Packet`
+    /// was handled, `false` if there was none pending.
+    pub fn step(&self) -> bool {
+        match self.receiver.try_recv() {
+            Some(packet) => {
+                self.controller.borrow_mut().handle_packet(packet);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handle pending `Packet`s until there are none left and return how many
+    /// were handled.
+    ///
+    /// This includes any `Packet`s sent by the functions of Join Patterns
+    /// fired along the way, so this method does not return for as long as
+    /// the Join Patterns keep sending new messages.
+    pub fn run_until_idle(&self) -> usize {
+        let mut handled = 0;
+
+        while self.step() {
+            handled += 1;
+        }
+
+        handled
+    }
+
+    /// Select which Join Pattern to fire with the given `SelectionPolicy`
+    /// whenever several are alive at the same time.
+    ///
+    /// Unlike with a `Junction`, the `SelectionPolicy` takes effect right
+    /// away, even for messages that have not been handled yet.
+    pub fn set_selection_policy<P>(&self, policy: P)
+    where
+        P: SelectionPolicy + 'static,
+    {
+        self.controller
+            .borrow_mut()
+            .set_selection_policy(Box::new(policy));
+    }
+
+    /// Report panics of the functions of fired Join Patterns to the given
+    /// hook.
+    ///
+    /// See `Junction::set_panic_hook` for details. Unlike with a `Junction`,
+    /// the hook takes effect right away.
+    pub fn set_panic_hook<H>(&self, hook: H)
+    where
+        H: Fn(ids::JoinPatternId, &(dyn Any + Send)) + Send + Sync + 'static,
+    {
+        self.controller.borrow_mut().set_panic_hook(Arc::new(hook));
+    }
+
+    /// Seal the `LocalJunction`, fixing its channels and Join Patterns.
+    ///
+    /// See `Junction::seal` for details. The channel-indexed collections of
+    /// the `Controller` only switch to dense arrays once the `LocalJunction`
+    /// has been driven past the point at which it was sealed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::JunctionClosed` if the request to seal the
+    /// `LocalJunction` could not be queued.
+    pub fn seal(&self) -> Result<(), JunctionError> {
+        self.seal_state.seal(|| {
+            self.sender
+                .send(Packet::SealRequest)
+                .map_err(|_| JunctionError::JunctionClosed)
+        })
+    }
+
+    /// Create and return a new `SendChannel` on this `LocalJunction`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::Sealed` if the `LocalJunction` has been sealed.
+    pub fn send_channel<T>(&self) -> Result<SendChannel<T>, JunctionError>
+    where
+        T: Any,
+    {
+        self.seal_state.unless_sealed(|| {
+            Ok(SendChannel::new(
+                self.new_channel_id(),
+                self.id,
+                self.sender.clone(),
+                LocalQueue::new,
+            ))
+        })
+    }
+
+    /// Create and return a new `RecvChannel` on this `LocalJunction`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::Sealed` if the `LocalJunction` has been sealed.
+    pub fn recv_channel<R>(&self) -> Result<RecvChannel<R>, JunctionError>
+    where
+        R: Any,
+    {
+        self.seal_state.unless_sealed(|| {
+            Ok(RecvChannel::new(
+                self.new_channel_id(),
+                self.id,
+                self.sender.clone(),
+                LocalQueue::new,
+            ))
+        })
+    }
+
+    /// Create and return a new `BidirChannel` on this `LocalJunction`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::Sealed` if the `LocalJunction` has been sealed.
+    pub fn bidir_channel<T, R>(&self) -> Result<BidirChannel<T, R>, JunctionError>
+    where
+        T: Any,
+        R: Any,
+    {
+        self.seal_state.unless_sealed(|| {
+            Ok(BidirChannel::new(
+                self.new_channel_id(),
+                self.id,
+                self.sender.clone(),
+                LocalQueue::new,
+            ))
+        })
+    }
+
+    /// Generate ID for a new channel.
+    fn new_channel_id(&self) -> ids::ChannelId {
+        let id = self.latest_channel_id.get();
+        self.latest_channel_id.set(id + 1);
+
+        ids::ChannelId::new(id)
+    }
+
+    /// Create new partial Join Pattern starting with a `SendChannel`.
+    ///
+    /// Should the supplied `SendChannel` not have been created by this
+    /// `LocalJunction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `LocalJunction` have been
+    /// sealed by then, it fails with `JunctionError::Sealed`.
+    pub fn when<T>(&self, send_channel: &SendChannel<T>) -> SendPartialPattern<T>
+    where
+        T: Any,
+    {
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(send_channel.junction_id(), self.id);
+
+        SendPartialPattern::new(
+            self.id,
+            send_channel.strip(),
+            self.sender.clone(),
+            attributes,
+        )
+    }
+
+    /// Create new partial Join Pattern starting with a `RecvChannel`.
+    ///
+    /// Should the supplied `RecvChannel` not have been created by this
+    /// `LocalJunction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `LocalJunction` have been
+    /// sealed by then, it fails with `JunctionError::Sealed`.
+    pub fn when_recv<R>(&self, recv_channel: &RecvChannel<R>) -> RecvPartialPattern<R>
+    where
+        R: Any,
+    {
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(recv_channel.junction_id(), self.id);
+
+        RecvPartialPattern::new(
+            self.id,
+            recv_channel.strip(),
+            self.sender.clone(),
+            attributes,
+        )
+    }
+
+    /// Create a new partial Join Pattern starting with a `BidirChannel`.
+    ///
+    /// Should the supplied `BidirChannel` not have been created by this
+    /// `LocalJunction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `LocalJunction` have been
+    /// sealed by then, it fails with `JunctionError::Sealed`.
+    pub fn when_bidir<T, R>(&self, bidir_channel: &BidirChannel<T, R>) -> BidirPartialPattern<T, R>
+    where
+        T: Any,
+        R: Any,
+    {
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(bidir_channel.junction_id(), self.id);
+
+        BidirPartialPattern::new(
+            self.id,
+            bidir_channel.strip(),
+            self.sender.clone(),
+            attributes,
+        )
+    }
+
+    /// Create new keyed partial Join Pattern starting with a `SendChannel`.
+    ///
+    /// See `Junction::when_keyed` for details.
+    ///
+    /// Should the supplied `SendChannel` not have been created by this
+    /// `LocalJunction`, completing the Join Pattern fails with
+    /// `JunctionError::ForeignChannel`. Should the `LocalJunction` have been
+    /// sealed by then, it fails with `JunctionError::Sealed`.
+    pub fn when_keyed<T, K, F>(
+        &self,
+        send_channel: &SendChannel<T>,
+        key: F,
+    ) -> keyed::PartialPattern<K, (SendChannel<T>, ())>
+    where
+        T: Any,
+        K: Hash + Eq + 'static,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        let mut attributes = PatternAttributes::new(self.seal_state.clone());
+        attributes.require_same_junction(send_channel.junction_id(), self.id);
+
+        keyed::PartialPattern::new(
+            self.id,
+            (send_channel.queue(), ()),
+            vec![keyed::send_key_fn(send_channel.queue(), key)],
+            self.sender.clone(),
+            attributes,
+        )
+    }
+}
+
+/// Queue of a channel on a `LocalJunction`, whose values need not be `Send`.
+struct LocalQueue<T>(TypedQueue<T>);
+
+impl<T> LocalQueue<T> {
+    fn new(queue: TypedQueue<T>) -> LocalQueue<T> {
+        LocalQueue(queue)
+    }
+}
+
+// SAFETY: The queue is only shared with the `Controller` of a
+// `LocalJunction`, which stays on the thread it was created on, and the
+// channels holding the queue are only `Send` if its values are. Values
+// leave that thread only through an `Executor` or `then_do_async`, both of
+// which require the messages of the Join Pattern to be `Send`.
+unsafe impl<T> Send for LocalQueue<T> {}
+unsafe impl<T> Sync for LocalQueue<T> {}
+
+impl<T> SharedQueue for LocalQueue<T>
+where
+    T: 'static,
+{
+    type Value = T;
+
+    fn typed(&self) -> &TypedQueue<T> {
+        &self.0
+    }
+
+    fn sendable(value: T) -> AssertSend<T> {
+        // SAFETY: See the `Send` implementation above.
+        unsafe { AssertSend::new(value) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::rc::Rc;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_fire_after_seal() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&values)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+
+        // When:
+        j.seal().unwrap();
+        values.send(1).unwrap();
+        values.send(2).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(vec![1, 2], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_channel_after_seal() {
+        // Given:
+        let j = LocalJunction::new();
+
+        // When:
+        j.seal().unwrap();
+
+        // Then:
+        assert_eq!(
+            Err(JunctionError::Sealed),
+            j.send_channel::<i32>().map(|_| ())
+        );
+        assert_eq!(
+            Err(JunctionError::Sealed),
+            j.recv_channel::<i32>().map(|_| ())
+        );
+        assert_eq!(
+            Err(JunctionError::Sealed),
+            j.bidir_channel::<i32, i32>().map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_pattern_after_seal() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<i32>().unwrap();
+
+        // When:
+        j.seal().unwrap();
+        let result = j.when(&values).then_do(|_| {});
+
+        // Then:
+        assert_eq!(Err(JunctionError::Sealed), result.map(|_| ()));
+    }
+
+    #[test]
+    fn test_pattern_started_before_seal() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<i32>().unwrap();
+        let partial = j.when(&values);
+
+        // When:
+        j.seal().unwrap();
+        let result = partial.then_do(|_| {});
+
+        // Then:
+        assert_eq!(Err(JunctionError::Sealed), result.map(|_| ()));
+    }
+
+    #[test]
+    fn test_negated_channel_after_dropped_request() {
+        // Given:
+        let j = LocalJunction::new();
+        let get = j.recv_channel::<i32>().unwrap();
+        let value = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&value)
+            .and_not_recv(&get)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+        let request = get.recv_async();
+        value.send(1).unwrap();
+        j.run_until_idle();
+        assert_eq!(None, receiver.try_iter().next());
+
+        // When:
+        drop(request);
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(vec![1], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_dropped_request_released() {
+        // Given:
+        let j = LocalJunction::new();
+        let swap = j.bidir_channel::<Arc<i32>, i32>().unwrap();
+        let value = Arc::new(1);
+
+        // When:
+        drop(swap.send_recv_async(value.clone()));
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(1, Arc::strong_count(&value));
+    }
+
+    #[test]
+    fn test_rc_message() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<Rc<i32>>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&values)
+            .then_do(move |v| sender.send(*v).unwrap())
+            .unwrap();
+        let value = Rc::new(1);
+
+        // When:
+        values.send(value.clone()).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(vec![1], receiver.try_iter().collect::<Vec<_>>());
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+}
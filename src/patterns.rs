@@ -1,15 +1,597 @@
 //! Structs to implement different types of `JoinPattern`s.
 
+// The n-ary partial Join Patterns spell out their full lists of channel types.
+#![allow(clippy::type_complexity)]
+
 use std::any::Any;
-use std::sync::mpsc::Sender;
-use std::thread;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, PoisonError};
 
 use super::channels::{
     BidirChannel, RecvChannel, SendChannel, StrippedBidirChannel, StrippedRecvChannel,
     StrippedSendChannel,
 };
+use super::error::JunctionError;
+use super::executor::{Executor, InlineExecutor, Spawner};
 use super::function_transforms;
-use super::types::{functions, ids, JoinPattern, Message, Packet};
+use super::queue::QueueSender;
+use super::reply::ReplySender;
+use super::store::{AssertSend, QueueRef, Retrieval};
+use super::types::{functions, ids, Packet, SealState};
+
+/*********************
+ * Full Join Pattern *
+ *********************/
+
+/// Handle to a Join Pattern that has been added to a `Junction`.
+///
+/// The handle allows the Join Pattern to be removed from its `Junction` at
+/// any later point. Dropping the handle leaves the Join Pattern in place.
+pub struct PatternHandle {
+    join_pattern_id: ids::JoinPatternId,
+    sender: QueueSender<Packet>,
+}
+
+impl PatternHandle {
+    /// Send request to add the given Join Pattern to the `Junction` under a
+    /// new `JoinPatternId` and return a handle to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error recorded while the Join Pattern was constructed, if
+    /// any, `JunctionError::Sealed` if the `Junction` has been sealed and
+    /// `JunctionError::JunctionClosed` if it was not possible to send the
+    /// request to add the Join Pattern to the `Junction`.
+    pub(crate) fn register(
+        join_pattern: JoinPattern,
+        sender: QueueSender<Packet>,
+    ) -> Result<PatternHandle, JunctionError> {
+        if let Some(error) = join_pattern.attributes.error {
+            return Err(error);
+        }
+
+        let seal_state = join_pattern.attributes.seal_state.clone();
+
+        seal_state.unless_sealed(|| {
+            let join_pattern_id = ids::JoinPatternId::new();
+
+            sender
+                .send(Packet::AddJoinPatternRequest {
+                    join_pattern_id,
+                    join_pattern: Box::new(join_pattern),
+                })
+                .map_err(|_| JunctionError::JunctionClosed)?;
+
+            Ok(PatternHandle {
+                join_pattern_id,
+                sender,
+            })
+        })
+    }
+
+    /// Return the ID of the Join Pattern within its `Junction`.
+    pub fn id(&self) -> ids::JoinPatternId {
+        self.join_pattern_id
+    }
+
+    /// Send request to remove the Join Pattern from its `Junction`.
+    ///
+    /// Once removed, the Join Pattern no longer fires. Messages that are
+    /// already available on its channels remain with the `Junction` and can
+    /// still be consumed by other Join Patterns.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::JunctionClosed` if the `Junction` has shut down.
+    pub fn remove(self) -> Result<(), JunctionError> {
+        self.sender
+            .send(Packet::RemoveJoinPatternRequest {
+                join_pattern_id: self.join_pattern_id,
+            })
+            .map_err(|_| JunctionError::JunctionClosed)
+    }
+}
+
+/// Attributes of a Join Pattern that are independent of its channels.
+///
+/// The attributes are collected while the partial Join Pattern is being
+/// constructed and passed on to the full Join Pattern once it is complete.
+#[derive(Default)]
+pub(crate) struct PatternAttributes {
+    guard: Option<functions::GuardBox>,
+    key: Option<functions::KeyBox>,
+    negated_channel_ids: Vec<ids::ChannelId>,
+    /// Number of times the Join Pattern may still fire, `None` if unlimited.
+    remaining_firings: Option<usize>,
+    /// Priority over other Join Patterns that are alive at the same time.
+    priority: i32,
+    /// `Sender` to notify the `Junction` that a firing has completed, `None`
+    /// if firings of the Join Pattern are not serialized.
+    completion_sender: Option<QueueSender<Packet>>,
+    /// `Executor` to run the function of the Join Pattern, `None` if the
+    /// `Executor` of the `Junction` should be used.
+    executor: Option<Arc<dyn Executor>>,
+    /// First error encountered while constructing the Join Pattern, which
+    /// prevents it from being added to the `Junction`.
+    error: Option<JunctionError>,
+    /// Whether the `Junction` of the Join Pattern has been sealed, which
+    /// prevents the Join Pattern from being added to it.
+    seal_state: SealState,
+}
+
+impl PatternAttributes {
+    /// Create the attributes of a new Join Pattern on the `Junction` with the
+    /// given `SealState`.
+    pub(crate) fn new(seal_state: SealState) -> PatternAttributes {
+        PatternAttributes {
+            seal_state,
+            ..PatternAttributes::default()
+        }
+    }
+
+    /// Add a guard, requiring it to hold in addition to any existing guard.
+    pub(crate) fn add_guard(&mut self, guard: functions::GuardBox) {
+        self.guard = match self.guard.take() {
+            Some(existing) => Some(Box::new(move |positions: &[usize]| {
+                existing(positions) && guard(positions)
+            })),
+            None => Some(guard),
+        };
+    }
+
+    /// Record a `JunctionError::ForeignChannel` unless a channel added to the
+    /// Join Pattern belongs to the `Junction` of the Join Pattern.
+    pub(crate) fn require_same_junction(
+        &mut self,
+        channel_junction_id: ids::JunctionId,
+        junction_id: ids::JunctionId,
+    ) {
+        if channel_junction_id != junction_id {
+            self.error.get_or_insert(JunctionError::ForeignChannel);
+        }
+    }
+
+    /// Add a channel that needs to hold no messages for the Join Pattern to fire.
+    pub(crate) fn add_negated_channel(&mut self, channel_id: ids::ChannelId) {
+        self.negated_channel_ids.push(channel_id);
+    }
+
+    /// Limit the number of times the Join Pattern may fire.
+    pub(crate) fn set_limit(&mut self, n: usize) {
+        self.remaining_firings = Some(n);
+    }
+
+    /// Set the priority of the Join Pattern.
+    pub(crate) fn set_priority(&mut self, priority: i32) {
+        self.priority = priority;
+    }
+
+    /// Set the `Executor` to run the function of the Join Pattern.
+    pub(crate) fn set_executor(&mut self, executor: Arc<dyn Executor>) {
+        self.executor = Some(executor);
+    }
+
+    /// Set the `Executor` to run the function of the Join Pattern, unless one
+    /// has already been set.
+    pub(crate) fn set_default_executor(&mut self, executor: Arc<dyn Executor>) {
+        self.executor.get_or_insert(executor);
+    }
+
+    /// Serialize the firings of the Join Pattern, notifying the `Junction`
+    /// through the given `Sender` whenever a firing has completed.
+    pub(crate) fn serialize(&mut self, completion_sender: QueueSender<Packet>) {
+        self.completion_sender = Some(completion_sender);
+    }
+
+    /// Set the function hashing the key of each message of a keyed Join
+    /// Pattern.
+    pub(crate) fn set_key(&mut self, key: functions::KeyBox) {
+        self.key = Some(key);
+    }
+}
+
+/// Full Join Pattern with any number and kind of channels.
+///
+/// Independent of the partial Join Pattern it was created from, the function
+/// body and guard of a full Join Pattern operate on the messages sent on its
+/// channels, in the same order as the channel IDs are stored.
+pub struct JoinPattern {
+    channel_ids: Vec<ids::ChannelId>,
+    f: functions::FnBox,
+    attributes: PatternAttributes,
+}
+
+impl JoinPattern {
+    pub(crate) fn new(
+        channel_ids: Vec<ids::ChannelId>,
+        f: functions::FnBox,
+        attributes: PatternAttributes,
+    ) -> JoinPattern {
+        JoinPattern {
+            channel_ids,
+            f,
+            attributes,
+        }
+    }
+
+    /// Return the IDs of all channels in this Join Pattern, in order.
+    pub(crate) fn channel_ids(&self) -> &[ids::ChannelId] {
+        &self.channel_ids
+    }
+
+    /// Return the guard of this Join Pattern, if it has one.
+    pub(crate) fn guard(&self) -> Option<&functions::GuardBox> {
+        self.attributes.guard.as_ref()
+    }
+
+    /// Return the IDs of all channels that need to hold no messages for this
+    /// Join Pattern to fire.
+    pub(crate) fn negated_channel_ids(&self) -> &[ids::ChannelId] {
+        &self.attributes.negated_channel_ids
+    }
+
+    /// Return the function hashing the key of each message, if this is a
+    /// keyed Join Pattern.
+    pub(crate) fn key(&self) -> Option<&functions::KeyBox> {
+        self.attributes.key.as_ref()
+    }
+
+    /// Return `true` if the Join Pattern may not fire any more times.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.attributes.remaining_firings == Some(0)
+    }
+
+    /// Return the priority of the Join Pattern over other alive ones.
+    pub(crate) fn priority(&self) -> i32 {
+        self.attributes.priority
+    }
+
+    /// Record that the Join Pattern has fired once more.
+    pub(crate) fn record_firing(&mut self) {
+        if let Some(remaining) = self.attributes.remaining_firings.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+    }
+
+    /// Return `true` if the firings of the Join Pattern are serialized.
+    pub(crate) fn is_serialized(&self) -> bool {
+        self.attributes.completion_sender.is_some()
+    }
+
+    /// Fire Join Pattern by handing associated function to an `Executor`.
+    ///
+    /// The messages consumed by the firing are taken out of the
+    /// `MessageStore` right away, one per channel of the Join Pattern, so
+    /// that only the function itself is left to the `Executor`.
+    ///
+    /// The function is run by the `Executor` of the Join Pattern if one was
+    /// set, or by the given default `Executor` of the `Junction` otherwise.
+    ///
+    /// The function is run under `catch_unwind`. Should it panic, whoever
+    /// waits on a reply from it is handed `JunctionError::PatternPanicked`,
+    /// and the payload of the panic is passed to the `PanicHook` of the
+    /// `Retrieval`, if any.
+    ///
+    /// If the firings of the Join Pattern are serialized, the `Junction` is
+    /// notified once the function has returned, or panicked, by a
+    /// `Packet::FiringCompleted` carrying the given `JoinPatternId`.
+    pub(crate) fn fire(
+        &self,
+        join_pattern_id: ids::JoinPatternId,
+        messages: &mut Retrieval,
+        default_executor: &dyn Executor,
+    ) {
+        let panic_hook = messages.panic_hook().cloned();
+        let job = (self.f)(messages);
+        let completion = self
+            .attributes
+            .completion_sender
+            .clone()
+            .map(|sender| FiringCompletion {
+                join_pattern_id,
+                sender,
+            });
+
+        let executor = self
+            .attributes
+            .executor
+            .as_deref()
+            .unwrap_or(default_executor);
+
+        executor.execute(Box::new(move || {
+            // Notify the `Junction` when dropped at the end of the job.
+            let _completion = completion;
+
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(job)) {
+                if let Some(hook) = panic_hook {
+                    hook(join_pattern_id, &*payload);
+                }
+            }
+        }));
+    }
+}
+
+/// Notification that a firing of a Join Pattern has completed.
+///
+/// The notification is sent to the `Junction` when the struct is dropped, so
+/// that it is also sent if the function body of the Join Pattern panics.
+struct FiringCompletion {
+    join_pattern_id: ids::JoinPatternId,
+    sender: QueueSender<Packet>,
+}
+
+impl Drop for FiringCompletion {
+    fn drop(&mut self) {
+        // The `Junction` may already have been shut down, in which case there
+        // is no one left to notify.
+        let _ = self.sender.send(Packet::FiringCompleted {
+            join_pattern_id: self.join_pattern_id,
+        });
+    }
+}
+
+/**********************************
+ * Shared Partial Pattern Methods *
+ **********************************/
+
+/// Implement the methods of a partial Join Pattern that only modify its
+/// `PatternAttributes`, which are the same for every kind of partial Join
+/// Pattern.
+///
+/// The partial Join Pattern needs to have `junction_id` and `attributes`
+/// fields.
+macro_rules! attribute_methods {
+    () => {
+        /// Require a `SendChannel` to hold no messages for the Join Pattern to fire.
+        ///
+        /// The Join Pattern is only considered alive while there are no messages
+        /// available on the supplied `SendChannel`. These messages are neither
+        /// consumed nor passed on to the function body.
+        ///
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_not<X>(mut self, send_channel: &SendChannel<X>) -> Self
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            self.attributes.add_negated_channel(send_channel.id());
+
+            self
+        }
+
+        /// Require a `RecvChannel` to hold no messages for the Join Pattern to fire.
+        ///
+        /// The Join Pattern is only considered alive while there are no messages
+        /// available on the supplied `RecvChannel`. These messages are neither
+        /// consumed nor passed on to the function body.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_not_recv<X>(mut self, recv_channel: &RecvChannel<X>) -> Self
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            self.attributes.add_negated_channel(recv_channel.id());
+
+            self
+        }
+
+        /// Require a `BidirChannel` to hold no messages for the Join Pattern to fire.
+        ///
+        /// The Join Pattern is only considered alive while there are no messages
+        /// available on the supplied `BidirChannel`. These messages are neither
+        /// consumed nor passed on to the function body.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_not_bidir<X, Y>(mut self, bidir_channel: &BidirChannel<X, Y>) -> Self
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            self.attributes.add_negated_channel(bidir_channel.id());
+
+            self
+        }
+
+        /// Limit the number of times the Join Pattern can fire.
+        ///
+        /// Once the Join Pattern has fired `n` times, it removes itself from the
+        /// `Junction`. A limit of zero results in a Join Pattern that never fires.
+        pub fn limit(mut self, n: usize) -> Self {
+            self.attributes.set_limit(n);
+
+            self
+        }
+
+        /// Set the priority of the Join Pattern, which is 0 unless set.
+        ///
+        /// Whenever several Join Patterns are alive at the same time, the one
+        /// with the highest priority fires. Only among those with equal
+        /// priority is the `SelectionPolicy` of the `Junction` consulted.
+        pub fn priority(mut self, priority: i32) -> Self {
+            self.attributes.set_priority(priority);
+
+            self
+        }
+
+        /// Run the function of the Join Pattern with the given `Executor`.
+        ///
+        /// Overrides the `Executor` of the `Junction` for this Join Pattern only.
+        /// As the `Executor` may run the function on any thread, this is only
+        /// possible if the messages of all channels of the Join Pattern are
+        /// `Send`.
+        pub fn executor<E>(mut self, executor: E) -> Self
+        where
+            Self: Send,
+            E: Executor + 'static,
+        {
+            self.attributes.set_executor(Arc::new(executor));
+
+            self
+        }
+    };
+}
+
+/// Implement the methods completing a partial Join Pattern with a function.
+///
+/// The signature of the function is given by the names and types of its
+/// arguments and its return type, if any, followed by closures transforming
+/// such a function, or an asynchronous one, into a `functions::FnBox`. The
+/// closures are handed the partial Join Pattern first, to take the queues of
+/// its channels from. The partial Join Pattern needs to have `sender` and
+/// `attributes` fields and a `register` method adding the full Join Pattern
+/// with a given `functions::FnBox` to the `Junction`.
+macro_rules! then_do_methods {
+    (
+        ($($arg:ident: $arg_ty:ty),*),
+        $transform:expr,
+        $transform_async:expr $(,)?
+    ) => {
+        then_do_methods!(($($arg: $arg_ty),*) -> (), $transform, $transform_async);
+    };
+    (
+        ($($arg:ident: $arg_ty:ty),*) -> $output:ty,
+        $transform:expr,
+        $transform_async:expr $(,)?
+    ) => {
+        /// Create full Join Pattern and send request to add it to `Junction`.
+        ///
+        /// Create a full Join Pattern by taking the channels that are part of
+        /// the partial pattern and adding a function to be executed when there
+        /// is at least one message sent on each channel. Attempt to add the
+        /// Join Pattern to the `Junction` after creation.
+        ///
+        /// Return a `PatternHandle` that can be used to remove the Join Pattern
+        /// from the `Junction` again later on.
+        ///
+        /// # Errors
+        ///
+        /// Returns `JunctionError::ForeignChannel` if any of the channels of
+        /// the Join Pattern was not created by its `Junction`,
+        /// `JunctionError::Sealed` if the `Junction` has been sealed, and
+        /// `JunctionError::JunctionClosed` if the `Junction` has shut down.
+        pub fn then_do<F>(self, f: F) -> Result<PatternHandle, JunctionError>
+        where
+            F: Fn($($arg_ty),*) -> $output + Send + Clone + 'static,
+        {
+            let f = ($transform)(&self, f);
+
+            self.register(f)
+        }
+
+        /// Create full Join Pattern with an asynchronous function and send
+        /// request to add it to `Junction`.
+        ///
+        /// Works like `then_do`, except that the function returns a `Future`,
+        /// which is handed to the given `Spawner` whenever the Join Pattern
+        /// fires. Replies to waiting callers, if any, are only sent back once
+        /// the `Future` has completed. Unless an `Executor` was set for the
+        /// Join Pattern, the function itself is called directly on the control
+        /// thread of the `Junction`, so it should do no more than create the
+        /// `Future`. As the `Spawner` may poll the `Future` on any thread, this
+        /// is only possible if the messages of all channels of the Join
+        /// Pattern are `Send`.
+        ///
+        /// # Errors
+        ///
+        /// Returns `JunctionError::ForeignChannel` if any of the channels of
+        /// the Join Pattern was not created by its `Junction`,
+        /// `JunctionError::Sealed` if the `Junction` has been sealed, and
+        /// `JunctionError::JunctionClosed` if the `Junction` has shut down.
+        pub fn then_do_async<S, F, Fut>(
+            mut self,
+            spawner: S,
+            f: F,
+        ) -> Result<PatternHandle, JunctionError>
+        where
+            Self: Send,
+            S: Spawner + 'static,
+            F: Fn($($arg_ty),*) -> Fut + Send + Clone + 'static,
+            Fut: Future<Output = $output> + Send + 'static,
+        {
+            self.attributes
+                .set_default_executor(Arc::new(InlineExecutor));
+
+            let f = ($transform_async)(&self, spawner, f);
+
+            self.register(f)
+        }
+
+        /// Create full Join Pattern with a stateful function and send request to
+        /// add it to `Junction`.
+        ///
+        /// Works like `then_do`, except that the function may mutate the values
+        /// it captures. Firings of the Join Pattern are serialized, i.e. the
+        /// Join Pattern is not considered alive while its function is running,
+        /// so the function is never called again before the previous call has
+        /// returned.
+        ///
+        /// # Errors
+        ///
+        /// Returns `JunctionError::ForeignChannel` if any of the channels of
+        /// the Join Pattern was not created by its `Junction`,
+        /// `JunctionError::Sealed` if the `Junction` has been sealed, and
+        /// `JunctionError::JunctionClosed` if the `Junction` has shut down.
+        pub fn then_do_mut<F>(mut self, f: F) -> Result<PatternHandle, JunctionError>
+        where
+            F: FnMut($($arg_ty),*) -> $output + Send + 'static,
+        {
+            let f = Arc::new(Mutex::new(f));
+
+            self.attributes.serialize(self.sender.clone());
+
+            self.then_do(move |$($arg),*| {
+                // Firings are serialized, so the lock is only ever poisoned by
+                // a previous call of the function that panicked.
+                let mut f = f.lock().unwrap_or_else(PoisonError::into_inner);
+
+                (*f)($($arg),*)
+            })
+        }
+
+        /// Create full Join Pattern that fires only once and send request to add
+        /// it to `Junction`.
+        ///
+        /// Works like `then_do`, except that the function is called at most once
+        /// and may therefore consume the values it captures. The Join Pattern
+        /// removes itself from the `Junction` once it has fired.
+        ///
+        /// # Errors
+        ///
+        /// Returns `JunctionError::ForeignChannel` if any of the channels of
+        /// the Join Pattern was not created by its `Junction`,
+        /// `JunctionError::Sealed` if the `Junction` has been sealed, and
+        /// `JunctionError::JunctionClosed` if the `Junction` has shut down.
+        pub fn then_do_once<F>(mut self, f: F) -> Result<PatternHandle, JunctionError>
+        where
+            F: FnOnce($($arg_ty),*) -> $output + Send + 'static,
+        {
+            let f = Arc::new(Mutex::new(Some(f)));
+
+            self.attributes.set_limit(1);
+
+            self.then_do(move |$($arg),*| {
+                let f = f.lock().unwrap().take().unwrap();
+
+                f($($arg),*)
+            })
+        }
+    };
+}
 
 /// Structs for Join Patterns with one channel.
 pub mod unary {
@@ -23,22 +605,25 @@ pub mod unary {
     pub struct SendPartialPattern<T> {
         junction_id: ids::JunctionId,
         send_channel: StrippedSendChannel<T>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T> SendPartialPattern<T>
     where
-        T: Any + Send,
+        T: Any,
     {
         pub(crate) fn new(
             junction_id: ids::JunctionId,
             send_channel: StrippedSendChannel<T>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> SendPartialPattern<T> {
             SendPartialPattern {
                 junction_id,
                 send_channel,
                 sender,
+                attributes,
             }
         }
 
@@ -47,30 +632,23 @@ pub mod unary {
         /// Create a new binary partial Join Pattern that starts with the current
         /// pattern and includes a new `SendChannel` after that.
         ///
-        /// # Panics
-        ///
-        /// Panics if the supplied `SendChannel` does not carry the same
-        /// `JunctionID` as this `SendPartialPattern`, i.e. has not been created by
-        /// and is associated with the same `Junction`.
-        pub fn and<U>(self, send_channel: &SendChannel<U>) -> binary::SendPartialPattern<T, U>
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<U>(mut self, send_channel: &SendChannel<U>) -> binary::SendPartialPattern<T, U>
         where
-            U: Any + Send,
+            U: Any,
         {
-            if send_channel.junction_id() == self.junction_id {
-                binary::SendPartialPattern::new(
-                    self.junction_id,
-                    self.send_channel,
-                    send_channel.strip(),
-                    self.sender,
-                )
-            } else {
-                panic!(
-                    "SendChannel and SendPartialPattern not associated \
-                     with same Junction! Please use a SendChannel created \
-                     using the same Junction as this partially complete Join \
-                     Pattern"
-                );
-            }
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            binary::SendPartialPattern::new(
+                self.junction_id,
+                self.send_channel,
+                send_channel.strip(),
+                self.sender,
+                self.attributes,
+            )
         }
 
         /// Create a binary partial Join Pattern with a send and receive channel.
@@ -78,29 +656,26 @@ pub mod unary {
         /// Create a new binary partial Join Pattern that starts with the current
         /// pattern and includes a new `RecvChannel` after that.
         ///
-        /// # Panics
-        ///
-        /// Panics if the supplied `RecvChannel` does not carry the same
-        /// `JunctionID` as this `SendPartialPattern`, i.e. has not been created by
-        /// and is associated with the same `Junction`.
-        pub fn and_recv<R>(self, recv_channel: &RecvChannel<R>) -> binary::RecvPartialPattern<T, R>
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<R>(
+            mut self,
+            recv_channel: &RecvChannel<R>,
+        ) -> binary::RecvPartialPattern<T, R>
         where
-            R: Any + Send,
+            R: Any,
         {
-            if recv_channel.junction_id() == self.junction_id {
-                binary::RecvPartialPattern::new(
-                    self.send_channel,
-                    recv_channel.strip(),
-                    self.sender,
-                )
-            } else {
-                panic!(
-                    "RecvChannel and SendPartialPattern not associated \
-                     with same Junction! Please use a RecvChannel created \
-                     using the same Junction as this partially complete Join \
-                     Pattern"
-                );
-            }
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            binary::RecvPartialPattern::new(
+                self.junction_id,
+                self.send_channel,
+                recv_channel.strip(),
+                self.sender,
+                self.attributes,
+            )
         }
 
         /// Create a binary partial Join Pattern with a send and bidirectional channel.
@@ -108,87 +683,70 @@ pub mod unary {
         /// Create a new binary partial Join Pattern that starts with the current
         /// pattern and includes a new `BidirChannel` after that.
         ///
-        /// # Panics
-        ///
-        /// Panics if the supplied `BidirChannel` does not carry the same
-        /// `JunctionID` as this `SendPartialPattern`, i.e. has not been created by
-        /// and is associated with the same `Junction`.
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
         pub fn and_bidir<U, R>(
-            self,
+            mut self,
             bidir_channel: &BidirChannel<U, R>,
         ) -> binary::BidirPartialPattern<T, U, R>
         where
-            U: Any + Send,
-            R: Any + Send,
+            U: Any,
+            R: Any,
         {
-            if bidir_channel.junction_id() == self.junction_id {
-                binary::BidirPartialPattern::new(
-                    self.send_channel,
-                    bidir_channel.strip(),
-                    self.sender,
-                )
-            } else {
-                panic!(
-                    "BidirChannel and SendPartialPattern not associated \
-                     with same Junction! Please use a BidirChannel created \
-                     using the same Junction as this partially complete Join \
-                     Pattern"
-                );
-            }
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            binary::BidirPartialPattern::new(
+                self.junction_id,
+                self.send_channel,
+                bidir_channel.strip(),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
+        /// Add a guard to the partial Join Pattern.
         ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
-        ///
-        /// # Panics
-        ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
         where
-            F: Fn(T) -> () + Send + Clone + 'static,
+            G: Fn(&T) -> bool + Send + 'static,
         {
-            let join_pattern = JoinPattern::UnarySend(SendJoinPattern::new(
-                self.send_channel.id(),
-                function_transforms::unary::transform_send(f),
-            ));
-
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
-        }
-    }
-
-    /// `SendChannel` full Join Pattern.
-    pub struct SendJoinPattern {
-        channel_id: ids::ChannelId,
-        f: functions::unary::FnBox,
-    }
+            self.attributes
+                .add_guard(function_transforms::unary::transform_send_guard(
+                    self.send_channel.queue(),
+                    g,
+                ));
 
-    impl SendJoinPattern {
-        pub(crate) fn new(
-            channel_id: ids::ChannelId,
-            f: functions::unary::FnBox,
-        ) -> SendJoinPattern {
-            SendJoinPattern { channel_id, f }
+            self
         }
 
-        /// Return the ID of the channel in this Join Pattern.
-        pub(crate) fn channel_id(&self) -> ids::ChannelId {
-            self.channel_id
+        attribute_methods!();
+
+        then_do_methods! {
+            (arg: T),
+            |this: &Self, f| {
+                function_transforms::unary::transform_send(this.send_channel.queue(), f)
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::unary::transform_send_async(
+                    this.send_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, arg: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(vec![self.send_channel.id()], f, self.attributes);
 
-            thread::spawn(move || {
-                (*f_clone)(arg);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 
@@ -198,80 +756,134 @@ pub mod unary {
 
     /// `RecvChannel` partial Join Pattern.
     pub struct RecvPartialPattern<R> {
+        junction_id: ids::JunctionId,
         recv_channel: StrippedRecvChannel<R>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<R> RecvPartialPattern<R>
     where
-        R: Any + Send,
+        R: Any,
     {
         pub(crate) fn new(
+            junction_id: ids::JunctionId,
             recv_channel: StrippedRecvChannel<R>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> RecvPartialPattern<R> {
             RecvPartialPattern {
+                junction_id,
                 recv_channel,
                 sender,
+                attributes,
             }
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
-        ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Create an n-ary partial Join Pattern with an additional send channel.
         ///
-        /// # Panics
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
         ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<X>(
+            mut self,
+            send_channel: &SendChannel<X>,
+        ) -> nary::PartialPattern<(RecvChannel<R>, (SendChannel<X>, ()))>
         where
-            F: Fn() -> R + Send + Clone + 'static,
+            X: Any,
         {
-            let join_pattern = JoinPattern::UnaryRecv(RecvJoinPattern::new(
-                self.recv_channel.id(),
-                function_transforms::unary::transform_recv(f),
-            ));
-
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (self.recv_channel.queue(), (send_channel.queue(), ())),
+                self.sender,
+                self.attributes,
+            )
         }
-    }
 
-    /// `RecvChannel` full Join Pattern.
-    ///
-    /// N.B.: While this struct appears to be a duplicate of `SendJoinPattern`
-    /// in terms of code, it is used to distinguish the capability of the
-    /// Join Pattern within the `Junction` through its type.
-    pub struct RecvJoinPattern {
-        channel_id: ids::ChannelId,
-        f: functions::unary::FnBox,
-    }
+        /// Create an n-ary partial Join Pattern with an additional receive channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<X>(
+            mut self,
+            recv_channel: &RecvChannel<X>,
+        ) -> nary::PartialPattern<(RecvChannel<R>, (RecvChannel<X>, ()))>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (self.recv_channel.queue(), (recv_channel.queue(), ())),
+                self.sender,
+                self.attributes,
+            )
+        }
 
-    impl RecvJoinPattern {
-        pub(crate) fn new(
-            channel_id: ids::ChannelId,
-            f: functions::unary::FnBox,
-        ) -> RecvJoinPattern {
-            RecvJoinPattern { channel_id, f }
+        /// Create an n-ary partial Join Pattern with an additional bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<X, Y>(
+            mut self,
+            bidir_channel: &BidirChannel<X, Y>,
+        ) -> nary::PartialPattern<(RecvChannel<R>, (BidirChannel<X, Y>, ()))>
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (self.recv_channel.queue(), (bidir_channel.queue(), ())),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of the channel in this Join Pattern.
-        pub(crate) fn channel_id(&self) -> ids::ChannelId {
-            self.channel_id
+        attribute_methods!();
+
+        then_do_methods! {
+            () -> R,
+            |this: &Self, f| {
+                function_transforms::unary::transform_recv(this.recv_channel.queue(), f)
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::unary::transform_recv_async(
+                    this.recv_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, return_sender: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(vec![self.recv_channel.id()], f, self.attributes);
 
-            thread::spawn(move || {
-                (*f_clone)(return_sender);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 
@@ -281,77 +893,154 @@ pub mod unary {
 
     /// Bidirectional channel partial Join Pattern.
     pub struct BidirPartialPattern<T, R> {
+        junction_id: ids::JunctionId,
         bidir_channel: StrippedBidirChannel<T, R>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T, R> BidirPartialPattern<T, R>
     where
-        T: Any + Send,
-        R: Any + Send,
+        T: Any,
+        R: Any,
     {
         pub(crate) fn new(
+            junction_id: ids::JunctionId,
             bidir_channel: StrippedBidirChannel<T, R>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> BidirPartialPattern<T, R> {
             BidirPartialPattern {
+                junction_id,
                 bidir_channel,
                 sender,
+                attributes,
             }
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
+        /// Create an n-ary partial Join Pattern with an additional send channel.
         ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
         ///
-        /// # Panics
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<X>(
+            mut self,
+            send_channel: &SendChannel<X>,
+        ) -> nary::PartialPattern<(BidirChannel<T, R>, (SendChannel<X>, ()))>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (self.bidir_channel.queue(), (send_channel.queue(), ())),
+                self.sender,
+                self.attributes,
+            )
+        }
+
+        /// Create an n-ary partial Join Pattern with an additional receive channel.
         ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<X>(
+            mut self,
+            recv_channel: &RecvChannel<X>,
+        ) -> nary::PartialPattern<(BidirChannel<T, R>, (RecvChannel<X>, ()))>
         where
-            F: Fn(T) -> R + Send + Clone + 'static,
+            X: Any,
         {
-            let join_pattern = JoinPattern::UnaryBidir(BidirJoinPattern::new(
-                self.bidir_channel.id(),
-                function_transforms::unary::transform_bidir(f),
-            ));
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (self.bidir_channel.queue(), (recv_channel.queue(), ())),
+                self.sender,
+                self.attributes,
+            )
+        }
 
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
+        /// Create an n-ary partial Join Pattern with an additional bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<X, Y>(
+            mut self,
+            bidir_channel: &BidirChannel<X, Y>,
+        ) -> nary::PartialPattern<(BidirChannel<T, R>, (BidirChannel<X, Y>, ()))>
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (self.bidir_channel.queue(), (bidir_channel.queue(), ())),
+                self.sender,
+                self.attributes,
+            )
         }
-    }
 
-    /// `BidirChannel` full Join Pattern.
-    pub struct BidirJoinPattern {
-        channel_id: ids::ChannelId,
-        f: functions::unary::FnBox,
-    }
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: Fn(&T) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::unary::transform_bidir_guard(
+                    self.bidir_channel.queue(),
+                    g,
+                ));
 
-    impl BidirJoinPattern {
-        pub(crate) fn new(
-            channel_id: ids::ChannelId,
-            f: functions::unary::FnBox,
-        ) -> BidirJoinPattern {
-            BidirJoinPattern { channel_id, f }
+            self
         }
 
-        /// Return the ID of the channel in this Join Pattern.
-        pub(crate) fn channel_id(&self) -> ids::ChannelId {
-            self.channel_id
+        attribute_methods!();
+
+        then_do_methods! {
+            (arg: T) -> R,
+            |this: &Self, f| {
+                function_transforms::unary::transform_bidir(this.bidir_channel.queue(), f)
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::unary::transform_bidir_async(
+                    this.bidir_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, arg_and_sender: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(vec![self.bidir_channel.id()], f, self.attributes);
 
-            thread::spawn(move || {
-                (*f_clone)(arg_and_sender);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 }
@@ -369,25 +1058,28 @@ pub mod binary {
         junction_id: ids::JunctionId,
         first_send_channel: StrippedSendChannel<T>,
         second_send_channel: StrippedSendChannel<U>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T, U> SendPartialPattern<T, U>
     where
-        T: Any + Send,
-        U: Any + Send,
+        T: Any,
+        U: Any,
     {
         pub(crate) fn new(
             junction_id: ids::JunctionId,
             first_send_channel: StrippedSendChannel<T>,
             second_send_channel: StrippedSendChannel<U>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> SendPartialPattern<T, U> {
             SendPartialPattern {
                 junction_id,
                 first_send_channel,
                 second_send_channel,
                 sender,
+                attributes,
             }
         }
 
@@ -396,31 +1088,27 @@ pub mod binary {
         /// Create a new ternary partial `JoinPattern` that starts with the current
         /// pattern and includes a new `SendChannel` after that.
         ///
-        /// # Panics
-        ///
-        /// Panics if the supplied `SendChannel` does not carry the same
-        /// `JunctionID` as this `SendPartialPattern`, i.e. has not been created by
-        /// and is associated with the same `Junction`.
-        pub fn and<V>(self, send_channel: &SendChannel<V>) -> ternary::SendPartialPattern<T, U, V>
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<V>(
+            mut self,
+            send_channel: &SendChannel<V>,
+        ) -> ternary::SendPartialPattern<T, U, V>
         where
-            V: Any + Send,
+            V: Any,
         {
-            if send_channel.junction_id() == self.junction_id {
-                ternary::SendPartialPattern::new(
-                    self.junction_id,
-                    self.first_send_channel,
-                    self.second_send_channel,
-                    send_channel.strip(),
-                    self.sender,
-                )
-            } else {
-                panic!(
-                    "SendChannel and SendPartialPattern not associated \
-                     with same Junction! Please use a SendChannel created \
-                     using the same Junction as this partially complete Join \
-                     Pattern"
-                );
-            }
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            ternary::SendPartialPattern::new(
+                self.junction_id,
+                self.first_send_channel,
+                self.second_send_channel,
+                send_channel.strip(),
+                self.sender,
+                self.attributes,
+            )
         }
 
         /// Create a ternary partial `JoinPattern` with two send and receive channel.
@@ -428,33 +1116,27 @@ pub mod binary {
         /// Create a new ternary partial `JoinPattern` that starts with the current
         /// pattern and includes a new `RecvChannel` after that.
         ///
-        /// # Panics
-        ///
-        /// Panics if the supplied `RecvChannel` does not carry the same
-        /// `JunctionID` as this `SendPartialPattern`, i.e. has not been created by
-        /// and is associated with the same `Junction`.
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
         pub fn and_recv<R>(
-            self,
+            mut self,
             recv_channel: &RecvChannel<R>,
         ) -> ternary::RecvPartialPattern<T, U, R>
         where
-            R: Any + Send,
+            R: Any,
         {
-            if recv_channel.junction_id() == self.junction_id {
-                ternary::RecvPartialPattern::new(
-                    self.first_send_channel,
-                    self.second_send_channel,
-                    recv_channel.strip(),
-                    self.sender,
-                )
-            } else {
-                panic!(
-                    "RecvChannel and SendPartialPattern not associated \
-                     with same Junction! Please use a RecvChannel created \
-                     using the same Junction as this partially complete Join \
-                     Pattern"
-                );
-            }
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            ternary::RecvPartialPattern::new(
+                self.junction_id,
+                self.first_send_channel,
+                self.second_send_channel,
+                recv_channel.strip(),
+                self.sender,
+                self.attributes,
+            )
         }
 
         /// Create a ternary partial `JoinPattern` with two send and bidirectional channel.
@@ -462,100 +1144,81 @@ pub mod binary {
         /// Create a new ternary partial Join Pattern that starts with the current
         /// pattern and includes a new `BidirChannel` after that.
         ///
-        /// # Panics
-        ///
-        /// Panics if the supplied `BidirChannel` does not carry the same
-        /// `JunctionID` as this `SendPartialPattern`, i.e. has not been created by
-        /// and is associated with the same `Junction`.
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
         pub fn and_bidir<V, R>(
-            self,
+            mut self,
             bidir_channel: &BidirChannel<V, R>,
         ) -> ternary::BidirPartialPattern<T, U, V, R>
         where
-            V: Any + Send,
-            R: Any + Send,
+            V: Any,
+            R: Any,
         {
-            if bidir_channel.junction_id() == self.junction_id {
-                ternary::BidirPartialPattern::new(
-                    self.first_send_channel,
-                    self.second_send_channel,
-                    bidir_channel.strip(),
-                    self.sender,
-                )
-            } else {
-                panic!(
-                    "BidirChannel and SendPartialPattern not associated \
-                     with same Junction! Please use a BidirChannel created \
-                     using the same Junction as this partially complete Join \
-                     Pattern"
-                );
-            }
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            ternary::BidirPartialPattern::new(
+                self.junction_id,
+                self.first_send_channel,
+                self.second_send_channel,
+                bidir_channel.strip(),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
-        ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Add a guard to the partial Join Pattern.
         ///
-        /// # Panics
-        ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
         where
-            F: Fn(T, U) -> () + Send + Clone + 'static,
+            G: Fn(&T, &U) -> bool + Send + 'static,
         {
-            let join_pattern = JoinPattern::BinarySend(SendJoinPattern::new(
-                self.first_send_channel.id(),
-                self.second_send_channel.id(),
-                function_transforms::binary::transform_send(f),
-            ));
-
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
-        }
-    }
-
-    /// `SendChannel` & `SendChannel` full Join Pattern.
-    pub struct SendJoinPattern {
-        first_send_channel_id: ids::ChannelId,
-        second_send_channel_id: ids::ChannelId,
-        f: functions::binary::FnBox,
-    }
-
-    impl SendJoinPattern {
-        pub(crate) fn new(
-            first_send_channel_id: ids::ChannelId,
-            second_send_channel_id: ids::ChannelId,
-            f: functions::binary::FnBox,
-        ) -> SendJoinPattern {
-            SendJoinPattern {
-                first_send_channel_id,
-                second_send_channel_id,
-                f,
-            }
+            self.attributes
+                .add_guard(function_transforms::binary::transform_send_guard(
+                    self.first_send_channel.queue(),
+                    self.second_send_channel.queue(),
+                    g,
+                ));
+
+            self
         }
 
-        /// Return the ID of the first `SendChannel` in this Join Pattern.
-        pub(crate) fn first_send_channel_id(&self) -> ids::ChannelId {
-            self.first_send_channel_id
-        }
+        attribute_methods!();
 
-        /// Return the ID of the second `SendChannel` in this Join Pattern.
-        pub(crate) fn second_send_channel_id(&self) -> ids::ChannelId {
-            self.second_send_channel_id
+        then_do_methods! {
+            (arg_1: T, arg_2: U),
+            |this: &Self, f| {
+                function_transforms::binary::transform_send(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    f,
+                )
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::binary::transform_send_async(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, arg_1: Message, arg_2: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(
+                vec![self.first_send_channel.id(), self.second_send_channel.id()],
+                f,
+                self.attributes,
+            );
 
-            thread::spawn(move || {
-                (*f_clone)(arg_1, arg_2);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 
@@ -565,96 +1228,175 @@ pub mod binary {
 
     /// `SendChannel` & `RecvChannel` partial Join Pattern.
     pub struct RecvPartialPattern<T, R> {
+        junction_id: ids::JunctionId,
         send_channel: StrippedSendChannel<T>,
         recv_channel: StrippedRecvChannel<R>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T, R> RecvPartialPattern<T, R>
     where
-        T: Any + Send,
-        R: Any + Send,
+        T: Any,
+        R: Any,
     {
         pub(crate) fn new(
+            junction_id: ids::JunctionId,
             send_channel: StrippedSendChannel<T>,
             recv_channel: StrippedRecvChannel<R>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> RecvPartialPattern<T, R> {
             RecvPartialPattern {
+                junction_id,
                 send_channel,
                 recv_channel,
                 sender,
+                attributes,
             }
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
+        /// Create an n-ary partial Join Pattern with an additional send channel.
         ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
         ///
-        /// # Panics
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<X>(
+            mut self,
+            send_channel: &SendChannel<X>,
+        ) -> nary::PartialPattern<(SendChannel<T>, (RecvChannel<R>, (SendChannel<X>, ())))>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.send_channel.queue(),
+                    (self.recv_channel.queue(), (send_channel.queue(), ())),
+                ),
+                self.sender,
+                self.attributes,
+            )
+        }
+
+        /// Create an n-ary partial Join Pattern with an additional receive channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
         ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<X>(
+            mut self,
+            recv_channel: &RecvChannel<X>,
+        ) -> nary::PartialPattern<(SendChannel<T>, (RecvChannel<R>, (RecvChannel<X>, ())))>
         where
-            F: Fn(T) -> R + Send + Clone + 'static,
+            X: Any,
         {
-            let join_pattern = JoinPattern::BinaryRecv(RecvJoinPattern::new(
-                self.send_channel.id(),
-                self.recv_channel.id(),
-                function_transforms::binary::transform_recv(f),
-            ));
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.send_channel.queue(),
+                    (self.recv_channel.queue(), (recv_channel.queue(), ())),
+                ),
+                self.sender,
+                self.attributes,
+            )
+        }
 
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
+        /// Create an n-ary partial Join Pattern with an additional bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<X, Y>(
+            mut self,
+            bidir_channel: &BidirChannel<X, Y>,
+        ) -> nary::PartialPattern<(SendChannel<T>, (RecvChannel<R>, (BidirChannel<X, Y>, ())))>
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.send_channel.queue(),
+                    (self.recv_channel.queue(), (bidir_channel.queue(), ())),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
-    }
 
-    /// `SendChannel` & `RecvChannel` full Join Pattern.
-    ///
-    /// N.B.: While this struct appears to be a duplicate of `SendJoinPattern`
-    /// in terms of code, it is used to distinguish the capability of the
-    /// Join Pattern within the `Junction` through its type.
-    pub struct RecvJoinPattern {
-        send_channel_id: ids::ChannelId,
-        recv_channel_id: ids::ChannelId,
-        f: functions::binary::FnBox,
-    }
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: Fn(&T) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::binary::transform_recv_guard(
+                    self.send_channel.queue(),
+                    g,
+                ));
 
-    impl RecvJoinPattern {
-        pub(crate) fn new(
-            send_channel_id: ids::ChannelId,
-            recv_channel_id: ids::ChannelId,
-            f: functions::binary::FnBox,
-        ) -> RecvJoinPattern {
-            RecvJoinPattern {
-                send_channel_id,
-                recv_channel_id,
-                f,
-            }
+            self
         }
 
-        /// Return the ID of the `SendChannel` in this Join Pattern.
-        pub(crate) fn send_channel_id(&self) -> ids::ChannelId {
-            self.send_channel_id
-        }
+        attribute_methods!();
 
-        /// Return the ID of the `RecvChannel` in this Join Pattern.
-        pub(crate) fn recv_channel_id(&self) -> ids::ChannelId {
-            self.recv_channel_id
+        then_do_methods! {
+            (arg: T) -> R,
+            |this: &Self, f| {
+                function_transforms::binary::transform_recv(
+                    this.send_channel.queue(),
+                    this.recv_channel.queue(),
+                    f,
+                )
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::binary::transform_recv_async(
+                    this.send_channel.queue(),
+                    this.recv_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, msg: Message, return_sender: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(
+                vec![self.send_channel.id(), self.recv_channel.id()],
+                f,
+                self.attributes,
+            );
 
-            thread::spawn(move || {
-                (*f_clone)(msg, return_sender);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 
@@ -664,98 +1406,185 @@ pub mod binary {
 
     /// `SendChannel` & `BidirChannel` partial Join Pattern.
     pub struct BidirPartialPattern<T, U, R> {
+        junction_id: ids::JunctionId,
         send_channel: StrippedSendChannel<T>,
         bidir_channel: StrippedBidirChannel<U, R>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T, U, R> BidirPartialPattern<T, U, R>
     where
-        T: Any + Send,
-        U: Any + Send,
-        R: Any + Send,
+        T: Any,
+        U: Any,
+        R: Any,
     {
         pub(crate) fn new(
+            junction_id: ids::JunctionId,
             send_channel: StrippedSendChannel<T>,
             bidir_channel: StrippedBidirChannel<U, R>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> BidirPartialPattern<T, U, R> {
             BidirPartialPattern {
+                junction_id,
                 send_channel,
                 bidir_channel,
                 sender,
+                attributes,
             }
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
-        ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Create an n-ary partial Join Pattern with an additional send channel.
         ///
-        /// # Panics
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
         ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<X>(
+            mut self,
+            send_channel: &SendChannel<X>,
+        ) -> nary::PartialPattern<(SendChannel<T>, (BidirChannel<U, R>, (SendChannel<X>, ())))>
         where
-            F: Fn(T, U) -> R + Send + Clone + 'static,
+            X: Any,
         {
-            let join_pattern = JoinPattern::BinaryBidir(BidirJoinPattern::new(
-                self.send_channel.id(),
-                self.bidir_channel.id(),
-                function_transforms::binary::transform_bidir(f),
-            ));
-
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.send_channel.queue(),
+                    (self.bidir_channel.queue(), (send_channel.queue(), ())),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
-    }
 
-    /// `SendChannel` & `BidirChannel` full Join Pattern.
-    pub struct BidirJoinPattern {
-        send_channel_id: ids::ChannelId,
-        bidir_channel_id: ids::ChannelId,
-        f: functions::binary::FnBox,
-    }
+        /// Create an n-ary partial Join Pattern with an additional receive channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<X>(
+            mut self,
+            recv_channel: &RecvChannel<X>,
+        ) -> nary::PartialPattern<(SendChannel<T>, (BidirChannel<U, R>, (RecvChannel<X>, ())))>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.send_channel.queue(),
+                    (self.bidir_channel.queue(), (recv_channel.queue(), ())),
+                ),
+                self.sender,
+                self.attributes,
+            )
+        }
 
-    impl BidirJoinPattern {
-        pub(crate) fn new(
-            send_channel_id: ids::ChannelId,
-            bidir_channel_id: ids::ChannelId,
-            f: functions::binary::FnBox,
-        ) -> BidirJoinPattern {
-            BidirJoinPattern {
-                send_channel_id,
-                bidir_channel_id,
-                f,
-            }
+        /// Create an n-ary partial Join Pattern with an additional bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<X, Y>(
+            mut self,
+            bidir_channel: &BidirChannel<X, Y>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (BidirChannel<U, R>, (BidirChannel<X, Y>, ())),
+        )>
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.send_channel.queue(),
+                    (self.bidir_channel.queue(), (bidir_channel.queue(), ())),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of the `SendChannel` in this Join Pattern.
-        pub(crate) fn send_channel_id(&self) -> ids::ChannelId {
-            self.send_channel_id
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: Fn(&T, &U) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::binary::transform_bidir_guard(
+                    self.send_channel.queue(),
+                    self.bidir_channel.queue(),
+                    g,
+                ));
+
+            self
         }
 
-        /// Return the ID of the `BidirChannel` in this Join Pattern.
-        pub(crate) fn bidir_channel_id(&self) -> ids::ChannelId {
-            self.bidir_channel_id
+        attribute_methods!();
+
+        then_do_methods! {
+            (arg_1: T, arg_2: U) -> R,
+            |this: &Self, f| {
+                function_transforms::binary::transform_bidir(
+                    this.send_channel.queue(),
+                    this.bidir_channel.queue(),
+                    f,
+                )
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::binary::transform_bidir_async(
+                    this.send_channel.queue(),
+                    this.bidir_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, arg_1: Message, arg_2_and_sender: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(
+                vec![self.send_channel.id(), self.bidir_channel.id()],
+                f,
+                self.attributes,
+            );
 
-            thread::spawn(move || {
-                (*f_clone)(arg_1, arg_2_and_sender);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 }
 
-/// Structs for Join Patterns with two channels.
+/// Structs for Join Patterns with three channels.
 pub mod ternary {
     use super::*;
 
@@ -769,21 +1598,23 @@ pub mod ternary {
         first_send_channel: StrippedSendChannel<T>,
         second_send_channel: StrippedSendChannel<U>,
         third_send_channel: StrippedSendChannel<V>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T, U, V> SendPartialPattern<T, U, V>
     where
-        T: Any + Send,
-        U: Any + Send,
-        V: Any + Send,
+        T: Any,
+        U: Any,
+        V: Any,
     {
         pub(crate) fn new(
             junction_id: ids::JunctionId,
             first_send_channel: StrippedSendChannel<T>,
             second_send_channel: StrippedSendChannel<U>,
             third_send_channel: StrippedSendChannel<V>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> SendPartialPattern<T, U, V> {
             SendPartialPattern {
                 junction_id,
@@ -791,82 +1622,177 @@ pub mod ternary {
                 second_send_channel,
                 third_send_channel,
                 sender,
+                attributes,
             }
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
+        /// Create an n-ary partial Join Pattern with four send channels.
         ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
         ///
-        /// # Panics
-        ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<W>(
+            mut self,
+            send_channel: &SendChannel<W>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (SendChannel<V>, (SendChannel<W>, ()))),
+        )>
         where
-            F: Fn(T, U, V) -> () + Send + Clone + 'static,
+            W: Any,
         {
-            let join_pattern = JoinPattern::TernarySend(SendJoinPattern::new(
-                self.first_send_channel.id(),
-                self.second_send_channel.id(),
-                self.third_send_channel.id(),
-                function_transforms::ternary::transform_send(f),
-            ));
-
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.third_send_channel.queue(), (send_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
-    }
-
-    /// Three `SendChannel` full `JoinPattern`.
-    pub struct SendJoinPattern {
-        first_send_channel_id: ids::ChannelId,
-        second_send_channel_id: ids::ChannelId,
-        third_send_channel_id: ids::ChannelId,
-        f: functions::ternary::FnBox,
-    }
 
-    impl SendJoinPattern {
-        pub(crate) fn new(
-            first_send_channel_id: ids::ChannelId,
-            second_send_channel_id: ids::ChannelId,
-            third_send_channel_id: ids::ChannelId,
-            f: functions::ternary::FnBox,
-        ) -> SendJoinPattern {
-            SendJoinPattern {
-                first_send_channel_id,
-                second_send_channel_id,
-                third_send_channel_id,
-                f,
-            }
+        /// Create an n-ary partial Join Pattern with three send and receive channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<X>(
+            mut self,
+            recv_channel: &RecvChannel<X>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (SendChannel<V>, (RecvChannel<X>, ()))),
+        )>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.third_send_channel.queue(), (recv_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of the first `SendChannel` in this Join Pattern.
-        pub(crate) fn first_send_channel_id(&self) -> ids::ChannelId {
-            self.first_send_channel_id
+        /// Create an n-ary partial Join Pattern with three send and bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<X, Y>(
+            mut self,
+            bidir_channel: &BidirChannel<X, Y>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (SendChannel<V>, (BidirChannel<X, Y>, ()))),
+        )>
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.third_send_channel.queue(), (bidir_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of the second `SendChannel` in this Join Pattern.
-        pub(crate) fn second_send_channel_id(&self) -> ids::ChannelId {
-            self.second_send_channel_id
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: Fn(&T, &U, &V) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::ternary::transform_send_guard(
+                    self.first_send_channel.queue(),
+                    self.second_send_channel.queue(),
+                    self.third_send_channel.queue(),
+                    g,
+                ));
+
+            self
         }
 
-        /// Return the ID of the third `SendChannel` in this Join Pattern.
-        pub(crate) fn third_send_channel_id(&self) -> ids::ChannelId {
-            self.third_send_channel_id
+        attribute_methods!();
+
+        then_do_methods! {
+            (arg_1: T, arg_2: U, arg_3: V),
+            |this: &Self, f| {
+                function_transforms::ternary::transform_send(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    this.third_send_channel.queue(),
+                    f,
+                )
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::ternary::transform_send_async(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    this.third_send_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, arg_1: Message, arg_2: Message, arg_3: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(
+                vec![
+                    self.first_send_channel.id(),
+                    self.second_send_channel.id(),
+                    self.third_send_channel.id(),
+                ],
+                f,
+                self.attributes,
+            );
 
-            thread::spawn(move || {
-                (*f_clone)(arg_1, arg_2, arg_3);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 
@@ -876,109 +1802,204 @@ pub mod ternary {
 
     /// Two `SendChannel` & `RecvChannel` partial Join Pattern.
     pub struct RecvPartialPattern<T, U, R> {
+        junction_id: ids::JunctionId,
         first_send_channel: StrippedSendChannel<T>,
         second_send_channel: StrippedSendChannel<U>,
         recv_channel: StrippedRecvChannel<R>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T, U, R> RecvPartialPattern<T, U, R>
     where
-        T: Any + Send,
-        U: Any + Send,
-        R: Any + Send,
+        T: Any,
+        U: Any,
+        R: Any,
     {
         pub(crate) fn new(
+            junction_id: ids::JunctionId,
             first_send_channel: StrippedSendChannel<T>,
             second_send_channel: StrippedSendChannel<U>,
             recv_channel: StrippedRecvChannel<R>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> RecvPartialPattern<T, U, R> {
             RecvPartialPattern {
+                junction_id,
                 first_send_channel,
                 second_send_channel,
                 recv_channel,
                 sender,
+                attributes,
             }
         }
 
-        /// Create full Join Pattern and send request to add it to `Junction`.
-        ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Create an n-ary partial Join Pattern with an additional send channel.
         ///
-        /// # Panics
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
         ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<X>(
+            mut self,
+            send_channel: &SendChannel<X>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (RecvChannel<R>, (SendChannel<X>, ()))),
+        )>
         where
-            F: Fn(T, U) -> R + Send + Clone + 'static,
+            X: Any,
         {
-            let join_pattern = JoinPattern::TernaryRecv(RecvJoinPattern::new(
-                self.first_send_channel.id(),
-                self.second_send_channel.id(),
-                self.recv_channel.id(),
-                function_transforms::ternary::transform_recv(f),
-            ));
-
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.recv_channel.queue(), (send_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
-    }
 
-    /// Two `SendChannel` & `RecvChannel` full `JoinPattern`.
-    ///
-    /// N.B.: While this struct appears to be a duplicate of `SendJoinPattern`
-    /// in terms of code, it is used to distinguish the capability of the
-    /// Join Pattern within the `Junction` through its type.
-    pub struct RecvJoinPattern {
-        first_send_channel_id: ids::ChannelId,
-        second_send_channel_id: ids::ChannelId,
-        recv_channel_id: ids::ChannelId,
-        f: functions::ternary::FnBox,
-    }
-
-    impl RecvJoinPattern {
-        pub(crate) fn new(
-            first_send_channel_id: ids::ChannelId,
-            second_send_channel_id: ids::ChannelId,
-            recv_channel_id: ids::ChannelId,
-            f: functions::ternary::FnBox,
-        ) -> RecvJoinPattern {
-            RecvJoinPattern {
-                first_send_channel_id,
-                second_send_channel_id,
-                recv_channel_id,
-                f,
-            }
+        /// Create an n-ary partial Join Pattern with an additional receive channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<X>(
+            mut self,
+            recv_channel: &RecvChannel<X>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (RecvChannel<R>, (RecvChannel<X>, ()))),
+        )>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.recv_channel.queue(), (recv_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of first `SendChannel` in this `JoinPattern`.
-        pub(crate) fn first_send_channel_id(&self) -> ids::ChannelId {
-            self.first_send_channel_id
+        /// Create an n-ary partial Join Pattern with an additional bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<X, Y>(
+            mut self,
+            bidir_channel: &BidirChannel<X, Y>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (RecvChannel<R>, (BidirChannel<X, Y>, ()))),
+        )>
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.recv_channel.queue(), (bidir_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of second `SendChannel` in this `JoinPattern`.
-        pub(crate) fn second_send_channel_id(&self) -> ids::ChannelId {
-            self.second_send_channel_id
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: Fn(&T, &U) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::ternary::transform_recv_guard(
+                    self.first_send_channel.queue(),
+                    self.second_send_channel.queue(),
+                    g,
+                ));
+
+            self
         }
 
-        /// Return the ID of the `RecvChannel` in this `JoinPattern`.
-        pub(crate) fn recv_channel_id(&self) -> ids::ChannelId {
-            self.recv_channel_id
+        attribute_methods!();
+
+        then_do_methods! {
+            (arg_1: T, arg_2: U) -> R,
+            |this: &Self, f| {
+                function_transforms::ternary::transform_recv(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    this.recv_channel.queue(),
+                    f,
+                )
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::ternary::transform_recv_async(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    this.recv_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
         }
 
-        /// Fire `JoinPattern` by running associated function in separate thread.
-        pub(crate) fn fire(&self, arg_1: Message, arg_2: Message, return_sender: Message) {
-            let f_clone = self.f.clone();
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(
+                vec![
+                    self.first_send_channel.id(),
+                    self.second_send_channel.id(),
+                    self.recv_channel.id(),
+                ],
+                f,
+                self.attributes,
+            );
 
-            thread::spawn(move || {
-                (*f_clone)(arg_1, arg_2, return_sender);
-            });
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
 
@@ -988,106 +2009,1222 @@ pub mod ternary {
 
     /// `SendChannel` & `BidirChannel` partial Join Pattern.
     pub struct BidirPartialPattern<T, U, V, R> {
+        junction_id: ids::JunctionId,
         first_send_channel: StrippedSendChannel<T>,
         second_send_channel: StrippedSendChannel<U>,
         bidir_channel: StrippedBidirChannel<V, R>,
-        sender: Sender<Packet>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
     }
 
     impl<T, U, V, R> BidirPartialPattern<T, U, V, R>
     where
-        T: Any + Send,
-        U: Any + Send,
-        V: Any + Send,
-        R: Any + Send,
+        T: Any,
+        U: Any,
+        V: Any,
+        R: Any,
     {
         pub(crate) fn new(
+            junction_id: ids::JunctionId,
             first_send_channel: StrippedSendChannel<T>,
             second_send_channel: StrippedSendChannel<U>,
             bidir_channel: StrippedBidirChannel<V, R>,
-            sender: Sender<Packet>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
         ) -> BidirPartialPattern<T, U, V, R> {
             BidirPartialPattern {
+                junction_id,
                 first_send_channel,
                 second_send_channel,
                 bidir_channel,
                 sender,
+                attributes,
             }
         }
 
-        /// Create full `JoinPattern` and send request to add it to `Junction`.
+        /// Create an n-ary partial Join Pattern with an additional send channel.
         ///
-        /// Create a full Join Pattern by taking the channels that are part of
-        /// the partial pattern and adding a function to be executed when there
-        /// is at least one message sent on each channel. Attempt to add the
-        /// Join Pattern to the `Junction` after creation.
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<X>(
+            mut self,
+            send_channel: &SendChannel<X>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (BidirChannel<V, R>, (SendChannel<X>, ()))),
+        )>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.bidir_channel.queue(), (send_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
+        }
+
+        /// Create an n-ary partial Join Pattern with an additional receive channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<X>(
+            mut self,
+            recv_channel: &RecvChannel<X>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (SendChannel<U>, (BidirChannel<V, R>, (RecvChannel<X>, ()))),
+        )>
+        where
+            X: Any,
+        {
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.bidir_channel.queue(), (recv_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
+        }
+
+        /// Create an n-ary partial Join Pattern with an additional bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that. See the `nary`
+        /// module for how the values of the channels are passed on.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<X, Y>(
+            mut self,
+            bidir_channel: &BidirChannel<X, Y>,
+        ) -> nary::PartialPattern<(
+            SendChannel<T>,
+            (
+                SendChannel<U>,
+                (BidirChannel<V, R>, (BidirChannel<X, Y>, ())),
+            ),
+        )>
+        where
+            X: Any,
+            Y: Any,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            nary::PartialPattern::new(
+                self.junction_id,
+                (
+                    self.first_send_channel.queue(),
+                    (
+                        self.second_send_channel.queue(),
+                        (self.bidir_channel.queue(), (bidir_channel.queue(), ())),
+                    ),
+                ),
+                self.sender,
+                self.attributes,
+            )
+        }
+
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: Fn(&T, &U, &V) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::ternary::transform_bidir_guard(
+                    self.first_send_channel.queue(),
+                    self.second_send_channel.queue(),
+                    self.bidir_channel.queue(),
+                    g,
+                ));
+
+            self
+        }
+
+        attribute_methods!();
+
+        then_do_methods! {
+            (arg_1: T, arg_2: U, arg_3: V) -> R,
+            |this: &Self, f| {
+                function_transforms::ternary::transform_bidir(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    this.bidir_channel.queue(),
+                    f,
+                )
+            },
+            |this: &Self, spawner, f| {
+                function_transforms::ternary::transform_bidir_async(
+                    this.first_send_channel.queue(),
+                    this.second_send_channel.queue(),
+                    this.bidir_channel.queue(),
+                    spawner,
+                    f,
+                )
+            },
+        }
+
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let join_pattern = JoinPattern::new(
+                vec![
+                    self.first_send_channel.id(),
+                    self.second_send_channel.id(),
+                    self.bidir_channel.id(),
+                ],
+                f,
+                self.attributes,
+            );
+
+            PatternHandle::register(join_pattern, self.sender)
+        }
+    }
+}
+
+/// Structs for Join Patterns with an arbitrary number and kind of channels.
+///
+/// Once a Join Pattern grows beyond three channels, or contains a `RecvChannel`
+/// or `BidirChannel` anywhere but in its last position, it is constructed with
+/// the structs in this module. The channels of such a pattern are tracked as a
+/// right-nested list of pairs terminated by `()`, i.e. in the style of a
+/// heterogeneous list, and so are the values passed to and returned from its
+/// function body:
+///
+/// * The function body receives a list of the values of all `SendChannel`s
+///   and `BidirChannel`s in the pattern.
+/// * The function body returns a list of the replies for all `RecvChannel`s
+///   and `BidirChannel`s in the pattern.
+///
+/// Both lists keep the order in which the channels were added to the pattern:
+///
+/// ```
+/// use rusty_junctions::Junction;
+///
+/// let j = Junction::new();
+///
+/// let a = j.send_channel::<i32>().unwrap();
+/// let b = j.send_channel::<char>().unwrap();
+/// let c = j.send_channel::<bool>().unwrap();
+/// let d = j.send_channel::<String>().unwrap();
+/// let e = j.send_channel::<u8>().unwrap();
+///
+/// j.when(&a)
+///     .and(&b)
+///     .and(&c)
+///     .and(&d)
+///     .and(&e)
+///     .then_do(|(a, (b, (c, (d, (e, ())))))| {
+///         println!("{} {} {} {} {}", a, b, c, d, e);
+///     })
+///     .unwrap();
+///
+/// a.send(1).unwrap();
+/// b.send('b').unwrap();
+/// c.send(true).unwrap();
+/// d.send(String::from("d")).unwrap();
+/// e.send(5).unwrap();
+/// ```
+///
+/// Patterns joining multiple synchronous channels send each of the replies
+/// back to the respective waiting caller, e.g. to exchange two values:
+///
+/// ```
+/// use std::thread;
+///
+/// use rusty_junctions::Junction;
+///
+/// let j = Junction::new();
+///
+/// let left = j.bidir_channel::<i32, i32>().unwrap();
+/// let right = j.bidir_channel::<i32, i32>().unwrap();
+///
+/// j.when_bidir(&left)
+///     .and_bidir(&right)
+///     .then_do(|(l, (r, ()))| (r, (l, ())))
+///     .unwrap();
+///
+/// let left_clone = left.clone();
+/// let handle = thread::spawn(move || left_clone.send_recv(1).unwrap());
+///
+/// assert_eq!(1, right.send_recv(2).unwrap());
+/// assert_eq!(2, handle.join().unwrap());
+/// ```
+pub mod nary {
+    use super::*;
+
+    use std::marker::PhantomData;
+    use std::slice::Iter;
+
+    /***********************************
+     * Heterogeneous Lists of Channels *
+     ***********************************/
+
+    /// List of the types of channels in an n-ary Join Pattern.
+    ///
+    /// The empty list is represented by `()` and a non-empty list by a pair
+    /// `(H, T)` of its first channel type `H` and the remaining list `T`.
+    pub trait ChannelList: Sized + 'static {
+        /// List of values passed to the function body of the Join Pattern.
+        type Args: 'static;
+
+        /// List of values returned by the function body of the Join Pattern.
+        type Replies: 'static;
+
+        /// List of `Sender`s the replies are sent back through.
+        type ReturnSenders: 'static;
+
+        /// List of references to the values passed to the guard of the Join
+        /// Pattern, i.e. to the values that would be passed to its function body.
+        type ArgRefs<'a>;
+
+        /// List of the typed handles to the queues of the channels.
+        type Queues: Clone + Send + 'static;
+
+        /// List of the messages taken out of the queues of the channels while
+        /// the guard of the Join Pattern inspects them.
+        type Inspected: 'static;
+
+        /// Append the IDs of the channels with the given queues to the given
+        /// `Vec`, in order.
+        fn collect_channel_ids(queues: &Self::Queues, channel_ids: &mut Vec<ids::ChannelId>);
+
+        /// Split the retrieved messages into the arguments for the function
+        /// body and the `Sender`s for its replies, taking one message out of
+        /// each of the given queues, in order.
         ///
         /// # Panics
         ///
-        /// Panics if it was not possible to send the request to add the newly
-        /// create Join Pattern to the `Junction`.
-        pub fn then_do<F>(self, f: F)
+        /// Panics if fewer messages than channels in the list are retrieved.
+        fn split_messages(
+            queues: &Self::Queues,
+            messages: &mut Retrieval,
+        ) -> AssertSend<(Self::Args, Self::ReturnSenders)>;
+
+        /// Take the messages at the given positions out of the given queues,
+        /// one per channel, in order, for the guard to inspect them.
+        ///
+        /// # Panics
+        ///
+        /// Panics if there are fewer positions than channels in the list, or
+        /// if a queue holds no message at its position.
+        fn take_inspected(queues: &Self::Queues, positions: &mut Iter<usize>) -> Self::Inspected;
+
+        /// Put the messages taken out by `take_inspected` back into the given
+        /// queues, at the same positions.
+        fn restore_inspected(
+            queues: &Self::Queues,
+            positions: &mut Iter<usize>,
+            inspected: Self::Inspected,
+        );
+
+        /// Collect references to the arguments for the guard from the given
+        /// inspected messages.
+        fn arg_refs(inspected: &Self::Inspected) -> Self::ArgRefs<'_>;
+
+        /// Send each of the replies back through its respective `Sender`.
+        ///
+        /// A reply that cannot be delivered, because its receiver has already
+        /// been dropped, does not prevent the remaining replies from being sent.
+        fn send_replies(return_senders: Self::ReturnSenders, replies: Self::Replies);
+    }
+
+    impl ChannelList for () {
+        type Args = ();
+        type Replies = ();
+        type ReturnSenders = ();
+        type ArgRefs<'a> = ();
+        type Queues = ();
+        type Inspected = ();
+
+        fn collect_channel_ids(_queues: &Self::Queues, _channel_ids: &mut Vec<ids::ChannelId>) {}
+
+        fn split_messages(
+            _queues: &Self::Queues,
+            _messages: &mut Retrieval,
+        ) -> AssertSend<(Self::Args, Self::ReturnSenders)> {
+            AssertSend::from_send(((), ()))
+        }
+
+        fn take_inspected(_queues: &Self::Queues, _positions: &mut Iter<usize>) -> Self::Inspected {
+        }
+
+        fn restore_inspected(
+            _queues: &Self::Queues,
+            _positions: &mut Iter<usize>,
+            _inspected: Self::Inspected,
+        ) {
+        }
+
+        fn arg_refs(_inspected: &Self::Inspected) -> Self::ArgRefs<'_> {}
+
+        fn send_replies(_return_senders: Self::ReturnSenders, _replies: Self::Replies) {}
+    }
+
+    impl<T, L> ChannelList for (SendChannel<T>, L)
+    where
+        T: Any,
+        L: ChannelList,
+    {
+        type Args = (T, L::Args);
+        type Replies = L::Replies;
+        type ReturnSenders = L::ReturnSenders;
+        type ArgRefs<'a> = (&'a T, L::ArgRefs<'a>);
+        type Queues = (QueueRef<T>, L::Queues);
+        type Inspected = (T, L::Inspected);
+
+        fn collect_channel_ids(queues: &Self::Queues, channel_ids: &mut Vec<ids::ChannelId>) {
+            channel_ids.push(queues.0.channel_id());
+
+            L::collect_channel_ids(&queues.1, channel_ids);
+        }
+
+        fn split_messages(
+            queues: &Self::Queues,
+            messages: &mut Retrieval,
+        ) -> AssertSend<(Self::Args, Self::ReturnSenders)> {
+            let arg = messages.take(&queues.0);
+            let (args, return_senders) = L::split_messages(&queues.1, messages).unzip();
+
+            arg.zip(args).zip(return_senders)
+        }
+
+        fn take_inspected(queues: &Self::Queues, positions: &mut Iter<usize>) -> Self::Inspected {
+            let arg = queues.0.take(*positions.next().unwrap());
+
+            (arg, L::take_inspected(&queues.1, positions))
+        }
+
+        fn restore_inspected(
+            queues: &Self::Queues,
+            positions: &mut Iter<usize>,
+            inspected: Self::Inspected,
+        ) {
+            queues.0.restore(*positions.next().unwrap(), inspected.0);
+
+            L::restore_inspected(&queues.1, positions, inspected.1);
+        }
+
+        fn arg_refs(inspected: &Self::Inspected) -> Self::ArgRefs<'_> {
+            (&inspected.0, L::arg_refs(&inspected.1))
+        }
+
+        fn send_replies(return_senders: Self::ReturnSenders, replies: Self::Replies) {
+            L::send_replies(return_senders, replies);
+        }
+    }
+
+    impl<R, L> ChannelList for (RecvChannel<R>, L)
+    where
+        R: Any,
+        L: ChannelList,
+    {
+        type Args = L::Args;
+        type Replies = (R, L::Replies);
+        type ReturnSenders = (ReplySender<R>, L::ReturnSenders);
+        type ArgRefs<'a> = L::ArgRefs<'a>;
+        type Queues = (QueueRef<ReplySender<R>>, L::Queues);
+        type Inspected = L::Inspected;
+
+        fn collect_channel_ids(queues: &Self::Queues, channel_ids: &mut Vec<ids::ChannelId>) {
+            channel_ids.push(queues.0.channel_id());
+
+            L::collect_channel_ids(&queues.1, channel_ids);
+        }
+
+        fn split_messages(
+            queues: &Self::Queues,
+            messages: &mut Retrieval,
+        ) -> AssertSend<(Self::Args, Self::ReturnSenders)> {
+            let return_sender = messages.take_reply(&queues.0);
+            let (args, return_senders) = L::split_messages(&queues.1, messages).unzip();
+
+            args.zip(return_sender.zip(return_senders))
+        }
+
+        fn take_inspected(queues: &Self::Queues, positions: &mut Iter<usize>) -> Self::Inspected {
+            // Requests carry no value for the guard to inspect.
+            positions.next().unwrap();
+
+            L::take_inspected(&queues.1, positions)
+        }
+
+        fn restore_inspected(
+            queues: &Self::Queues,
+            positions: &mut Iter<usize>,
+            inspected: Self::Inspected,
+        ) {
+            positions.next().unwrap();
+
+            L::restore_inspected(&queues.1, positions, inspected);
+        }
+
+        fn arg_refs(inspected: &Self::Inspected) -> Self::ArgRefs<'_> {
+            L::arg_refs(inspected)
+        }
+
+        fn send_replies(return_senders: Self::ReturnSenders, replies: Self::Replies) {
+            let _ = return_senders.0.send(replies.0);
+
+            L::send_replies(return_senders.1, replies.1);
+        }
+    }
+
+    impl<T, R, L> ChannelList for (BidirChannel<T, R>, L)
+    where
+        T: Any,
+        R: Any,
+        L: ChannelList,
+    {
+        type Args = (T, L::Args);
+        type Replies = (R, L::Replies);
+        type ReturnSenders = (ReplySender<R>, L::ReturnSenders);
+        type ArgRefs<'a> = (&'a T, L::ArgRefs<'a>);
+        type Queues = (QueueRef<(T, ReplySender<R>)>, L::Queues);
+        type Inspected = ((T, ReplySender<R>), L::Inspected);
+
+        fn collect_channel_ids(queues: &Self::Queues, channel_ids: &mut Vec<ids::ChannelId>) {
+            channel_ids.push(queues.0.channel_id());
+
+            L::collect_channel_ids(&queues.1, channel_ids);
+        }
+
+        fn split_messages(
+            queues: &Self::Queues,
+            messages: &mut Retrieval,
+        ) -> AssertSend<(Self::Args, Self::ReturnSenders)> {
+            let (arg, return_sender) = messages.take_request(&queues.0).unzip();
+            let (args, return_senders) = L::split_messages(&queues.1, messages).unzip();
+
+            arg.zip(args).zip(return_sender.zip(return_senders))
+        }
+
+        fn take_inspected(queues: &Self::Queues, positions: &mut Iter<usize>) -> Self::Inspected {
+            let request = queues.0.take(*positions.next().unwrap());
+
+            (request, L::take_inspected(&queues.1, positions))
+        }
+
+        fn restore_inspected(
+            queues: &Self::Queues,
+            positions: &mut Iter<usize>,
+            inspected: Self::Inspected,
+        ) {
+            queues.0.restore(*positions.next().unwrap(), inspected.0);
+
+            L::restore_inspected(&queues.1, positions, inspected.1);
+        }
+
+        fn arg_refs(inspected: &Self::Inspected) -> Self::ArgRefs<'_> {
+            (&(inspected.0).0, L::arg_refs(&inspected.1))
+        }
+
+        fn send_replies(return_senders: Self::ReturnSenders, replies: Self::Replies) {
+            let _ = return_senders.0.send(replies.0);
+
+            L::send_replies(return_senders.1, replies.1);
+        }
+    }
+
+    /// Type-level operation to append a channel type `X` to the end of a list.
+    pub trait Append<X>: ChannelList {
+        /// Type of the list after `X` has been appended.
+        type Output: ChannelList;
+
+        /// Append the queue of the given channel to the given list of queues.
+        fn append(queues: Self::Queues, channel: &X) -> <Self::Output as ChannelList>::Queues;
+    }
+
+    impl<T> Append<SendChannel<T>> for ()
+    where
+        T: Any,
+    {
+        type Output = (SendChannel<T>, ());
+
+        fn append(_queues: (), channel: &SendChannel<T>) -> (QueueRef<T>, ()) {
+            (channel.queue(), ())
+        }
+    }
+
+    impl<R> Append<RecvChannel<R>> for ()
+    where
+        R: Any,
+    {
+        type Output = (RecvChannel<R>, ());
+
+        fn append(_queues: (), channel: &RecvChannel<R>) -> (QueueRef<ReplySender<R>>, ()) {
+            (channel.queue(), ())
+        }
+    }
+
+    impl<T, R> Append<BidirChannel<T, R>> for ()
+    where
+        T: Any,
+        R: Any,
+    {
+        type Output = (BidirChannel<T, R>, ());
+
+        fn append(
+            _queues: (),
+            channel: &BidirChannel<T, R>,
+        ) -> (QueueRef<(T, ReplySender<R>)>, ()) {
+            (channel.queue(), ())
+        }
+    }
+
+    impl<T, L, X> Append<X> for (SendChannel<T>, L)
+    where
+        T: Any,
+        L: Append<X>,
+    {
+        type Output = (SendChannel<T>, L::Output);
+
+        fn append(queues: Self::Queues, channel: &X) -> <Self::Output as ChannelList>::Queues {
+            (queues.0, L::append(queues.1, channel))
+        }
+    }
+
+    impl<R, L, X> Append<X> for (RecvChannel<R>, L)
+    where
+        R: Any,
+        L: Append<X>,
+    {
+        type Output = (RecvChannel<R>, L::Output);
+
+        fn append(queues: Self::Queues, channel: &X) -> <Self::Output as ChannelList>::Queues {
+            (queues.0, L::append(queues.1, channel))
+        }
+    }
+
+    impl<T, R, L, X> Append<X> for (BidirChannel<T, R>, L)
+    where
+        T: Any,
+        R: Any,
+        L: Append<X>,
+    {
+        type Output = (BidirChannel<T, R>, L::Output);
+
+        fn append(queues: Self::Queues, channel: &X) -> <Self::Output as ChannelList>::Queues {
+            (queues.0, L::append(queues.1, channel))
+        }
+    }
+
+    /*****************************
+     * Join Pattern Construction *
+     *****************************/
+
+    /// Arbitrary channels partial Join Pattern.
+    ///
+    /// The generic parameter `C` is the `ChannelList` of the types of the
+    /// channels in this pattern, in order.
+    pub struct PartialPattern<C>
+    where
+        C: ChannelList,
+    {
+        junction_id: ids::JunctionId,
+        queues: C::Queues,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
+        channels_type: PhantomData<C>,
+    }
+
+    impl<C> PartialPattern<C>
+    where
+        C: ChannelList,
+    {
+        pub(crate) fn new(
+            junction_id: ids::JunctionId,
+            queues: C::Queues,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
+        ) -> PartialPattern<C> {
+            PartialPattern {
+                junction_id,
+                queues,
+                sender,
+                attributes,
+                channels_type: PhantomData,
+            }
+        }
+
+        /// Create an n-ary partial Join Pattern with one more send channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that.
+        ///
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and<T>(
+            mut self,
+            send_channel: &SendChannel<T>,
+        ) -> PartialPattern<<C as Append<SendChannel<T>>>::Output>
         where
-            F: Fn(T, U, V) -> R + Send + Clone + 'static,
+            T: Any,
+            C: Append<SendChannel<T>>,
+            <C as Append<SendChannel<T>>>::Output: ChannelList,
         {
-            let join_pattern = JoinPattern::TernaryBidir(BidirJoinPattern::new(
-                self.first_send_channel.id(),
-                self.second_send_channel.id(),
-                self.bidir_channel.id(),
-                function_transforms::ternary::transform_bidir(f),
-            ));
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            PartialPattern::new(
+                self.junction_id,
+                C::append(self.queues, send_channel),
+                self.sender,
+                self.attributes,
+            )
+        }
 
-            self.sender
-                .send(Packet::AddJoinPatternRequest { join_pattern })
-                .unwrap();
+        /// Create an n-ary partial Join Pattern with one more receive channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `RecvChannel` after that.
+        ///
+        /// Should the supplied `RecvChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_recv<R>(
+            mut self,
+            recv_channel: &RecvChannel<R>,
+        ) -> PartialPattern<<C as Append<RecvChannel<R>>>::Output>
+        where
+            R: Any,
+            C: Append<RecvChannel<R>>,
+            <C as Append<RecvChannel<R>>>::Output: ChannelList,
+        {
+            self.attributes
+                .require_same_junction(recv_channel.junction_id(), self.junction_id);
+
+            PartialPattern::new(
+                self.junction_id,
+                C::append(self.queues, recv_channel),
+                self.sender,
+                self.attributes,
+            )
+        }
+
+        /// Create an n-ary partial Join Pattern with one more bidirectional channel.
+        ///
+        /// Create a new n-ary partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_bidir<T, R>(
+            mut self,
+            bidir_channel: &BidirChannel<T, R>,
+        ) -> PartialPattern<<C as Append<BidirChannel<T, R>>>::Output>
+        where
+            T: Any,
+            R: Any,
+            C: Append<BidirChannel<T, R>>,
+            <C as Append<BidirChannel<T, R>>>::Output: ChannelList,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            PartialPattern::new(
+                self.junction_id,
+                C::append(self.queues, bidir_channel),
+                self.sender,
+                self.attributes,
+            )
+        }
+
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages that satisfies the guard, all other messages stay queued.
+        /// Adding several guards requires all of them to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: for<'a> Fn(C::ArgRefs<'a>) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::nary::transform_guard::<G, C>(
+                    self.queues.clone(),
+                    g,
+                ));
+
+            self
+        }
+
+        attribute_methods!();
+
+        then_do_methods! {
+            (args: C::Args) -> C::Replies,
+            |this: &Self, f| function_transforms::nary::transform::<F, C>(this.queues.clone(), f),
+            |this: &Self, spawner, f| {
+                function_transforms::nary::transform_async::<S, F, Fut, C>(
+                    this.queues.clone(),
+                    spawner,
+                    f,
+                )
+            },
+        }
+
+        /// Create full Join Pattern with the given function and send request
+        /// to add it to `Junction`.
+        fn register(self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let mut channel_ids = Vec::new();
+            C::collect_channel_ids(&self.queues, &mut channel_ids);
+
+            let join_pattern = JoinPattern::new(channel_ids, f, self.attributes);
+
+            PatternHandle::register(join_pattern, self.sender)
         }
     }
+}
+
+/// Structs for Join Patterns correlating the messages on their channels by key.
+///
+/// A keyed Join Pattern only fires for messages that carry the same key on
+/// each of its channels, as determined by a key function supplied for every
+/// channel. The `Junction` keeps an index of the messages by their key, so
+/// finding matching messages does not require scanning the messages of any
+/// other key, no matter how many keys are outstanding.
+///
+/// Values are passed to and returned from the function body of a keyed Join
+/// Pattern in the same way as for n-ary Join Patterns, see the `nary` module:
+///
+/// ```
+/// use std::sync::mpsc::channel;
+///
+/// use rusty_junctions::Junction;
+///
+/// struct Request {
+///     id: u32,
+///     query: &'static str,
+/// }
+///
+/// struct Response {
+///     request_id: u32,
+///     answer: u32,
+/// }
+///
+/// let j = Junction::new();
+///
+/// let requests = j.send_channel::<Request>().unwrap();
+/// let responses = j.send_channel::<Response>().unwrap();
+///
+/// let (sender, receiver) = channel();
+///
+/// j.when_keyed(&requests, |req| req.id)
+///     .and_keyed(&responses, |resp| resp.request_id)
+///     .then_do(move |(req, (resp, ()))| {
+///         sender.send(format!("{} = {}", req.query, resp.answer)).unwrap();
+///     })
+///     .unwrap();
+///
+/// requests.send(Request { id: 1, query: "1 + 1" }).unwrap();
+/// requests.send(Request { id: 2, query: "2 + 2" }).unwrap();
+/// responses.send(Response { request_id: 2, answer: 4 }).unwrap();
+///
+/// assert_eq!("2 + 2 = 4", receiver.recv().unwrap());
+/// ```
+pub mod keyed {
+    use super::*;
 
-    /// Two `SendChannel` & `BidirChannel` full `JoinPattern`.
-    pub struct BidirJoinPattern {
-        first_send_channel_id: ids::ChannelId,
-        second_send_channel_id: ids::ChannelId,
-        bidir_channel_id: ids::ChannelId,
-        f: functions::ternary::FnBox,
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::marker::PhantomData;
+
+    use super::nary::{Append, ChannelList};
+
+    /// Type alias for boxed up functions extracting the key of the message at
+    /// the given position in the queue of a channel.
+    type KeyFn<K> = Box<dyn Fn(usize) -> K + Send + Sync>;
+
+    /// Keyed channels partial Join Pattern.
+    ///
+    /// The generic parameter `K` is the type of the key correlating the
+    /// messages and `C` is the `ChannelList` of the types of the channels in
+    /// this pattern, in order.
+    pub struct PartialPattern<K, C>
+    where
+        C: ChannelList,
+    {
+        junction_id: ids::JunctionId,
+        queues: C::Queues,
+        key_fns: Vec<KeyFn<K>>,
+        sender: QueueSender<Packet>,
+        attributes: PatternAttributes,
+        channels_type: PhantomData<C>,
     }
 
-    impl BidirJoinPattern {
+    impl<K, C> PartialPattern<K, C>
+    where
+        K: Hash + Eq + 'static,
+        C: ChannelList,
+    {
         pub(crate) fn new(
-            first_send_channel_id: ids::ChannelId,
-            second_send_channel_id: ids::ChannelId,
-            bidir_channel_id: ids::ChannelId,
-            f: functions::ternary::FnBox,
-        ) -> BidirJoinPattern {
-            BidirJoinPattern {
-                first_send_channel_id,
-                second_send_channel_id,
-                bidir_channel_id,
-                f,
+            junction_id: ids::JunctionId,
+            queues: C::Queues,
+            key_fns: Vec<KeyFn<K>>,
+            sender: QueueSender<Packet>,
+            attributes: PatternAttributes,
+        ) -> PartialPattern<K, C> {
+            PartialPattern {
+                junction_id,
+                queues,
+                key_fns,
+                sender,
+                attributes,
+                channels_type: PhantomData,
             }
         }
 
-        /// Return the ID of first `SendChannel` in this Join Pattern.
-        pub(crate) fn first_send_channel_id(&self) -> ids::ChannelId {
-            self.first_send_channel_id
+        /// Create a keyed partial Join Pattern with one more keyed send channel.
+        ///
+        /// Create a new keyed partial Join Pattern that starts with the current
+        /// pattern and includes a new `SendChannel` after that, whose messages
+        /// are correlated with the others by the key extracted with `key`.
+        ///
+        /// Should the supplied `SendChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_keyed<T, F>(
+            mut self,
+            send_channel: &SendChannel<T>,
+            key: F,
+        ) -> PartialPattern<K, <C as Append<SendChannel<T>>>::Output>
+        where
+            T: Any,
+            F: Fn(&T) -> K + Send + Sync + 'static,
+            C: Append<SendChannel<T>>,
+            <C as Append<SendChannel<T>>>::Output: ChannelList,
+        {
+            self.attributes
+                .require_same_junction(send_channel.junction_id(), self.junction_id);
+
+            self.key_fns.push(send_key_fn(send_channel.queue(), key));
+
+            PartialPattern::new(
+                self.junction_id,
+                C::append(self.queues, send_channel),
+                self.key_fns,
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of second `SendChannel` in this Join Pattern.
-        pub(crate) fn second_send_channel_id(&self) -> ids::ChannelId {
-            self.second_send_channel_id
+        /// Create a keyed partial Join Pattern with one more keyed bidirectional
+        /// channel.
+        ///
+        /// Create a new keyed partial Join Pattern that starts with the current
+        /// pattern and includes a new `BidirChannel` after that, whose messages
+        /// are correlated with the others by the key extracted with `key`.
+        ///
+        /// Should the supplied `BidirChannel` not have been created by the same
+        /// `Junction` as this partial Join Pattern, completing the Join
+        /// Pattern fails with `JunctionError::ForeignChannel`.
+        pub fn and_keyed_bidir<T, R, F>(
+            mut self,
+            bidir_channel: &BidirChannel<T, R>,
+            key: F,
+        ) -> PartialPattern<K, <C as Append<BidirChannel<T, R>>>::Output>
+        where
+            T: Any,
+            R: Any,
+            F: Fn(&T) -> K + Send + Sync + 'static,
+            C: Append<BidirChannel<T, R>>,
+            <C as Append<BidirChannel<T, R>>>::Output: ChannelList,
+        {
+            self.attributes
+                .require_same_junction(bidir_channel.junction_id(), self.junction_id);
+
+            self.key_fns.push(bidir_key_fn(bidir_channel.queue(), key));
+
+            PartialPattern::new(
+                self.junction_id,
+                C::append(self.queues, bidir_channel),
+                self.key_fns,
+                self.sender,
+                self.attributes,
+            )
         }
 
-        /// Return the ID of the `BidirChannel` in this Join Pattern.
-        pub(crate) fn bidir_channel_id(&self) -> ids::ChannelId {
-            self.bidir_channel_id
+        /// Add a guard to the partial Join Pattern.
+        ///
+        /// The guard is a predicate over references to the values sent on the
+        /// channels of the pattern. The Join Pattern only fires for a set of
+        /// messages with the same key that satisfies the guard, all other
+        /// messages stay queued. Adding several guards requires all of them
+        /// to hold.
+        pub fn guard<G>(mut self, g: G) -> Self
+        where
+            G: for<'a> Fn(C::ArgRefs<'a>) -> bool + Send + 'static,
+        {
+            self.attributes
+                .add_guard(function_transforms::nary::transform_guard::<G, C>(
+                    self.queues.clone(),
+                    g,
+                ));
+
+            self
         }
 
-        /// Fire Join Pattern by running associated function in separate thread.
-        pub(crate) fn fire(&self, arg_1: Message, arg_2: Message, arg_3_and_sender: Message) {
-            let f_clone = self.f.clone();
+        attribute_methods!();
 
-            thread::spawn(move || {
-                (*f_clone)(arg_1, arg_2, arg_3_and_sender);
-            });
+        then_do_methods! {
+            (args: C::Args) -> C::Replies,
+            |this: &Self, f| function_transforms::nary::transform::<F, C>(this.queues.clone(), f),
+            |this: &Self, spawner, f| {
+                function_transforms::nary::transform_async::<S, F, Fut, C>(
+                    this.queues.clone(),
+                    spawner,
+                    f,
+                )
+            },
         }
+
+        /// Create full Join Pattern with given function and send request to add
+        /// it to `Junction`, requiring the keys of its messages to be equal.
+        fn register(mut self, f: functions::FnBox) -> Result<PatternHandle, JunctionError> {
+            let key_fns = Arc::new(self.key_fns);
+
+            // Messages with the same key hash are only candidates for a match,
+            // so the keys themselves still need to be compared.
+            let guard_key_fns = Arc::clone(&key_fns);
+            self.attributes
+                .add_guard(Box::new(move |positions: &[usize]| {
+                    let key = guard_key_fns[0](positions[0]);
+
+                    guard_key_fns
+                        .iter()
+                        .zip(positions.iter())
+                        .skip(1)
+                        .all(|(key_fn, &position)| key_fn(position) == key)
+                }));
+            self.attributes
+                .set_key(Box::new(move |index: usize, position: usize| {
+                    let mut hasher = DefaultHasher::new();
+                    key_fns[index](position).hash(&mut hasher);
+
+                    hasher.finish()
+                }));
+
+            let mut channel_ids = Vec::new();
+            C::collect_channel_ids(&self.queues, &mut channel_ids);
+
+            let join_pattern = JoinPattern::new(channel_ids, f, self.attributes);
+
+            PatternHandle::register(join_pattern, self.sender)
+        }
+    }
+
+    /// Wrap function extracting the key of a `SendChannel` value to extract
+    /// it from the message at a given position in the given queue.
+    pub(crate) fn send_key_fn<T, K, F>(queue: QueueRef<T>, key: F) -> KeyFn<K>
+    where
+        T: Any,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        Box::new(move |position: usize| queue.with(position, &key))
+    }
+
+    /// Wrap function extracting the key of a `BidirChannel` value to extract
+    /// it from the message at a given position in the given queue.
+    fn bidir_key_fn<T, R, K, F>(queue: QueueRef<(T, ReplySender<R>)>, key: F) -> KeyFn<K>
+    where
+        T: Any,
+        R: Any,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        Box::new(move |position: usize| queue.with(position, |(arg, _)| key(arg)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc::channel;
+
+    use crate::{Junction, LocalJunction};
+
+    /// Create a `RecvChannel` on the given `Junction`, whose `recv` returns
+    /// once the `Junction` has handled all messages sent before it.
+    fn flush_channel(j: &Junction) -> RecvChannel<()> {
+        let flush = j.recv_channel::<()>().unwrap();
+        j.when_recv(&flush).then_do(|| ()).unwrap();
+
+        flush
+    }
+
+    #[test]
+    fn test_guard_on_junction() {
+        // Given:
+        let j = Junction::with_executor(InlineExecutor);
+        let flush = flush_channel(&j);
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&values)
+            .and(&values)
+            .guard(|x, y| x + y == 10)
+            .then_do(move |x, y| sender.send((x, y)).unwrap())
+            .unwrap();
+
+        // When:
+        for v in [1, 2, 9, 8] {
+            values.send(v).unwrap();
+        }
+        flush.recv().unwrap();
+
+        // Then:
+        assert_eq!(
+            vec![(1, 9), (2, 8)],
+            receiver.try_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_guard_on_local_junction() {
+        // Given:
+        let j = LocalJunction::new();
+        let left = j.send_channel::<i32>().unwrap();
+        let right = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&left)
+            .and(&right)
+            .guard(|x, y| x < y)
+            .then_do(move |x, y| sender.send((x, y)).unwrap())
+            .unwrap();
+
+        // When:
+        left.send(5).unwrap();
+        right.send(3).unwrap();
+        left.send(2).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(vec![(2, 3)], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_removed_pattern_on_junction() {
+        // Given:
+        let j = Junction::with_executor(InlineExecutor);
+        let flush = flush_channel(&j);
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        let handle = j
+            .when(&values)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+        values.send(1).unwrap();
+        flush.recv().unwrap();
+
+        // When:
+        handle.remove().unwrap();
+        values.send(2).unwrap();
+        flush.recv().unwrap();
+
+        // Then:
+        assert_eq!(vec![1], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_removed_pattern_on_local_junction() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        let handle = j
+            .when(&values)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+        values.send(1).unwrap();
+        j.run_until_idle();
+
+        // When:
+        handle.remove().unwrap();
+        values.send(2).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(vec![1], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_limited_pattern_on_junction() {
+        // Given:
+        let j = Junction::with_executor(InlineExecutor);
+        let flush = flush_channel(&j);
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        j.when(&values)
+            .limit(2)
+            .then_do(move |v| sender.send(v).unwrap())
+            .unwrap();
+
+        // When:
+        for v in 1..=3 {
+            values.send(v).unwrap();
+        }
+        flush.recv().unwrap();
+
+        // Then:
+        assert_eq!(vec![1, 2], receiver.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_once_pattern_on_local_junction() {
+        // Given:
+        let j = LocalJunction::new();
+        let values = j.send_channel::<i32>().unwrap();
+        let (sender, receiver) = channel();
+        let owned = vec![0];
+        j.when(&values)
+            .then_do_once(move |v| {
+                let mut owned = owned;
+                owned.push(v);
+                sender.send(owned).unwrap();
+            })
+            .unwrap();
+
+        // When:
+        values.send(1).unwrap();
+        values.send(2).unwrap();
+        j.run_until_idle();
+
+        // Then:
+        assert_eq!(vec![vec![0, 1]], receiver.try_iter().collect::<Vec<_>>());
     }
 }
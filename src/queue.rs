@@ -0,0 +1,290 @@
+//! Unbounded multi-producer, single-consumer queue that can be received from
+//! both by blocking the current thread and by polling a `Future`.
+//!
+//! Used to deliver `Packet`s to the `Controller` of a `Junction`, regardless
+//! of whether it runs in a dedicated control thread or as a task on an async
+//! runtime.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{RecvError, SendError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// State shared between all sending ends and the receiving end of a queue.
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    /// Notified whenever an item has been queued or the last sender dropped.
+    condvar: Condvar,
+}
+
+struct State<T> {
+    items: VecDeque<T>,
+    senders: usize,
+    receiver_alive: bool,
+    /// `Waker` of the task last polling the receiving end, if any.
+    waker: Option<Waker>,
+}
+
+impl<T> Shared<T> {
+    /// Wake up the receiving end, whether it is blocked or polled.
+    fn notify(&self, state: &mut State<T>) {
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+
+        self.condvar.notify_one();
+    }
+}
+
+/// Create a new queue, returning its sending and receiving end.
+pub(crate) fn queue<T>() -> (QueueSender<T>, QueueReceiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            items: VecDeque::new(),
+            senders: 1,
+            receiver_alive: true,
+            waker: None,
+        }),
+        condvar: Condvar::new(),
+    });
+
+    (
+        QueueSender {
+            shared: Arc::clone(&shared),
+        },
+        QueueReceiver { shared },
+    )
+}
+
+/// Sending end of a queue, which can be cloned to send from multiple places.
+pub(crate) struct QueueSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueSender<T> {
+    /// Queue the given item, never blocking.
+    ///
+    /// Return the item in an error if the receiving end has been dropped.
+    pub(crate) fn send(&self, item: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if !state.receiver_alive {
+            return Err(SendError(item));
+        }
+
+        state.items.push_back(item);
+        self.shared.notify(&mut state);
+
+        Ok(())
+    }
+}
+
+impl<T> Clone for QueueSender<T> {
+    fn clone(&self) -> QueueSender<T> {
+        self.shared.state.lock().unwrap().senders += 1;
+
+        QueueSender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for QueueSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        state.senders -= 1;
+
+        if state.senders == 0 {
+            self.shared.notify(&mut state);
+        }
+    }
+}
+
+/// Receiving end of a queue.
+pub(crate) struct QueueReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueReceiver<T> {
+    /// Block the current thread until an item is available and return it.
+    ///
+    /// Return an error if the queue is empty and all sending ends have been
+    /// dropped.
+    pub(crate) fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        loop {
+            if let Some(item) = state.items.pop_front() {
+                return Ok(item);
+            }
+
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+
+            state = self.shared.condvar.wait(state).unwrap();
+        }
+    }
+
+    /// Return the next item if one is available, without blocking.
+    pub(crate) fn try_recv(&self) -> Option<T> {
+        self.shared.state.lock().unwrap().items.pop_front()
+    }
+
+    /// Return a `Future` resolving to the next item once it is available.
+    ///
+    /// The `Future` resolves to an error if the queue is empty and all sending
+    /// ends have been dropped.
+    pub(crate) fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture { receiver: self }
+    }
+}
+
+impl<T> Drop for QueueReceiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        state.receiver_alive = false;
+
+        // Items that will never be received are dropped right away, so that
+        // anyone waiting on them is released.
+        let items = std::mem::take(&mut state.items);
+        drop(state);
+        drop(items);
+    }
+}
+
+/// `Future` returned by `QueueReceiver::recv_async`.
+pub(crate) struct RecvFuture<'a, T> {
+    receiver: &'a QueueReceiver<T>,
+}
+
+impl<'a, T> Future for RecvFuture<'a, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.receiver.shared.state.lock().unwrap();
+
+        if let Some(item) = state.items.pop_front() {
+            return Poll::Ready(Ok(item));
+        }
+
+        if state.senders == 0 {
+            return Poll::Ready(Err(RecvError));
+        }
+
+        state.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::task::Wake;
+    use std::thread;
+
+    /// `Waker` counting how many times it has been woken.
+    struct CountingWaker(Mutex<usize>);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            *self.0.lock().unwrap() += 1;
+        }
+    }
+
+    #[test]
+    fn test_recv_in_order() {
+        // Given:
+        let (sender, receiver) = queue::<i32>();
+
+        // When:
+        sender.send(1).unwrap();
+        sender.clone().send(2).unwrap();
+
+        // Then:
+        assert_eq!(Ok(1), receiver.recv());
+        assert_eq!(Ok(2), receiver.recv());
+    }
+
+    #[test]
+    fn test_try_recv() {
+        // Given:
+        let (sender, receiver) = queue::<i32>();
+
+        sender.send(42).unwrap();
+
+        // When:
+        let first = receiver.try_recv();
+        let second = receiver.try_recv();
+
+        // Then:
+        assert_eq!(Some(42), first);
+        assert_eq!(None, second);
+    }
+
+    #[test]
+    fn test_recv_from_other_thread() {
+        // Given:
+        let (sender, receiver) = queue::<i32>();
+
+        // When:
+        thread::spawn(move || sender.send(42).unwrap());
+
+        // Then:
+        assert_eq!(Ok(42), receiver.recv());
+    }
+
+    #[test]
+    fn test_recv_after_all_senders_dropped() {
+        // Given:
+        let (sender, receiver) = queue::<i32>();
+        let sender_clone = sender.clone();
+
+        sender.send(42).unwrap();
+
+        // When:
+        drop(sender);
+        drop(sender_clone);
+
+        // Then:
+        assert_eq!(Ok(42), receiver.recv());
+        assert_eq!(Err(RecvError), receiver.recv());
+    }
+
+    #[test]
+    fn test_send_after_receiver_dropped() {
+        // Given:
+        let (sender, receiver) = queue::<i32>();
+
+        // When:
+        drop(receiver);
+
+        // Then:
+        assert_eq!(Err(SendError(42)), sender.send(42));
+    }
+
+    #[test]
+    fn test_poll_wakes_on_send() {
+        // Given:
+        let (sender, receiver) = queue::<i32>();
+        let counting_waker = Arc::new(CountingWaker(Mutex::new(0)));
+        let waker = Waker::from(Arc::clone(&counting_waker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = receiver.recv_async();
+
+        assert_eq!(Poll::Pending, Pin::new(&mut future).poll(&mut cx));
+
+        // When:
+        sender.send(42).unwrap();
+
+        // Then:
+        assert_eq!(1, *counting_waker.0.lock().unwrap());
+        assert_eq!(Poll::Ready(Ok(42)), Pin::new(&mut future).poll(&mut cx));
+    }
+}
@@ -0,0 +1,386 @@
+//! Index tracking which Join Patterns have enough messages available to fire,
+//! updated incrementally as messages arrive and are consumed.
+//!
+//! Rather than recounting the messages on every channel of every Join Pattern
+//! whenever a message arrives, each Join Pattern keeps a count of its unmet
+//! requirements, similar to the bitmasks of non-empty channels used by the
+//! join automata of JoCaml. A requirement is either that a channel holds at
+//! least as many messages as the Join Pattern consumes from it, or that a
+//! negated channel holds none at all. The requirements are indexed by the
+//! message count at which they change from unmet to met or vice versa, so
+//! that a change in the number of messages on a channel only touches the Join
+//! Patterns whose readiness actually changes.
+
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+
+use super::sealable_map::{DenseKey, SealableMap};
+
+/// Requirements of a single Join Pattern and how many of them are unmet.
+struct Entry<C> {
+    /// Distinct channels the Join Pattern consumes from, alongside the number
+    /// of messages it consumes from each of them.
+    required: Vec<(C, usize)>,
+    /// Distinct channels that need to hold no messages.
+    negated: Vec<C>,
+    unmet: usize,
+}
+
+/// Incrementally updated index of ready Join Patterns.
+///
+/// A Join Pattern is ready if all of its requirements regarding the number
+/// of messages on its channels are met. It may still not be able to fire,
+/// for instance because none of the available messages satisfy its guard.
+///
+/// Ready Join Patterns are kept in order, so that they are always retrieved
+/// in a deterministic order.
+pub(crate) struct ReadinessIndex<C, P> {
+    entries: HashMap<P, Entry<C>>,
+    /// Map of channels to the Join Patterns consuming from them, grouped by the
+    /// number of messages the channel needs to hold to meet their requirement.
+    thresholds: SealableMap<C, HashMap<usize, Vec<P>>>,
+    /// Map of channels to the Join Patterns requiring them to be empty.
+    negations: SealableMap<C, Vec<P>>,
+    /// Map of channels to the ready Join Patterns consuming from them.
+    ready_requiring: SealableMap<C, BTreeSet<P>>,
+    /// Map of channels to the ready Join Patterns requiring them to be empty.
+    ready_negating: SealableMap<C, BTreeSet<P>>,
+}
+
+impl<C, P> ReadinessIndex<C, P>
+where
+    C: DenseKey,
+    P: Hash + Ord + Copy,
+{
+    pub(crate) fn new() -> ReadinessIndex<C, P> {
+        ReadinessIndex {
+            entries: HashMap::new(),
+            thresholds: SealableMap::new(),
+            negations: SealableMap::new(),
+            ready_requiring: SealableMap::new(),
+            ready_negating: SealableMap::new(),
+        }
+    }
+
+    /// Store the Join Patterns of each channel in dense arrays indexed by
+    /// channel from now on.
+    pub(crate) fn seal(&mut self) {
+        self.thresholds.seal();
+        self.negations.seal();
+        self.ready_requiring.seal();
+        self.ready_negating.seal();
+    }
+
+    /// Insert a Join Pattern consuming one message for every occurrence of a
+    /// channel in `channels` and requiring the channels in `negated_channels`
+    /// to be empty.
+    ///
+    /// The number of messages currently available on each channel is given by
+    /// `count`, so that the readiness of the Join Pattern is known right away.
+    pub(crate) fn insert<F>(&mut self, pattern: P, channels: &[C], negated_channels: &[C], count: F)
+    where
+        F: Fn(&C) -> usize,
+    {
+        let mut required: Vec<(C, usize)> = Vec::new();
+
+        for ch in channels {
+            match required.iter_mut().find(|(c, _)| c == ch) {
+                Some((_, n)) => *n += 1,
+                None => required.push((*ch, 1)),
+            }
+        }
+
+        let mut negated: Vec<C> = Vec::new();
+
+        for ch in negated_channels {
+            if !negated.contains(ch) {
+                negated.push(*ch);
+            }
+        }
+
+        for &(ch, n) in &required {
+            self.thresholds
+                .get_or_default(ch)
+                .entry(n)
+                .or_default()
+                .push(pattern);
+        }
+
+        for &ch in &negated {
+            self.negations.get_or_default(ch).push(pattern);
+        }
+
+        let unmet = required.iter().filter(|(ch, n)| count(ch) < *n).count()
+            + negated.iter().filter(|ch| count(ch) > 0).count();
+
+        self.entries.insert(
+            pattern,
+            Entry {
+                required,
+                negated,
+                unmet,
+            },
+        );
+
+        if unmet == 0 {
+            self.mark_ready(pattern);
+        }
+    }
+
+    /// Remove the given Join Pattern from the index.
+    ///
+    /// Nothing will be done if the Join Pattern is not in the index.
+    pub(crate) fn remove(&mut self, pattern: &P) {
+        if let Some(entry) = self.entries.get(pattern) {
+            if entry.unmet == 0 {
+                self.mark_unready(*pattern);
+            }
+        }
+
+        if let Some(entry) = self.entries.remove(pattern) {
+            for (ch, n) in entry.required {
+                if let Some(patterns) = self.thresholds.get_mut(&ch).and_then(|t| t.get_mut(&n)) {
+                    patterns.retain(|p| p != pattern);
+                }
+            }
+
+            for ch in entry.negated {
+                if let Some(patterns) = self.negations.get_mut(&ch) {
+                    patterns.retain(|p| p != pattern);
+                }
+            }
+        }
+    }
+
+    /// Update the index after a message has been added to the given channel,
+    /// which now holds `count` messages.
+    pub(crate) fn increased(&mut self, channel: &C, count: usize) {
+        for pattern in self.patterns_at_threshold(channel, count) {
+            self.decrement_unmet(pattern);
+        }
+
+        if count == 1 {
+            for pattern in self.negations.get(channel).cloned().unwrap_or_default() {
+                self.increment_unmet(pattern);
+            }
+        }
+    }
+
+    /// Update the index after a message has been removed from the given
+    /// channel, which now holds `count` messages.
+    pub(crate) fn decreased(&mut self, channel: &C, count: usize) {
+        for pattern in self.patterns_at_threshold(channel, count + 1) {
+            self.increment_unmet(pattern);
+        }
+
+        if count == 0 {
+            for pattern in self.negations.get(channel).cloned().unwrap_or_default() {
+                self.decrement_unmet(pattern);
+            }
+        }
+    }
+
+    /// Return `true` if the given Join Pattern is ready.
+    pub(crate) fn is_ready(&self, pattern: &P) -> bool {
+        self.entries.get(pattern).is_some_and(|e| e.unmet == 0)
+    }
+
+    /// Return the ready Join Patterns consuming from the given channel, in
+    /// order.
+    pub(crate) fn ready_requiring(&self, channel: &C) -> Vec<P> {
+        Self::collect(self.ready_requiring.get(channel))
+    }
+
+    /// Return the ready Join Patterns requiring the given channel to be
+    /// empty, in order.
+    pub(crate) fn ready_negating(&self, channel: &C) -> Vec<P> {
+        Self::collect(self.ready_negating.get(channel))
+    }
+
+    fn collect(patterns: Option<&BTreeSet<P>>) -> Vec<P> {
+        patterns
+            .map(|ps| ps.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Return the Join Patterns whose requirement for the given channel
+    /// changes at the given number of messages.
+    fn patterns_at_threshold(&self, channel: &C, count: usize) -> Vec<P> {
+        self.thresholds
+            .get(channel)
+            .and_then(|t| t.get(&count))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn increment_unmet(&mut self, pattern: P) {
+        let entry = self.entries.get_mut(&pattern).unwrap();
+        entry.unmet += 1;
+
+        if entry.unmet == 1 {
+            self.mark_unready(pattern);
+        }
+    }
+
+    fn decrement_unmet(&mut self, pattern: P) {
+        let entry = self.entries.get_mut(&pattern).unwrap();
+        entry.unmet -= 1;
+
+        if entry.unmet == 0 {
+            self.mark_ready(pattern);
+        }
+    }
+
+    fn mark_ready(&mut self, pattern: P) {
+        let entry = &self.entries[&pattern];
+
+        for (ch, _) in &entry.required {
+            self.ready_requiring.get_or_default(*ch).insert(pattern);
+        }
+
+        for ch in &entry.negated {
+            self.ready_negating.get_or_default(*ch).insert(pattern);
+        }
+    }
+
+    fn mark_unready(&mut self, pattern: P) {
+        let entry = &self.entries[&pattern];
+
+        for (ch, _) in &entry.required {
+            Self::remove_ready(&mut self.ready_requiring, ch, &pattern);
+        }
+
+        for ch in &entry.negated {
+            Self::remove_ready(&mut self.ready_negating, ch, &pattern);
+        }
+    }
+
+    fn remove_ready(ready: &mut SealableMap<C, BTreeSet<P>>, channel: &C, pattern: &P) {
+        if let Some(patterns) = ready.get_mut(channel) {
+            patterns.remove(pattern);
+
+            if patterns.is_empty() {
+                ready.remove(channel);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_not_ready() {
+        // Given:
+        let mut index: ReadinessIndex<usize, u32> = ReadinessIndex::new();
+
+        // When:
+        index.insert(1, &[10, 11], &[], |_| 0);
+
+        // Then:
+        assert!(!index.is_ready(&1));
+        assert!(index.ready_requiring(&10).is_empty());
+    }
+
+    #[test]
+    fn test_insert_ready() {
+        // Given:
+        let mut index: ReadinessIndex<usize, u32> = ReadinessIndex::new();
+
+        // When:
+        index.insert(1, &[10, 11], &[], |_| 1);
+
+        // Then:
+        assert!(index.is_ready(&1));
+        assert_eq!(vec![1], index.ready_requiring(&10));
+        assert_eq!(vec![1], index.ready_requiring(&11));
+    }
+
+    #[test]
+    fn test_ready_once_all_channels_hold_messages() {
+        // Given:
+        let mut index: ReadinessIndex<usize, u32> = ReadinessIndex::new();
+        index.insert(1, &[10, 11], &[], |_| 0);
+
+        // When:
+        index.increased(&10, 1);
+        let ready_after_a = index.is_ready(&1);
+        index.increased(&11, 1);
+
+        // Then:
+        assert!(!ready_after_a);
+        assert!(index.is_ready(&1));
+    }
+
+    #[test]
+    fn test_repeated_channel_requires_multiple_messages() {
+        // Given:
+        let mut index: ReadinessIndex<usize, u32> = ReadinessIndex::new();
+        index.insert(1, &[10, 10], &[], |_| 0);
+
+        // When:
+        index.increased(&10, 1);
+        let ready_after_one = index.is_ready(&1);
+        index.increased(&10, 2);
+        let ready_after_two = index.is_ready(&1);
+        index.decreased(&10, 1);
+
+        // Then:
+        assert!(!ready_after_one);
+        assert!(ready_after_two);
+        assert!(!index.is_ready(&1));
+    }
+
+    #[test]
+    fn test_negated_channel() {
+        // Given:
+        let mut index: ReadinessIndex<usize, u32> = ReadinessIndex::new();
+        index.insert(1, &[10], &[11], |&ch| if ch == 10 { 1 } else { 0 });
+
+        // When:
+        let ready_initially = index.is_ready(&1);
+        index.increased(&11, 1);
+        let ready_after_b = index.is_ready(&1);
+        index.decreased(&11, 0);
+
+        // Then:
+        assert!(ready_initially);
+        assert!(!ready_after_b);
+        assert!(index.is_ready(&1));
+        assert_eq!(vec![1], index.ready_negating(&11));
+    }
+
+    #[test]
+    fn test_ready_in_order() {
+        // Given:
+        let mut index: ReadinessIndex<usize, u32> = ReadinessIndex::new();
+        index.insert(3, &[10], &[], |_| 0);
+        index.insert(1, &[10], &[], |_| 0);
+        index.insert(2, &[10, 10], &[], |_| 0);
+
+        // When:
+        index.increased(&10, 1);
+        index.increased(&10, 2);
+
+        // Then:
+        assert_eq!(vec![1, 2, 3], index.ready_requiring(&10));
+    }
+
+    #[test]
+    fn test_remove() {
+        // Given:
+        let mut index: ReadinessIndex<usize, u32> = ReadinessIndex::new();
+        index.insert(1, &[10], &[11], |_| 0);
+        index.insert(2, &[10], &[], |_| 0);
+
+        // When:
+        index.remove(&1);
+        index.increased(&10, 1);
+
+        // Then:
+        assert!(!index.is_ready(&1));
+        assert_eq!(vec![2], index.ready_requiring(&10));
+        assert!(index.ready_negating(&11).is_empty());
+    }
+}
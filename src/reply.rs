@@ -0,0 +1,533 @@
+//! One-shot channel used to deliver the value generated by a fired Join Pattern
+//! back to the thread or task waiting on a synchronous channel.
+//!
+//! Unlike a `std::sync::mpsc` channel, the receiving end can be waited on both
+//! by blocking the current thread and by polling it as a `Future`, which
+//! wakes the polling task once the value is available.
+//!
+//! Once taken out of the `MessageStore` by a firing Join Pattern, the sending
+//! end records the ID of that Join Pattern. Should it be dropped without a
+//! value while the function body of the Join Pattern panics, the waiting side
+//! is told about the panic rather than just about the missing reply.
+//!
+//! Before firing a Join Pattern, the `Controller` claims the requests it
+//! consumes. The receiving end may withdraw the request when it stops waiting,
+//! which succeeds as long as no Join Pattern has claimed it, and a withdrawn
+//! request can no longer be claimed. Whichever happens first under the lock of
+//! the shared state wins, so either the request is dropped by the `Controller`
+//! or its reply is on its way. A request whose receiving end has been dropped
+//! without withdrawing it, for instance along with a cancelled `ReplyFuture`,
+//! counts as abandoned, and is dropped rather than claimed as well.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::SendError;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Instant;
+
+use super::error::JunctionError;
+use super::types::ids::JoinPatternId;
+
+/// State shared between the sending and receiving end of a reply channel.
+struct Shared<R> {
+    state: Mutex<State<R>>,
+    /// Notified whenever the value has been sent or the sender was dropped.
+    condvar: Condvar,
+}
+
+struct State<R> {
+    value: Option<R>,
+    sender_alive: bool,
+    receiver_alive: bool,
+    /// ID of the Join Pattern whose function body panicked before sending
+    /// the value, if any.
+    panicked: Option<JoinPatternId>,
+    /// Whether the request has been claimed by a firing Join Pattern.
+    consumed: bool,
+    /// Whether the receiving end requested the withdrawal of the request.
+    withdrawal_requested: bool,
+    /// `Waker` of the task last polling the receiving end, if any.
+    waker: Option<Waker>,
+}
+
+/// Status of a request that waits for a Join Pattern to consume it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequestStatus {
+    /// The receiving end is waiting for the reply.
+    Pending,
+    /// The receiving end requested the withdrawal of the request.
+    Withdrawn,
+    /// The receiving end has been dropped, so no one waits for the reply.
+    Abandoned,
+}
+
+/// Create a new reply channel, returning its sending and receiving end.
+pub(crate) fn reply_channel<R>() -> (ReplySender<R>, ReplyReceiver<R>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            value: None,
+            sender_alive: true,
+            receiver_alive: true,
+            panicked: None,
+            consumed: false,
+            withdrawal_requested: false,
+            waker: None,
+        }),
+        condvar: Condvar::new(),
+    });
+
+    (
+        ReplySender {
+            shared: Arc::clone(&shared),
+            join_pattern_id: None,
+        },
+        ReplyReceiver { shared },
+    )
+}
+
+/// Sending end of a reply channel.
+///
+/// Public only because it appears in `nary::ChannelList`, it cannot be named
+/// or used outside of this crate.
+pub struct ReplySender<R> {
+    shared: Arc<Shared<R>>,
+    /// ID of the Join Pattern that consumed the request, if any.
+    join_pattern_id: Option<JoinPatternId>,
+}
+
+/// Request waiting in the `MessageStore` for a Join Pattern to consume it,
+/// independent of the type of its reply.
+pub(crate) trait Request {
+    /// Return the status of the request, as far as the receiving end is
+    /// concerned.
+    fn status(&self) -> RequestStatus;
+
+    /// Claim the request for a firing Join Pattern, so that it can no longer
+    /// be withdrawn.
+    ///
+    /// Return `false` if the request has been withdrawn or abandoned, in which
+    /// case it must not be consumed.
+    fn claim(&self) -> bool;
+
+    /// Release a claimed request again, as the Join Pattern claiming it did
+    /// not fire after all.
+    fn release(&self);
+}
+
+impl<R> ReplySender<R> {
+    /// Record that the request has been consumed by a firing of the Join
+    /// Pattern with the given `JoinPatternId`.
+    pub(crate) fn consumed_by(&mut self, join_pattern_id: JoinPatternId) {
+        self.join_pattern_id = Some(join_pattern_id);
+    }
+
+    /// Return the sending end as the `Request` it answers.
+    pub(crate) fn as_request(&self) -> &dyn Request {
+        self
+    }
+
+    /// Send the reply, waking up whoever is waiting on the receiving end.
+    ///
+    /// Return the value in an error if the receiving end has been dropped.
+    pub(crate) fn send(self, value: R) -> Result<(), SendError<R>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if !state.receiver_alive {
+            return Err(SendError(value));
+        }
+
+        state.value = Some(value);
+
+        // The notification itself happens when `self` is dropped.
+        Ok(())
+    }
+}
+
+impl<R> Request for ReplySender<R> {
+    fn status(&self) -> RequestStatus {
+        let state = self.shared.state.lock().unwrap();
+
+        if !state.receiver_alive {
+            RequestStatus::Abandoned
+        } else if state.withdrawal_requested {
+            RequestStatus::Withdrawn
+        } else {
+            RequestStatus::Pending
+        }
+    }
+
+    fn claim(&self) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if !state.receiver_alive || state.withdrawal_requested {
+            return false;
+        }
+
+        state.consumed = true;
+
+        true
+    }
+
+    fn release(&self) {
+        self.shared.state.lock().unwrap().consumed = false;
+    }
+}
+
+impl<R> Drop for ReplySender<R> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        state.sender_alive = false;
+
+        if state.value.is_none() && thread::panicking() {
+            state.panicked = self.join_pattern_id;
+        }
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+
+        self.shared.condvar.notify_all();
+    }
+}
+
+impl<R> State<R> {
+    /// Return the error explaining why the sending end was dropped without
+    /// sending a value.
+    fn error(&self) -> JunctionError {
+        match self.panicked {
+            Some(pattern_id) => JunctionError::PatternPanicked { pattern_id },
+            None if self.withdrawal_requested => JunctionError::Timeout,
+            None => JunctionError::ReplyDropped,
+        }
+    }
+}
+
+/// Receiving end of a reply channel.
+pub(crate) struct ReplyReceiver<R> {
+    shared: Arc<Shared<R>>,
+}
+
+impl<R> ReplyReceiver<R> {
+    /// Block the current thread until the reply has been sent.
+    ///
+    /// Return an error if the sending end was dropped without sending a reply.
+    pub(crate) fn recv(&self) -> Result<R, JunctionError> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        loop {
+            if let Some(value) = state.value.take() {
+                return Ok(value);
+            }
+
+            if !state.sender_alive {
+                return Err(state.error());
+            }
+
+            state = self.shared.condvar.wait(state).unwrap();
+        }
+    }
+
+    /// Block the current thread until the reply has been sent or the given
+    /// deadline has passed.
+    ///
+    /// Return `JunctionError::Timeout` if the deadline passed first, and the
+    /// same errors as `recv` otherwise.
+    pub(crate) fn recv_deadline(&self, deadline: Instant) -> Result<R, JunctionError> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        loop {
+            if let Some(value) = state.value.take() {
+                return Ok(value);
+            }
+
+            if !state.sender_alive {
+                return Err(state.error());
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Err(JunctionError::Timeout);
+            }
+
+            state = self
+                .shared
+                .condvar
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Return the reply if it has been sent, without blocking.
+    ///
+    /// Return `None` if the sending end is still waiting to send it, and the
+    /// same errors as `recv` otherwise.
+    pub(crate) fn try_recv(&self) -> Option<Result<R, JunctionError>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Some(value) = state.value.take() {
+            return Some(Ok(value));
+        }
+
+        if !state.sender_alive {
+            return Some(Err(state.error()));
+        }
+
+        None
+    }
+
+    /// Return whether the request still waits for a Join Pattern to consume
+    /// it, i.e. it has neither been claimed nor withdrawn.
+    pub(crate) fn is_pending(&self) -> bool {
+        let state = self.shared.state.lock().unwrap();
+
+        state.sender_alive && !state.consumed && !state.withdrawal_requested
+    }
+
+    /// Withdraw the request, unless a Join Pattern has claimed it already.
+    ///
+    /// Return whether the request has been withdrawn, in which case no Join
+    /// Pattern can claim it anymore and the `Controller` drops it instead.
+    /// Otherwise, the reply of the Join Pattern claiming it is on its way.
+    pub(crate) fn withdraw(&self) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.consumed {
+            return false;
+        }
+
+        state.withdrawal_requested = true;
+
+        true
+    }
+
+    /// Mark the request as abandoned, as if the receiving end had been
+    /// dropped already.
+    ///
+    /// Any reply sent from now on is dropped instead of being delivered.
+    pub(crate) fn abandon(&self) {
+        self.shared.state.lock().unwrap().receiver_alive = false;
+    }
+}
+
+impl<R> Drop for ReplyReceiver<R> {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
+
+impl<R> Future for ReplyReceiver<R> {
+    type Output = Result<R, JunctionError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Some(value) = state.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+
+        if !state.sender_alive {
+            return Poll::Ready(Err(state.error()));
+        }
+
+        state.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::task::Wake;
+    use std::thread;
+
+    /// `Waker` counting how many times it has been woken.
+    struct CountingWaker(Mutex<usize>);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            *self.0.lock().unwrap() += 1;
+        }
+    }
+
+    #[test]
+    fn test_recv() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+
+        // When:
+        thread::spawn(move || sender.send(42).unwrap());
+
+        // Then:
+        assert_eq!(Ok(42), receiver.recv());
+    }
+
+    #[test]
+    fn test_recv_after_sender_dropped() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+
+        // When:
+        drop(sender);
+
+        // Then:
+        assert_eq!(Err(JunctionError::ReplyDropped), receiver.recv());
+    }
+
+    #[test]
+    fn test_recv_deadline_passed() {
+        // Given:
+        let (_sender, receiver) = reply_channel::<i32>();
+
+        // When:
+        let reply = receiver.recv_deadline(Instant::now());
+
+        // Then:
+        assert_eq!(Err(JunctionError::Timeout), reply);
+    }
+
+    #[test]
+    fn test_recv_after_withdrawal() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+
+        // When:
+        assert!(receiver.withdraw());
+        assert_eq!(RequestStatus::Withdrawn, sender.status());
+        assert!(!sender.claim());
+        drop(sender);
+
+        // Then:
+        assert_eq!(Err(JunctionError::Timeout), receiver.recv());
+    }
+
+    #[test]
+    fn test_withdrawal_of_claimed_request() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+        assert!(sender.claim());
+
+        // When:
+        let withdrawn = receiver.withdraw();
+        sender.send(42).unwrap();
+
+        // Then:
+        assert!(!withdrawn);
+        assert_eq!(Ok(42), receiver.recv());
+    }
+
+    #[test]
+    fn test_withdrawal_of_released_request() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+        assert!(sender.claim());
+
+        // When:
+        sender.release();
+
+        // Then:
+        assert!(receiver.withdraw());
+        assert_eq!(RequestStatus::Withdrawn, sender.status());
+    }
+
+    #[test]
+    fn test_try_recv() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+        assert!(receiver.try_recv().is_none());
+
+        // When:
+        sender.send(42).unwrap();
+
+        // Then:
+        assert_eq!(Some(Ok(42)), receiver.try_recv());
+    }
+
+    #[test]
+    fn test_status_after_receiver_dropped() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+        assert_eq!(RequestStatus::Pending, sender.status());
+
+        // When:
+        drop(receiver);
+
+        // Then:
+        assert_eq!(RequestStatus::Abandoned, sender.status());
+    }
+
+    #[test]
+    fn test_send_after_receiver_dropped() {
+        // Given:
+        let (sender, receiver) = reply_channel::<i32>();
+
+        // When:
+        drop(receiver);
+
+        // Then:
+        assert_eq!(Err(SendError(42)), sender.send(42));
+    }
+
+    #[test]
+    fn test_poll_wakes_on_send() {
+        // Given:
+        let (sender, mut receiver) = reply_channel::<i32>();
+        let counting_waker = Arc::new(CountingWaker(Mutex::new(0)));
+        let waker = Waker::from(Arc::clone(&counting_waker));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(Poll::Pending, Pin::new(&mut receiver).poll(&mut cx));
+
+        // When:
+        sender.send(42).unwrap();
+
+        // Then:
+        assert_eq!(1, *counting_waker.0.lock().unwrap());
+        assert_eq!(Poll::Ready(Ok(42)), Pin::new(&mut receiver).poll(&mut cx));
+    }
+
+    #[test]
+    fn test_poll_after_sender_dropped() {
+        // Given:
+        let (sender, mut receiver) = reply_channel::<i32>();
+        let counting_waker = Arc::new(CountingWaker(Mutex::new(0)));
+        let waker = Waker::from(Arc::clone(&counting_waker));
+        let mut cx = Context::from_waker(&waker);
+
+        // When:
+        drop(sender);
+
+        // Then:
+        assert_eq!(
+            Poll::Ready(Err(JunctionError::ReplyDropped)),
+            Pin::new(&mut receiver).poll(&mut cx)
+        );
+    }
+
+    #[test]
+    fn test_recv_after_consumer_panicked() {
+        // Given:
+        let (mut sender, receiver) = reply_channel::<i32>();
+        let pattern_id = JoinPatternId::new();
+        sender.consumed_by(pattern_id);
+
+        // When:
+        let _ = thread::spawn(move || {
+            let _sender = sender;
+            panic!("function body panicked");
+        })
+        .join();
+
+        // Then:
+        assert_eq!(
+            Err(JunctionError::PatternPanicked { pattern_id }),
+            receiver.recv()
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Map from keys to values that can be turned into a dense array once all
+//! keys are known.
+//!
+//! Used for the collections of the `Controller` that are keyed by channel.
+//! While channels can still be created, these are backed by a `HashMap`. Once
+//! the `Junction` has been sealed, the `ChannelId`s in use are fixed and, as
+//! they are handed out in increasing order from zero, can index a `Vec`
+//! directly instead, saving the hashing on every look-up.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::types::ids::ChannelId;
+
+/// Trait for keys that can double as index into a dense array.
+///
+/// Keys should be handed out in increasing order from zero, as the size of
+/// the dense array grows with the largest index of any key stored in it.
+pub(crate) trait DenseKey: Hash + Eq + Copy {
+    /// Return the position of the key in a dense array.
+    fn index(&self) -> usize;
+}
+
+impl DenseKey for usize {
+    fn index(&self) -> usize {
+        *self
+    }
+}
+
+impl DenseKey for ChannelId {
+    fn index(&self) -> usize {
+        self.value()
+    }
+}
+
+/// Map backed by a `HashMap` until it is sealed and by a `Vec` afterwards.
+///
+/// A sealed map can still store values for new keys, in which case the `Vec`
+/// grows to accommodate them.
+pub(crate) enum SealableMap<K, V> {
+    Sparse(HashMap<K, V>),
+    Dense(Vec<Option<V>>),
+}
+
+impl<K, V> SealableMap<K, V>
+where
+    K: DenseKey,
+{
+    pub(crate) fn new() -> SealableMap<K, V> {
+        SealableMap::Sparse(HashMap::new())
+    }
+
+    /// Move all values into a dense array indexed by their keys.
+    ///
+    /// Nothing will be done if the map has already been sealed.
+    pub(crate) fn seal(&mut self) {
+        if let SealableMap::Sparse(map) = self {
+            let len = map.keys().map(|k| k.index() + 1).max().unwrap_or(0);
+            let mut values: Vec<Option<V>> = (0..len).map(|_| None).collect();
+
+            for (k, v) in map.drain() {
+                values[k.index()] = Some(v);
+            }
+
+            *self = SealableMap::Dense(values);
+        }
+    }
+
+    /// Return a reference to the value for the given key, if any.
+    pub(crate) fn get(&self, key: &K) -> Option<&V> {
+        match self {
+            SealableMap::Sparse(map) => map.get(key),
+            SealableMap::Dense(values) => values.get(key.index())?.as_ref(),
+        }
+    }
+
+    /// Return a mutable reference to the value for the given key, if any.
+    pub(crate) fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self {
+            SealableMap::Sparse(map) => map.get_mut(key),
+            SealableMap::Dense(values) => values.get_mut(key.index())?.as_mut(),
+        }
+    }
+
+    /// Return a mutable reference to the value for the given key, inserting
+    /// the default value first if there is none.
+    pub(crate) fn get_or_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        match self {
+            SealableMap::Sparse(map) => map.entry(key).or_default(),
+            SealableMap::Dense(values) => {
+                let i = key.index();
+
+                if i >= values.len() {
+                    values.resize_with(i + 1, || None);
+                }
+
+                values[i].get_or_insert_with(V::default)
+            }
+        }
+    }
+
+    /// Store the given value for the given key, replacing any value stored
+    /// for it before.
+    pub(crate) fn insert(&mut self, key: K, value: V) {
+        match self {
+            SealableMap::Sparse(map) => {
+                map.insert(key, value);
+            }
+            SealableMap::Dense(values) => {
+                let i = key.index();
+
+                if i >= values.len() {
+                    values.resize_with(i + 1, || None);
+                }
+
+                values[i] = Some(value);
+            }
+        }
+    }
+
+    /// Remove and return the value for the given key, if any.
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V> {
+        match self {
+            SealableMap::Sparse(map) => map.remove(key),
+            SealableMap::Dense(values) => values.get_mut(key.index())?.take(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_or_default_then_get() {
+        // Given:
+        let mut map: SealableMap<usize, Vec<char>> = SealableMap::new();
+
+        // When:
+        map.get_or_default(3).push('a');
+
+        // Then:
+        assert_eq!(Some(&vec!['a']), map.get(&3));
+        assert!(map.get(&2).is_none());
+    }
+
+    #[test]
+    fn test_seal_keeps_values() {
+        // Given:
+        let mut map: SealableMap<usize, Vec<char>> = SealableMap::new();
+        map.get_or_default(3).push('a');
+        map.get_or_default(0).push('b');
+
+        // When:
+        map.seal();
+
+        // Then:
+        assert_eq!(Some(&vec!['a']), map.get(&3));
+        assert_eq!(Some(&vec!['b']), map.get(&0));
+        assert!(map.get(&1).is_none());
+        assert!(map.get(&7).is_none());
+    }
+
+    #[test]
+    fn test_sealed_grows_for_new_keys() {
+        // Given:
+        let mut map: SealableMap<usize, Vec<char>> = SealableMap::new();
+        map.seal();
+
+        // When:
+        map.get_or_default(5).push('a');
+        map.get_mut(&5).unwrap().push('b');
+
+        // Then:
+        assert_eq!(Some(&vec!['a', 'b']), map.get(&5));
+    }
+
+    #[test]
+    fn test_insert_replaces() {
+        // Given:
+        let mut map: SealableMap<usize, Vec<char>> = SealableMap::new();
+        map.insert(1, vec!['a']);
+        map.seal();
+
+        // When:
+        map.insert(1, vec!['b']);
+        map.insert(4, vec!['c']);
+
+        // Then:
+        assert_eq!(Some(&vec!['b']), map.get(&1));
+        assert_eq!(Some(&vec!['c']), map.get(&4));
+    }
+
+    #[test]
+    fn test_remove() {
+        // Given:
+        let mut map: SealableMap<usize, Vec<char>> = SealableMap::new();
+        map.get_or_default(1).push('a');
+        map.get_or_default(2).push('b');
+
+        // When:
+        let removed = map.remove(&1);
+        map.seal();
+        let removed_sealed = map.remove(&2);
+
+        // Then:
+        assert_eq!(Some(vec!['a']), removed);
+        assert_eq!(Some(vec!['b']), removed_sealed);
+        assert!(map.get(&1).is_none());
+        assert!(map.get(&2).is_none());
+    }
+}
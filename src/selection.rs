@@ -0,0 +1,352 @@
+//! Strategies to select which Join Pattern to fire when several are alive.
+//!
+//! Whenever a message makes more than one Join Pattern of a `Junction` alive
+//! at the same time, only one of them can consume it. Which one is decided by
+//! the `SelectionPolicy` of the `Junction`. By default, the Join Pattern that
+//! has gone without firing for the longest is selected, but a `Junction` can
+//! be set up with any of the other strategies of this module, or with ones
+//! defined outside of it.
+
+use std::collections::HashMap;
+
+use super::counter::Counter;
+use super::types::ids::JoinPatternId;
+
+/// Trait for strategies to select which of the alive Join Patterns to fire.
+///
+/// The `Controller` of a `Junction` calls `select` every time it is about to
+/// fire one of several alive Join Patterns, then informs the policy about
+/// the Join Pattern that was actually fired, so that stateful policies can
+/// keep track of the history of firings.
+pub trait SelectionPolicy: Send {
+    /// Return the index of the Join Pattern to fire within `alive`.
+    ///
+    /// `alive` is never empty. Should the returned index be out of bounds,
+    /// the first Join Pattern in `alive` is fired instead.
+    fn select(&mut self, alive: &[JoinPatternId]) -> usize;
+
+    /// Record that the Join Pattern with the given `JoinPatternId` has fired.
+    fn fired(&mut self, _join_pattern_id: JoinPatternId) {}
+
+    /// Record that the Join Pattern with the given `JoinPatternId` has been
+    /// removed from the `Junction` and will not be alive again.
+    fn removed(&mut self, _join_pattern_id: JoinPatternId) {}
+}
+
+/// `SelectionPolicy` selecting the Join Pattern that has gone without firing
+/// for the longest.
+///
+/// Join Patterns that have never fired take precedence over all others. This
+/// ensures a certain form of *fairness*, by which a Join Pattern that is alive
+/// an infinite amount of times will fire at least once, so that no Join
+/// Pattern can be starved by others, for instance because it has a subset of
+/// their channels.
+///
+/// This is the `SelectionPolicy` used by a `Junction` unless another one is
+/// given.
+#[derive(Debug, Default)]
+pub struct LeastRecentlyFired {
+    /// Number of firings recorded so far, used as a pseudo-time.
+    firings: Counter,
+    /// Map of `JoinPatternId`s to the number of firings at which they have
+    /// last fired. Join Patterns that have never fired are not in the map.
+    last_fired: HashMap<JoinPatternId, Counter>,
+}
+
+impl LeastRecentlyFired {
+    /// Create a new `LeastRecentlyFired` policy.
+    pub fn new() -> LeastRecentlyFired {
+        LeastRecentlyFired::default()
+    }
+}
+
+impl SelectionPolicy for LeastRecentlyFired {
+    fn select(&mut self, alive: &[JoinPatternId]) -> usize {
+        let mut selected = 0;
+
+        for (i, jp_id) in alive.iter().enumerate().skip(1) {
+            let candidate = self.last_fired.get(jp_id);
+            let current = self.last_fired.get(&alive[selected]);
+
+            // `None` compares as less than any `Some`, so Join Patterns that
+            // have never fired take precedence.
+            if candidate < current {
+                selected = i;
+            }
+        }
+
+        selected
+    }
+
+    fn fired(&mut self, join_pattern_id: JoinPatternId) {
+        self.last_fired
+            .insert(join_pattern_id, self.firings.clone());
+        self.firings.increment();
+    }
+
+    fn removed(&mut self, join_pattern_id: JoinPatternId) {
+        self.last_fired.remove(&join_pattern_id);
+    }
+}
+
+/// `SelectionPolicy` selecting the Join Pattern that was declared first.
+///
+/// This policy is fully deterministic but offers no fairness: a Join Pattern
+/// is only fired if none of the Join Patterns declared before it are alive.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeclarationOrder;
+
+impl SelectionPolicy for DeclarationOrder {
+    fn select(&mut self, alive: &[JoinPatternId]) -> usize {
+        min_index_by_key(alive, |&jp_id| jp_id)
+    }
+}
+
+/// `SelectionPolicy` selecting the Join Pattern with the highest priority.
+///
+/// Priorities are assigned to Join Patterns by their `JoinPatternId`, which
+/// can be retrieved from the `PatternHandle` returned upon declaring them.
+/// Join Patterns without an assigned priority have priority 0. Among Join
+/// Patterns with the same priority, the one that has gone without firing for
+/// the longest is selected, as with `LeastRecentlyFired`.
+#[derive(Debug, Default)]
+pub struct Priority {
+    priorities: HashMap<JoinPatternId, i32>,
+    fallback: LeastRecentlyFired,
+}
+
+impl Priority {
+    /// Create a new `Priority` policy without any assigned priorities.
+    pub fn new() -> Priority {
+        Priority::default()
+    }
+
+    /// Assign the given priority to the Join Pattern with the given
+    /// `JoinPatternId`, replacing any priority assigned before.
+    pub fn with_priority(mut self, join_pattern_id: JoinPatternId, priority: i32) -> Priority {
+        self.priorities.insert(join_pattern_id, priority);
+
+        self
+    }
+
+    /// Return the priority of the Join Pattern with the given `JoinPatternId`.
+    fn priority(&self, join_pattern_id: &JoinPatternId) -> i32 {
+        self.priorities.get(join_pattern_id).cloned().unwrap_or(0)
+    }
+}
+
+impl SelectionPolicy for Priority {
+    fn select(&mut self, alive: &[JoinPatternId]) -> usize {
+        let highest = alive.iter().map(|jp_id| self.priority(jp_id)).max();
+
+        let highest_alive: Vec<JoinPatternId> = alive
+            .iter()
+            .filter(|jp_id| Some(self.priority(jp_id)) == highest)
+            .cloned()
+            .collect();
+
+        let selected = highest_alive[self.fallback.select(&highest_alive)];
+
+        alive.iter().position(|&jp_id| jp_id == selected).unwrap()
+    }
+
+    fn fired(&mut self, join_pattern_id: JoinPatternId) {
+        self.fallback.fired(join_pattern_id);
+    }
+
+    fn removed(&mut self, join_pattern_id: JoinPatternId) {
+        self.priorities.remove(&join_pattern_id);
+        self.fallback.removed(join_pattern_id);
+    }
+}
+
+/// `SelectionPolicy` selecting one of the alive Join Patterns uniformly at
+/// random.
+///
+/// The pseudo-random numbers are generated from a seed, so that the same
+/// sequence of messages leads to the same sequence of firings when the
+/// Join Patterns are fired on the `Controller` thread, e.g. with a
+/// `LocalJunction`. The generator is not suitable for cryptographic use.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Create a new `Random` policy generating its numbers from the given
+    /// seed.
+    pub fn with_seed(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    /// Generate the next pseudo-random number with SplitMix64.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+        z ^ (z >> 31)
+    }
+}
+
+impl SelectionPolicy for Random {
+    fn select(&mut self, alive: &[JoinPatternId]) -> usize {
+        // Scale the number to the range of indices rather than taking the
+        // remainder, which would favor lower indices.
+        ((u128::from(self.next_u64()) * alive.len() as u128) >> 64) as usize
+    }
+}
+
+/// `SelectionPolicy` taking turns between the Join Patterns in the order in
+/// which they were declared.
+///
+/// After a Join Pattern has fired, the next alive Join Pattern declared after
+/// it is selected, wrapping around to the first declared one once the last
+/// declared one has fired.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RoundRobin {
+    last_fired: Option<JoinPatternId>,
+}
+
+impl RoundRobin {
+    /// Create a new `RoundRobin` policy.
+    pub fn new() -> RoundRobin {
+        RoundRobin::default()
+    }
+}
+
+impl SelectionPolicy for RoundRobin {
+    fn select(&mut self, alive: &[JoinPatternId]) -> usize {
+        let last_fired = self.last_fired;
+
+        // Join Patterns declared after the last fired one come first, in
+        // order of declaration, followed by all others.
+        min_index_by_key(alive, |&jp_id| (Some(jp_id) <= last_fired, jp_id))
+    }
+
+    fn fired(&mut self, join_pattern_id: JoinPatternId) {
+        self.last_fired = Some(join_pattern_id);
+    }
+}
+
+/// Return the index of the `JoinPatternId` with the smallest key.
+fn min_index_by_key<K, F>(join_pattern_ids: &[JoinPatternId], key: F) -> usize
+where
+    K: Ord,
+    F: Fn(&JoinPatternId) -> K,
+{
+    join_pattern_ids
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, jp_id)| key(jp_id))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_fired_prefers_never_fired() {
+        // Given:
+        let (a, b) = (JoinPatternId::new(), JoinPatternId::new());
+        let mut policy = LeastRecentlyFired::new();
+
+        // When:
+        policy.fired(a);
+
+        // Then:
+        assert_eq!(1, policy.select(&[a, b]));
+    }
+
+    #[test]
+    fn test_least_recently_fired_prefers_earlier_firing() {
+        // Given:
+        let (a, b) = (JoinPatternId::new(), JoinPatternId::new());
+        let mut policy = LeastRecentlyFired::new();
+
+        // When:
+        policy.fired(b);
+        policy.fired(a);
+
+        // Then:
+        assert_eq!(1, policy.select(&[a, b]));
+    }
+
+    #[test]
+    fn test_declaration_order() {
+        // Given:
+        let (a, b) = (JoinPatternId::new(), JoinPatternId::new());
+        let mut policy = DeclarationOrder;
+
+        // When:
+        policy.fired(a);
+
+        // Then:
+        assert_eq!(1, policy.select(&[b, a]));
+    }
+
+    #[test]
+    fn test_priority_with_fallback() {
+        // Given:
+        let (a, b, c) = (
+            JoinPatternId::new(),
+            JoinPatternId::new(),
+            JoinPatternId::new(),
+        );
+        let mut policy = Priority::new().with_priority(b, 1).with_priority(c, 1);
+
+        // When:
+        let first = policy.select(&[a, b, c]);
+        policy.fired(b);
+        let second = policy.select(&[a, b, c]);
+
+        // Then:
+        assert_eq!(1, first);
+        assert_eq!(2, second);
+    }
+
+    #[test]
+    fn test_random_is_reproducible() {
+        // Given:
+        let alive: Vec<JoinPatternId> = (0..5).map(|_| JoinPatternId::new()).collect();
+        let mut policy_1 = Random::with_seed(42);
+        let mut policy_2 = Random::with_seed(42);
+
+        // When:
+        let selected_1: Vec<usize> = (0..100).map(|_| policy_1.select(&alive)).collect();
+        let selected_2: Vec<usize> = (0..100).map(|_| policy_2.select(&alive)).collect();
+
+        // Then:
+        assert_eq!(selected_1, selected_2);
+        assert!(selected_1.iter().all(|&i| i < alive.len()));
+        assert!((0..alive.len()).all(|i| selected_1.contains(&i)));
+    }
+
+    #[test]
+    fn test_round_robin_wraps_around() {
+        // Given:
+        let (a, b, c) = (
+            JoinPatternId::new(),
+            JoinPatternId::new(),
+            JoinPatternId::new(),
+        );
+        let alive = [c, a, b];
+        let mut policy = RoundRobin::new();
+
+        // When:
+        let mut selected = Vec::new();
+
+        for _ in 0..4 {
+            let jp_id = alive[policy.select(&alive)];
+            policy.fired(jp_id);
+            selected.push(jp_id);
+        }
+
+        // Then:
+        assert_eq!(vec![a, b, c, a], selected);
+    }
+}
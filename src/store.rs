@@ -0,0 +1,982 @@
+//! Typed storage for the messages sent on the channels of a `Junction`.
+//!
+//! Every channel has a queue of its own, holding values of exactly the type
+//! that is sent on it, so that messages never need to be boxed up or cast
+//! back to their type. A value sent on a channel is first pushed onto the
+//! `Inbox` of its `TypedQueue`, then moved further into the queue once the
+//! `Controller` handles the notification about it.
+//!
+//! The queue is shared between the handles of the channel, the `Controller`
+//! and the Join Patterns built from the channel. The `Controller` only sees
+//! it as an `ErasedQueue` trait object, while each Join Pattern keeps the
+//! typed `QueueRef`s of its channels, taken from the channels when the Join
+//! Pattern was built. The values are thus only ever accessed with the type
+//! they were sent with, without recovering it at runtime.
+//!
+//! Every value keeps the position it was given when it entered its queue for
+//! as long as it stays there, no matter which other values are removed in
+//! the meantime. The values are kept in a `BTreeMap` by position, so that
+//! any value can be removed without moving any others, and neither the
+//! memory held by a queue nor iterating over its positions depends on how
+//! many values have passed through it before.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::slice::Iter;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::reply::{ReplySender, Request, RequestStatus};
+use super::sealable_map::SealableMap;
+use super::types::functions::PanicHook;
+use super::types::ids::{ChannelId, JoinPatternId};
+
+/// Values sent on a channel that the `Controller` has yet to take in.
+///
+/// Once closed, which happens as soon as the `Controller` is gone, no more
+/// values can be pushed onto it.
+struct Inbox<T> {
+    state: Mutex<InboxState<T>>,
+}
+
+struct InboxState<T> {
+    values: VecDeque<T>,
+    /// Number of values pushed onto the `Inbox` so far.
+    pushed: usize,
+    closed: bool,
+}
+
+impl<T> Inbox<T> {
+    fn new() -> Inbox<T> {
+        Inbox {
+            state: Mutex::new(InboxState {
+                values: VecDeque::new(),
+                pushed: 0,
+                closed: false,
+            }),
+        }
+    }
+
+    /// Push the given value onto the `Inbox`, returning it as error if the
+    /// `Inbox` has been closed.
+    ///
+    /// Return the position the value is given once it enters the queue
+    /// behind the `Inbox`. Values enter the queue in the order they were
+    /// pushed, each at the next position, so this is simply the number of
+    /// values pushed before.
+    fn push(&self, value: T) -> Result<usize, T> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if state.closed {
+            return Err(value);
+        }
+
+        state.values.push_back(value);
+        state.pushed += 1;
+
+        Ok(state.pushed - 1)
+    }
+
+    /// Remove and return the least recently pushed value, if any.
+    fn pop(&self) -> Option<T> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values
+            .pop_front()
+    }
+
+    /// Close the `Inbox` and drop all values that are still in it.
+    ///
+    /// Dropping the values notifies anyone waiting on a reply to one of them
+    /// that no reply is coming.
+    fn close(&self) {
+        let values = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.closed = true;
+
+            std::mem::take(&mut state.values)
+        };
+
+        drop(values);
+    }
+}
+
+/// Type-erased interface of a queue, used by the `Controller`.
+pub(crate) trait ErasedQueue: Send + Sync {
+    /// Move the least recently sent value from the `Inbox` into the queue.
+    ///
+    /// Return `Some` of the position of the value in the queue, `None` if
+    /// there was no value to move. A request that has already been withdrawn
+    /// or abandoned is dropped rather than moved into the queue, in which
+    /// case `None` is returned as well, but its position is used up all the
+    /// same.
+    fn receive(&self) -> Option<usize>;
+
+    /// Return the number of values in the queue.
+    fn len(&self) -> usize;
+
+    /// Return the positions of all values in the queue, in increasing order.
+    fn positions(&self) -> Vec<usize>;
+
+    /// Return the position of the value that `n` other values in the queue
+    /// precede, if any.
+    fn nth_position(&self, n: usize) -> Option<usize>;
+
+    /// Return `true` if there is a value at the given position in the queue.
+    fn contains(&self, position: usize) -> bool;
+
+    /// Return the `RequestStatus` of the value at the given position,
+    /// `Pending` if there is no such value or the values are not requests.
+    fn request_status(&self, position: usize) -> RequestStatus;
+
+    /// Claim the request at the given position for a firing Join Pattern.
+    ///
+    /// Return `false` if it has been withdrawn or abandoned. Values that are
+    /// not requests can always be claimed.
+    fn claim(&self, position: usize) -> bool;
+
+    /// Release the request at the given position, claimed before.
+    fn release(&self, position: usize);
+
+    /// Remove the value at the given position from the queue.
+    fn remove(&self, position: usize);
+
+    /// Close the `Inbox` of the queue and drop all values in the queue.
+    fn close(&self);
+}
+
+/// Typed interface of a queue, used by the handles of its channel and the
+/// Join Patterns built from them.
+pub(crate) trait MessageQueue<T>: ErasedQueue {
+    /// Push the given value onto the `Inbox` of the queue, returning it as
+    /// error if the `Inbox` has been closed.
+    ///
+    /// Return the position the value is given in the queue.
+    fn push(&self, value: T) -> Result<usize, T>;
+
+    /// Remove and return the value at the given position, if any.
+    fn take(&self, position: usize) -> Option<T>;
+
+    /// Put a value taken out of the queue back at its position.
+    fn restore(&self, position: usize, value: T);
+
+    /// Wrap a value taken out of the queue to hand it to the function body
+    /// of a firing Join Pattern, which may run on another thread.
+    fn sendable(&self, value: T) -> AssertSend<T>;
+}
+
+/// Queue that can be shared with the `Controller` of a `Junction`.
+///
+/// Implemented by `TypedQueue` for values that are `Send`, and by the queues
+/// of a `LocalJunction`, which never leave its thread, for any values.
+pub(crate) trait SharedQueue: Send + Sync + 'static {
+    /// Type of the values in the queue.
+    type Value: 'static;
+
+    /// Return the `TypedQueue` holding the values.
+    fn typed(&self) -> &TypedQueue<Self::Value>;
+
+    /// Wrap a value taken out of the queue to hand it to the function body
+    /// of a firing Join Pattern.
+    fn sendable(value: Self::Value) -> AssertSend<Self::Value>;
+}
+
+/// Values a `TypedQueue` has taken in from its `Inbox`.
+struct Values<T> {
+    values: BTreeMap<usize, T>,
+    /// Position the next value entering the queue is given.
+    next_position: usize,
+}
+
+/// Queue of the values available on a channel, in the order they were sent.
+///
+/// Values are given increasing positions as they enter the queue and only
+/// the values still in the queue are stored.
+pub(crate) struct TypedQueue<T> {
+    inbox: Inbox<T>,
+    values: Mutex<Values<T>>,
+    /// Function returning the `Request` a value carries, `None` if the
+    /// values are not requests.
+    request: Option<fn(&T) -> &dyn Request>,
+}
+
+impl<T> TypedQueue<T> {
+    pub(crate) fn new() -> TypedQueue<T> {
+        TypedQueue {
+            inbox: Inbox::new(),
+            values: Mutex::new(Values {
+                values: BTreeMap::new(),
+                next_position: 0,
+            }),
+            request: None,
+        }
+    }
+
+    /// Create a queue of requests, whose `Request` is returned by the given
+    /// function.
+    pub(crate) fn for_requests(request: fn(&T) -> &dyn Request) -> TypedQueue<T> {
+        TypedQueue {
+            request: Some(request),
+            ..TypedQueue::new()
+        }
+    }
+
+    /// Lock the values taken in from the `Inbox`.
+    fn values(&self) -> MutexGuard<'_, Values<T>> {
+        self.values.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run `f` on the `Request` at the given position, returning `None` if
+    /// there is no such value or the values are not requests.
+    fn with_request<U>(&self, position: usize, f: impl FnOnce(&dyn Request) -> U) -> Option<U> {
+        let request = self.request?;
+
+        self.values()
+            .values
+            .get(&position)
+            .map(|value| f(request(value)))
+    }
+}
+
+impl<T> SharedQueue for TypedQueue<T>
+where
+    T: Send + 'static,
+{
+    type Value = T;
+
+    fn typed(&self) -> &TypedQueue<T> {
+        self
+    }
+
+    fn sendable(value: T) -> AssertSend<T> {
+        AssertSend::from_send(value)
+    }
+}
+
+impl<Q> ErasedQueue for Q
+where
+    Q: SharedQueue,
+{
+    fn receive(&self) -> Option<usize> {
+        let queue = self.typed();
+        let value = queue.inbox.pop()?;
+        let mut values = queue.values();
+        let position = values.next_position;
+        values.next_position += 1;
+
+        if let Some(request) = queue.request {
+            if request(&value).status() != RequestStatus::Pending {
+                return None;
+            }
+        }
+
+        values.values.insert(position, value);
+
+        Some(position)
+    }
+
+    fn len(&self) -> usize {
+        self.typed().values().values.len()
+    }
+
+    fn positions(&self) -> Vec<usize> {
+        self.typed().values().values.keys().cloned().collect()
+    }
+
+    fn nth_position(&self, n: usize) -> Option<usize> {
+        self.typed().values().values.keys().nth(n).cloned()
+    }
+
+    fn contains(&self, position: usize) -> bool {
+        self.typed().values().values.contains_key(&position)
+    }
+
+    fn request_status(&self, position: usize) -> RequestStatus {
+        self.typed()
+            .with_request(position, |request| request.status())
+            .unwrap_or(RequestStatus::Pending)
+    }
+
+    fn claim(&self, position: usize) -> bool {
+        self.typed()
+            .with_request(position, |request| request.claim())
+            .unwrap_or(true)
+    }
+
+    fn release(&self, position: usize) {
+        self.typed()
+            .with_request(position, |request| request.release());
+    }
+
+    fn remove(&self, position: usize) {
+        let value = self.typed().values().values.remove(&position);
+
+        drop(value);
+    }
+
+    fn close(&self) {
+        let queue = self.typed();
+        queue.inbox.close();
+
+        let values = std::mem::take(&mut queue.values().values);
+
+        drop(values);
+    }
+}
+
+impl<Q> MessageQueue<Q::Value> for Q
+where
+    Q: SharedQueue,
+{
+    fn push(&self, value: Q::Value) -> Result<usize, Q::Value> {
+        self.typed().inbox.push(value)
+    }
+
+    fn take(&self, position: usize) -> Option<Q::Value> {
+        self.typed().values().values.remove(&position)
+    }
+
+    fn restore(&self, position: usize, value: Q::Value) {
+        self.typed().values().values.insert(position, value);
+    }
+
+    fn sendable(&self, value: Q::Value) -> AssertSend<Q::Value> {
+        Q::sendable(value)
+    }
+}
+
+/// Wrapper for a message consumed by a firing Join Pattern, asserting that
+/// it may be sent to the thread running the function body of the Join
+/// Pattern, even though its type may not be `Send`.
+///
+/// The `Job`s and `Future`s running the function bodies of Join Patterns
+/// need to be `Send`, as they may be handed to an `Executor` or `Spawner`.
+/// The messages they consume are wrapped by the queues of their channels:
+///
+/// - The queues of a `Junction` only hold values that are `Send`, which are
+///   wrapped with `from_send`.
+/// - The queues of a `LocalJunction` may hold values that are not `Send`,
+///   which it wraps with the unsafe `new`. This is sound as the functions of
+///   its Join Patterns run on its own thread, unless an `Executor` was set
+///   for the Join Pattern or it was declared with `then_do_async`, both of
+///   which require the messages of all of its channels to be `Send`.
+///
+/// Public only because it appears in `nary::ChannelList`, it cannot be named
+/// or used outside of this crate.
+pub struct AssertSend<T>(T);
+
+// SAFETY: Values are only wrapped if they are `Send` or under the conditions
+// listed above.
+unsafe impl<T> Send for AssertSend<T> {}
+
+impl<T> AssertSend<T> {
+    /// Wrap the given value, which is `Send` already.
+    pub(crate) fn from_send(value: T) -> AssertSend<T>
+    where
+        T: Send,
+    {
+        AssertSend(value)
+    }
+
+    /// Wrap the given value.
+    ///
+    /// # Safety
+    ///
+    /// The value must only be sent to another thread under the conditions
+    /// listed above.
+    pub(crate) unsafe fn new(value: T) -> AssertSend<T> {
+        AssertSend(value)
+    }
+
+    /// Wrap the given value together with the value of `other`, both of
+    /// which may be sent to another thread.
+    pub(crate) fn zip<U>(self, other: AssertSend<U>) -> AssertSend<(T, U)> {
+        AssertSend((self.0, other.0))
+    }
+
+    /// Return a mutable reference to the wrapped value.
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
+    /// Unwrap the wrapped value.
+    pub(crate) fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, U> AssertSend<(T, U)> {
+    /// Split the wrapped pair into its two values, each of which may be sent
+    /// to another thread along with the pair.
+    pub(crate) fn unzip(self) -> (AssertSend<T>, AssertSend<U>) {
+        let (first, second) = self.0;
+
+        (AssertSend(first), AssertSend(second))
+    }
+}
+
+/// Typed handle to the queue of a channel.
+///
+/// Handed to the Join Patterns built from the channel, so that they access
+/// the messages of the channel with their actual type.
+///
+/// Public only because it appears in `nary::ChannelList`, it cannot be named
+/// or used outside of this crate.
+pub struct QueueRef<T> {
+    channel_id: ChannelId,
+    queue: Arc<dyn MessageQueue<T>>,
+}
+
+impl<T> Clone for QueueRef<T> {
+    fn clone(&self) -> QueueRef<T> {
+        QueueRef {
+            channel_id: self.channel_id,
+            queue: Arc::clone(&self.queue),
+        }
+    }
+}
+
+impl<T> QueueRef<T> {
+    /// Return the ID of the channel of the queue.
+    pub(crate) fn channel_id(&self) -> ChannelId {
+        self.channel_id
+    }
+}
+
+impl<T> QueueRef<T>
+where
+    T: 'static,
+{
+    /// Create the handle to the given queue of the channel with the given
+    /// `ChannelId`.
+    pub(crate) fn new<Q>(channel_id: ChannelId, queue: Q) -> QueueRef<T>
+    where
+        Q: SharedQueue<Value = T>,
+    {
+        QueueRef {
+            channel_id,
+            queue: Arc::new(queue),
+        }
+    }
+
+    /// Return the queue as `OwnedQueue`, to register it with the `Controller`.
+    pub(crate) fn owned(&self) -> OwnedQueue {
+        OwnedQueue(Arc::clone(&self.queue) as Arc<dyn ErasedQueue>)
+    }
+
+    /// Push the given value onto the queue, returning it as error if the
+    /// `Controller` is gone.
+    ///
+    /// Return the position the value is given in the queue.
+    pub(crate) fn push(&self, value: T) -> Result<usize, T> {
+        self.queue.push(value)
+    }
+
+    /// Remove and return the value at the given position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no value at the position.
+    pub(crate) fn take(&self, position: usize) -> T {
+        self.queue.take(position).unwrap()
+    }
+
+    /// Remove and return the value at the given position, to hand it to the
+    /// function body of a firing Join Pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no value at the position.
+    pub(crate) fn take_sendable(&self, position: usize) -> AssertSend<T> {
+        self.queue.sendable(self.take(position))
+    }
+
+    /// Run `f` on a reference to the value at the given position.
+    ///
+    /// The value is taken out of the queue while `f` runs and put back
+    /// afterwards, even if `f` panics. `f` may thus inspect other values of
+    /// the same queue in turn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no value at the position.
+    pub(crate) fn with<U>(&self, position: usize, f: impl FnOnce(&T) -> U) -> U {
+        let value = Restore {
+            queue: self,
+            position,
+            value: Some(self.take(position)),
+        };
+
+        f(value.value.as_ref().unwrap())
+    }
+
+    /// Put a value taken out of the queue back at its position.
+    pub(crate) fn restore(&self, position: usize, value: T) {
+        self.queue.restore(position, value);
+    }
+}
+
+/// Value taken out of a queue, which is put back at its position once
+/// dropped.
+struct Restore<'a, T>
+where
+    T: 'static,
+{
+    queue: &'a QueueRef<T>,
+    position: usize,
+    value: Option<T>,
+}
+
+impl<'a, T> Drop for Restore<'a, T>
+where
+    T: 'static,
+{
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.queue.restore(self.position, value);
+        }
+    }
+}
+
+/// Queue of a channel as registered with the `Controller`.
+///
+/// The queue is shared with the handles of the channel and the Join Patterns
+/// built from them, which keep it alive. Once the `OwnedQueue` is dropped,
+/// along with the `Controller` or an unhandled request to register it, the
+/// queue is closed, so that nothing can be sent on the channel any more and
+/// the values left in it are dropped.
+pub(crate) struct OwnedQueue(Arc<dyn ErasedQueue>);
+
+impl Drop for OwnedQueue {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Collection of the queues of all channels of a `Junction`.
+pub(crate) struct MessageStore {
+    queues: SealableMap<ChannelId, OwnedQueue>,
+}
+
+impl MessageStore {
+    pub(crate) fn new() -> MessageStore {
+        MessageStore {
+            queues: SealableMap::new(),
+        }
+    }
+
+    /// Store the queues in a dense array indexed by `ChannelId` from now on.
+    pub(crate) fn seal(&mut self) {
+        self.queues.seal();
+    }
+
+    /// Register the queue of the channel with the given `ChannelId`.
+    pub(crate) fn register(&mut self, channel_id: ChannelId, queue: OwnedQueue) {
+        self.queues.insert(channel_id, queue);
+    }
+
+    /// Return the queue of the given channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no queue for the given channel.
+    fn queue(&self, channel_id: &ChannelId) -> &dyn ErasedQueue {
+        &*self.queues.get(channel_id).unwrap().0
+    }
+
+    /// Move the least recently sent value on the given channel from its
+    /// `Inbox` into its queue.
+    ///
+    /// Return `Some` of the position of the message in the queue, `None` if
+    /// the channel is unknown or there was no value to move.
+    pub(crate) fn receive(&mut self, channel_id: &ChannelId) -> Option<usize> {
+        self.queues.get(channel_id)?.0.receive()
+    }
+
+    /// Return the number of messages in the queue of the given channel.
+    pub(crate) fn count(&self, channel_id: &ChannelId) -> usize {
+        self.queues.get(channel_id).map_or(0, |queue| queue.0.len())
+    }
+
+    /// Return the positions of all messages in the queue of the given
+    /// channel, in increasing order.
+    pub(crate) fn positions(&self, channel_id: &ChannelId) -> Vec<usize> {
+        self.queues
+            .get(channel_id)
+            .map_or_else(Vec::new, |queue| queue.0.positions())
+    }
+
+    /// Return the position of the message in the queue of the given channel
+    /// that `n` other messages precede, if any.
+    pub(crate) fn nth_position(&self, channel_id: &ChannelId, n: usize) -> Option<usize> {
+        self.queues.get(channel_id)?.0.nth_position(n)
+    }
+
+    /// Return `true` if there is a message at the given position in the
+    /// queue of the given channel.
+    pub(crate) fn contains(&self, channel_id: &ChannelId, position: usize) -> bool {
+        self.queues
+            .get(channel_id)
+            .is_some_and(|queue| queue.0.contains(position))
+    }
+
+    /// Return the `RequestStatus` of the message at the given position in the
+    /// queue of the given channel, `Pending` if there is no such message or
+    /// it is not a request.
+    pub(crate) fn request_status(&self, channel_id: &ChannelId, position: usize) -> RequestStatus {
+        self.queues
+            .get(channel_id)
+            .map_or(RequestStatus::Pending, |queue| {
+                queue.0.request_status(position)
+            })
+    }
+
+    /// Claim the message at the given position in the queue of the given
+    /// channel for a firing Join Pattern, should it be a request.
+    ///
+    /// Return `false` if the request has been withdrawn or abandoned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no queue for the given channel.
+    pub(crate) fn claim(&self, channel_id: &ChannelId, position: usize) -> bool {
+        self.queue(channel_id).claim(position)
+    }
+
+    /// Release the message at the given position in the queue of the given
+    /// channel, claimed before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no queue for the given channel.
+    pub(crate) fn release(&self, channel_id: &ChannelId, position: usize) {
+        self.queue(channel_id).release(position);
+    }
+
+    /// Remove the message at the given position in the queue of the given
+    /// channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no queue for the given channel.
+    pub(crate) fn remove(&mut self, channel_id: &ChannelId, position: usize) {
+        self.queue(channel_id).remove(position);
+    }
+}
+
+/// Retrieval of the messages consumed by a firing Join Pattern, one message
+/// per channel of the Join Pattern, in order.
+///
+/// The messages are taken out of the queues given by the Join Pattern, which
+/// need to be those of its channels, in the same order.
+pub struct Retrieval<'a> {
+    join_pattern_id: JoinPatternId,
+    positions: Iter<'a, usize>,
+    panic_hook: Option<&'a PanicHook>,
+}
+
+impl<'a> Retrieval<'a> {
+    /// Prepare the retrieval of the messages at the given positions in the
+    /// queues of the channels of the Join Pattern with the given
+    /// `JoinPatternId`, whose panics are reported to the given `PanicHook`,
+    /// if any.
+    pub(crate) fn new(
+        join_pattern_id: JoinPatternId,
+        positions: &'a [usize],
+        panic_hook: Option<&'a PanicHook>,
+    ) -> Retrieval<'a> {
+        Retrieval {
+            join_pattern_id,
+            positions: positions.iter(),
+            panic_hook,
+        }
+    }
+
+    /// Return the ID of the firing Join Pattern.
+    pub(crate) fn join_pattern_id(&self) -> JoinPatternId {
+        self.join_pattern_id
+    }
+
+    /// Return the `PanicHook` that panics of the function body of the firing
+    /// Join Pattern are reported to, if any.
+    pub(crate) fn panic_hook(&self) -> Option<&'a PanicHook> {
+        self.panic_hook
+    }
+
+    /// Remove and return the value of the next message from the given queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if all messages have been retrieved already, or if the queue
+    /// holds no message at the position of the next message.
+    pub(crate) fn take<T>(&mut self, queue: &QueueRef<T>) -> AssertSend<T>
+    where
+        T: 'static,
+    {
+        queue.take_sendable(*self.positions.next().unwrap())
+    }
+
+    /// Remove and return the `ReplySender` of the next message from the
+    /// given queue of a `RecvChannel`, recording the Join Pattern consuming
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `take`.
+    pub(crate) fn take_reply<R>(
+        &mut self,
+        queue: &QueueRef<ReplySender<R>>,
+    ) -> AssertSend<ReplySender<R>>
+    where
+        R: 'static,
+    {
+        let mut return_sender = self.take(queue);
+        return_sender.get_mut().consumed_by(self.join_pattern_id);
+
+        return_sender
+    }
+
+    /// Remove and return the value and `ReplySender` of the next message
+    /// from the given queue of a `BidirChannel`, recording the Join Pattern
+    /// consuming it.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `take`.
+    pub(crate) fn take_request<T, R>(
+        &mut self,
+        queue: &QueueRef<(T, ReplySender<R>)>,
+    ) -> AssertSend<(T, ReplySender<R>)>
+    where
+        T: 'static,
+        R: 'static,
+    {
+        let mut request = self.take(queue);
+        request.get_mut().1.consumed_by(self.join_pattern_id);
+
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::reply::reply_channel;
+
+    fn store_with_queue<T>(queue: TypedQueue<T>) -> (MessageStore, ChannelId, QueueRef<T>)
+    where
+        T: Send + 'static,
+    {
+        let mut store = MessageStore::new();
+        let channel_id = ChannelId::new(0);
+        let queue = QueueRef::new(channel_id, queue);
+        store.register(channel_id, queue.owned());
+
+        (store, channel_id, queue)
+    }
+
+    fn store_with_channel(values: &[u32]) -> (MessageStore, ChannelId, QueueRef<u32>) {
+        let (mut store, channel_id, queue) = store_with_queue(TypedQueue::new());
+
+        for &value in values {
+            queue.push(value).unwrap();
+            store.receive(&channel_id);
+        }
+
+        (store, channel_id, queue)
+    }
+
+    fn store_with_requests() -> (MessageStore, ChannelId, QueueRef<ReplySender<u32>>) {
+        store_with_queue(TypedQueue::for_requests(ReplySender::<u32>::as_request))
+    }
+
+    #[test]
+    fn test_receive_empty_inbox() {
+        // Given:
+        let (mut store, channel_id, _queue) = store_with_channel(&[]);
+
+        // When:
+        let received = store.receive(&channel_id);
+
+        // Then:
+        assert!(received.is_none());
+        assert!(store.receive(&ChannelId::new(1)).is_none());
+    }
+
+    #[test]
+    fn test_positions_kept_after_removal() {
+        // Given:
+        let (mut store, channel_id, queue) = store_with_channel(&[1, 2, 3]);
+
+        // When:
+        store.remove(&channel_id, 1);
+        store.remove(&channel_id, 0);
+        queue.push(4).unwrap();
+        let received = store.receive(&channel_id);
+
+        // Then:
+        assert_eq!(Some(3), received);
+        assert_eq!(2, store.count(&channel_id));
+        assert_eq!(vec![2, 3], store.positions(&channel_id));
+        assert_eq!(Some(3), store.nth_position(&channel_id, 1));
+        assert_eq!(3, queue.with(2, |value| *value));
+    }
+
+    #[test]
+    fn test_only_live_values_kept() {
+        // Given:
+        let queue = TypedQueue::new();
+
+        for value in 0..1000 {
+            queue.inbox.push(value).unwrap();
+            queue.receive();
+        }
+
+        // When:
+        for position in 1..1000 {
+            ErasedQueue::remove(&queue, position);
+        }
+
+        // Then:
+        assert_eq!(1, queue.len());
+        assert_eq!(vec![0], queue.positions());
+    }
+
+    #[test]
+    fn test_with_restores_value() {
+        // Given:
+        let (store, channel_id, queue) = store_with_channel(&[1, 2]);
+
+        // When:
+        let sum = queue.with(0, |first| queue.with(1, |second| first + second));
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            queue.with(1, |_| panic!("inspection failed"))
+        }));
+
+        // Then:
+        assert_eq!(3, sum);
+        assert!(panicked.is_err());
+        assert_eq!(vec![0, 1], store.positions(&channel_id));
+        assert_eq!(2, queue.with(1, |value| *value));
+    }
+
+    #[test]
+    fn test_retrieval_take() {
+        // Given:
+        let (store, channel_id, queue) = store_with_channel(&[1, 2, 3]);
+        let positions = [2, 0];
+
+        // When:
+        let mut retrieval = Retrieval::new(JoinPatternId::new(), &positions, None);
+        let taken = (
+            retrieval.take(&queue).into_inner(),
+            retrieval.take(&queue).into_inner(),
+        );
+
+        // Then:
+        assert_eq!((3, 1), taken);
+        assert_eq!(vec![1], store.positions(&channel_id));
+    }
+
+    #[test]
+    fn test_request_status() {
+        // Given:
+        let (mut store, channel_id, queue) = store_with_requests();
+
+        let receivers: Vec<_> = (0..3)
+            .map(|_| {
+                let (tx, rx) = reply_channel::<u32>();
+                queue.push(tx).ok().unwrap();
+                store.receive(&channel_id);
+
+                rx
+            })
+            .collect();
+
+        // When:
+        assert!(receivers[1].withdraw());
+        receivers[2].abandon();
+
+        // Then:
+        assert_eq!(RequestStatus::Pending, store.request_status(&channel_id, 0));
+        assert_eq!(
+            RequestStatus::Withdrawn,
+            store.request_status(&channel_id, 1)
+        );
+        assert_eq!(
+            RequestStatus::Abandoned,
+            store.request_status(&channel_id, 2)
+        );
+        assert_eq!(RequestStatus::Pending, store.request_status(&channel_id, 3));
+    }
+
+    #[test]
+    fn test_non_pending_requests_dropped_on_receive() {
+        // Given:
+        let (mut store, channel_id, queue) = store_with_requests();
+
+        let (abandoned_tx, abandoned_rx) = reply_channel::<u32>();
+        let (withdrawn_tx, withdrawn_rx) = reply_channel::<u32>();
+        let (pending_tx, _pending_rx) = reply_channel::<u32>();
+        let positions = (
+            queue.push(abandoned_tx).ok().unwrap(),
+            queue.push(withdrawn_tx).ok().unwrap(),
+            queue.push(pending_tx).ok().unwrap(),
+        );
+
+        // When:
+        drop(abandoned_rx);
+        assert!(withdrawn_rx.withdraw());
+        let received = (
+            store.receive(&channel_id),
+            store.receive(&channel_id),
+            store.receive(&channel_id),
+        );
+
+        // Then:
+        assert_eq!((0, 1, 2), positions);
+        assert_eq!((None, None, Some(2)), received);
+        assert_eq!(vec![2], store.positions(&channel_id));
+    }
+
+    #[test]
+    fn test_claim_request() {
+        // Given:
+        let (mut store, channel_id, queue) = store_with_requests();
+
+        let receivers: Vec<_> = (0..2)
+            .map(|_| {
+                let (tx, rx) = reply_channel::<u32>();
+                queue.push(tx).ok().unwrap();
+                store.receive(&channel_id);
+
+                rx
+            })
+            .collect();
+
+        // When:
+        let claimed = store.claim(&channel_id, 0);
+        assert!(receivers[1].withdraw());
+
+        // Then:
+        assert!(claimed);
+        assert!(!receivers[0].withdraw());
+        assert!(!store.claim(&channel_id, 1));
+    }
+
+    #[test]
+    fn test_dropped_store_closes_queue() {
+        // Given:
+        let (store, channel_id, queue) = store_with_channel(&[1]);
+        queue.push(2).unwrap();
+
+        // When:
+        drop(store);
+
+        // Then:
+        assert_eq!(Err(3), queue.push(3));
+        assert!(queue.queue.take(0).is_none());
+        assert!(queue.queue.positions().is_empty());
+        assert_eq!(ChannelId::new(0), channel_id);
+    }
+}
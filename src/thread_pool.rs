@@ -0,0 +1,193 @@
+//! Bounded pool of worker threads to run the function bodies of fired
+//! Join Patterns.
+//!
+//! Provides a `ThreadPool` that can be handed to any number of `Junction`s so
+//! that they run the function bodies of their Join Patterns on a fixed set
+//! of threads rather than spawning a new thread for every firing.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::executor::{Executor, Job};
+
+/// Sending end of the queue of `Job`s of a `ThreadPool`.
+#[derive(Clone)]
+enum JobSender {
+    Unbounded(Sender<Job>),
+    Bounded(SyncSender<Job>),
+}
+
+/// Fixed-size pool of worker threads.
+///
+/// Jobs submitted to the pool are queued and run by the next idle worker
+/// thread in the order in which they were submitted. Clones of a `ThreadPool`
+/// share the same worker threads and queue, so the same pool can be handed
+/// to multiple `Junction`s. The worker threads shut down once the last clone
+/// of the pool, including the ones held by `Junction`s, has been dropped and
+/// all queued jobs have been run.
+///
+/// Note that a function body waiting on a synchronous channel, such as a
+/// `RecvChannel`, occupies its worker thread for as long as it waits. A pool
+/// that is too small for the Join Patterns running on it can therefore
+/// deadlock where spawning a new thread for every firing would not.
+#[derive(Clone)]
+pub struct ThreadPool {
+    size: usize,
+    sender: JobSender,
+}
+
+impl ThreadPool {
+    /// Create a new `ThreadPool` with `size` worker threads and a queue of
+    /// unbounded capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn new(size: usize) -> ThreadPool {
+        let (sender, receiver) = channel::<Job>();
+
+        ThreadPool::start(size, JobSender::Unbounded(sender), receiver)
+    }
+
+    /// Create a new `ThreadPool` with `size` worker threads and a queue that
+    /// holds at most `capacity` jobs.
+    ///
+    /// Submitting a job while the queue is full blocks until a worker thread
+    /// has taken a job off the queue. For a `Junction`, this means that no
+    /// further messages are handled until the job of the fired Join Pattern
+    /// could be queued. A `capacity` of 0 hands each job directly to an idle
+    /// worker thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn with_queue_capacity(size: usize, capacity: usize) -> ThreadPool {
+        let (sender, receiver) = sync_channel::<Job>(capacity);
+
+        ThreadPool::start(size, JobSender::Bounded(sender), receiver)
+    }
+
+    /// Spawn `size` worker threads taking jobs off the given `Receiver`.
+    fn start(size: usize, sender: JobSender, receiver: Receiver<Job>) -> ThreadPool {
+        assert!(size > 0, "thread pool needs at least one worker thread");
+
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..size {
+            let receiver = Arc::clone(&receiver);
+
+            thread::spawn(move || ThreadPool::work(&receiver));
+        }
+
+        ThreadPool { size, sender }
+    }
+
+    /// Return the number of worker threads in this `ThreadPool`.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Run jobs taken off the queue until all `Sender`s have been dropped.
+    fn work(receiver: &Mutex<Receiver<Job>>) {
+        loop {
+            // Only hold the lock while waiting for the next job, so that
+            // other worker threads can take jobs while this one is running.
+            let job = match receiver.lock().unwrap().recv() {
+                Ok(job) => job,
+                Err(_) => break,
+            };
+
+            // A panicking job must not take its worker thread down with it.
+            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+        }
+    }
+}
+
+impl Executor for ThreadPool {
+    /// Queue the given `Job` to be run by one of the worker threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if all worker threads have shut down, which can only happen if
+    /// one of them panicked outside of a job.
+    fn execute(&self, job: Job) {
+        match &self.sender {
+            JobSender::Unbounded(sender) => sender.send(job).unwrap(),
+            JobSender::Bounded(sender) => sender.send(job).unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_size() {
+        // When:
+        let pool = ThreadPool::new(3);
+
+        // Then:
+        assert_eq!(3, pool.size());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_without_threads() {
+        ThreadPool::new(0);
+    }
+
+    #[test]
+    fn test_execute() {
+        // Given:
+        let pool = ThreadPool::new(2);
+        let (sender, receiver) = channel();
+
+        // When:
+        for i in 0..10 {
+            let sender = sender.clone();
+            pool.execute(Box::new(move || sender.send(i).unwrap()));
+        }
+
+        // Then:
+        let mut actual: Vec<i32> = receiver.iter().take(10).collect();
+        actual.sort();
+        assert_eq!((0..10).collect::<Vec<i32>>(), actual);
+    }
+
+    #[test]
+    fn test_execute_in_order_with_single_thread() {
+        // Given:
+        let pool = ThreadPool::with_queue_capacity(1, 2);
+        let (sender, receiver) = channel();
+
+        // When:
+        for i in 0..10 {
+            let sender = sender.clone();
+            pool.execute(Box::new(move || sender.send(i).unwrap()));
+        }
+
+        // Then:
+        let actual: Vec<i32> = receiver.iter().take(10).collect();
+        assert_eq!((0..10).collect::<Vec<i32>>(), actual);
+    }
+
+    #[test]
+    fn test_execute_after_panic() {
+        // Given:
+        let pool = ThreadPool::new(1);
+        let (sender, receiver) = channel();
+
+        pool.execute(Box::new(|| panic!("job panicked")));
+
+        // When:
+        pool.execute(Box::new(move || sender.send(()).unwrap()));
+
+        // Then:
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
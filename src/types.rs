@@ -1,94 +1,143 @@
 //! Collection of types to increase readability and maintainability of the
 //! crate.
 
-use std::any::Any;
-use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::thread::{JoinHandle, Thread};
 
-use crate::patterns;
-
-/// Shallow wrapper for a trait object using `Box` that can pass through thread
-/// boundaries.
-pub struct Message(Box<dyn Any + Send>);
-
-impl Message {
-    pub(crate) fn new<T>(raw_value: T) -> Message
-    where
-        T: Any + Send,
-    {
-        Message(Box::new(raw_value))
-    }
-
-    /// Cast internal trait object to `Box<T>`.
-    pub(crate) fn downcast<T>(self) -> Result<Box<T>, Box<dyn Any + Send>>
-    where
-        T: Any + Send,
-    {
-        self.0.downcast::<T>()
-    }
-}
+use crate::error::JunctionError;
+use crate::patterns::JoinPattern;
+use crate::queue::QueueSender;
+use crate::selection::SelectionPolicy;
+use crate::store::OwnedQueue;
 
 /// Standardized packet to be used to send messages of various types on the
 /// channels of a Junction.
-pub enum Packet {
-    /// General message send from channel identified by `channel_id`.
-    Message {
+pub(crate) enum Packet {
+    /// Notification that a message has been sent on the channel identified by
+    /// `channel_id`. The message itself waits in the `Inbox` of the channel.
+    Message { channel_id: ids::ChannelId },
+    /// Request registering the `queue` holding the messages of the new
+    /// channel identified by `channel_id`. Should the request be dropped
+    /// instead, so is the queue, which closes it.
+    AddChannelRequest {
+        channel_id: ids::ChannelId,
+        queue: OwnedQueue,
+    },
+    /// Request dropping the message at `position` in the queue of the
+    /// channel identified by `channel_id`, whose withdrawal has been
+    /// requested, unless it has been consumed already.
+    WithdrawRequest {
         channel_id: ids::ChannelId,
-        msg: Message,
+        position: usize,
     },
-    /// Request a new channel ID from the Junction so a new channel can be
-    /// constructed. New ID will be sent back through `return_sender`.
-    NewChannelIdRequest {
-        return_sender: Sender<ids::ChannelId>,
+    /// Request adding a new Join Pattern to the Junction under the given
+    /// `join_pattern_id`.
+    AddJoinPatternRequest {
+        join_pattern_id: ids::JoinPatternId,
+        join_pattern: Box<JoinPattern>,
     },
-    /// Request adding a new Join Pattern to the Junction.
-    AddJoinPatternRequest { join_pattern: JoinPattern },
+    /// Request removing the Join Pattern identified by `join_pattern_id` from
+    /// the Junction.
+    RemoveJoinPatternRequest { join_pattern_id: ids::JoinPatternId },
+    /// Notify the Junction that a firing of the Join Pattern identified by
+    /// `join_pattern_id` has completed.
+    FiringCompleted { join_pattern_id: ids::JoinPatternId },
+    /// Request the Junction to select which Join Pattern to fire with the
+    /// given `policy` from now on.
+    SetSelectionPolicy { policy: Box<dyn SelectionPolicy> },
+    /// Request the Junction to report panicking function bodies of its Join
+    /// Patterns to the given `hook` from now on.
+    SetPanicHook { hook: functions::PanicHook },
+    /// Request the Junction to switch its channel-indexed collections to dense
+    /// arrays, as its channels and Join Patterns are fixed from now on.
+    SealRequest,
     /// Request the internal control thread managing the `Message`s to shut down.
     ShutDownRequest,
 }
 
-/// Enum defining all Join Patterns that can be added to a Junction using the
-/// `AddJoinPatternRequest` in a `Packet`.
-pub enum JoinPattern {
-    /// Single channel Join Pattern.
-    UnarySend(patterns::unary::SendJoinPattern),
-    /// Single `RecvChannel` Join Pattern.
-    UnaryRecv(patterns::unary::RecvJoinPattern),
-    /// Single `BidirChannel` Join Pattern.
-    UnaryBidir(patterns::unary::BidirJoinPattern),
-    /// Two `SendChannel` Join Pattern.
-    BinarySend(patterns::binary::SendJoinPattern),
-    /// `SendChannel` and `RecvChannel` Join Pattern.
-    BinaryRecv(patterns::binary::RecvJoinPattern),
-    /// `SendChannel` and `BidirChannel` Join Pattern.
-    BinaryBidir(patterns::binary::BidirJoinPattern),
-    /// Three `SendChannel` Join Pattern.
-    TernarySend(patterns::ternary::SendJoinPattern),
-    /// Two `SendChannel` and `RecvChannel` Join Pattern.
-    TernaryRecv(patterns::ternary::RecvJoinPattern),
-    /// Two `SendChannel` and `BidirChannel` Join Pattern.
-    TernaryBidir(patterns::ternary::BidirJoinPattern),
+/// Record of whether a `Junction` has been sealed, shared between the
+/// `Junction` and the partial Join Patterns created on it.
+///
+/// Sealing the `Junction` and creating a channel or adding a Join Pattern
+/// each hold the lock while sending their request to the `Controller`. A
+/// channel or Join Pattern is thus either known to the `Controller` before it
+/// seals itself or rejected with `JunctionError::Sealed`, but never silently
+/// discarded.
+#[derive(Clone, Default)]
+pub(crate) struct SealState(Arc<Mutex<bool>>);
+
+impl SealState {
+    /// Run `f` with the lock held, unless the `Junction` has been sealed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionError::Sealed` if the `Junction` has been sealed,
+    /// otherwise whatever error `f` returns.
+    pub(crate) fn unless_sealed<T, F>(&self, f: F) -> Result<T, JunctionError>
+    where
+        F: FnOnce() -> Result<T, JunctionError>,
+    {
+        let sealed = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if *sealed {
+            return Err(JunctionError::Sealed);
+        }
+
+        f()
+    }
+
+    /// Mark the `Junction` as sealed and run `f` with the lock held, unless it
+    /// has been sealed before.
+    ///
+    /// # Errors
+    ///
+    /// Returns whatever error `f` returns, in which case the `Junction` is
+    /// not marked as sealed.
+    pub(crate) fn seal<F>(&self, f: F) -> Result<(), JunctionError>
+    where
+        F: FnOnce() -> Result<(), JunctionError>,
+    {
+        let mut sealed = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if !*sealed {
+            f()?;
+            *sealed = true;
+        }
+
+        Ok(())
+    }
 }
 
 /// Handle to a `Junction`'s underlying `Controller`.
 ///
 /// This struct carries a `JoinHandle` to the thread that the `Controller` of
-/// a `Junction` is running in. It allows for the `Controller` and its thread
-/// to be stopped gracefully at any point.
+/// a `Junction` is running in, unless the `Controller` runs as a task on an
+/// async runtime. It allows for the `Controller` and its thread to be stopped
+/// gracefully at any point.
 pub struct ControllerHandle {
-    sender: Sender<Packet>,
+    sender: QueueSender<Packet>,
     control_thread_handle: Option<JoinHandle<()>>,
 }
 
 impl ControllerHandle {
-    pub(crate) fn new(sender: Sender<Packet>, handle: JoinHandle<()>) -> ControllerHandle {
+    pub(crate) fn new(sender: QueueSender<Packet>, handle: JoinHandle<()>) -> ControllerHandle {
         ControllerHandle {
             sender,
             control_thread_handle: Some(handle),
         }
     }
 
-    /// Extracts a handle to the underlying thread.
+    /// Create a handle to a `Controller` running as a task rather than in a
+    /// thread of its own.
+    pub(crate) fn new_task(sender: QueueSender<Packet>) -> ControllerHandle {
+        ControllerHandle {
+            sender,
+            control_thread_handle: None,
+        }
+    }
+
+    /// Extracts a handle to the underlying thread, `None` if the `Controller`
+    /// runs as a task.
     pub fn thread(&self) -> Option<&Thread> {
         match &self.control_thread_handle {
             Some(h) => Some(h.thread()),
@@ -96,118 +145,56 @@ impl ControllerHandle {
         }
     }
 
-    /// Request the `Controller` to stop gracefully, then join its thread.
+    /// Request the `Controller` to stop gracefully, then join its thread if it
+    /// runs in one.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if it was unable to send shut-down request to the control thread.
-    pub fn stop(&mut self) {
-        self.sender.send(Packet::ShutDownRequest).unwrap();
+    /// Returns `JunctionError::JunctionClosed` if the `Controller` had already
+    /// stopped, or if its thread panicked.
+    pub fn stop(&mut self) -> Result<(), JunctionError> {
+        let sent = self
+            .sender
+            .send(Packet::ShutDownRequest)
+            .map_err(|_| JunctionError::JunctionClosed);
+
+        if let Some(handle) = self.control_thread_handle.take() {
+            handle.join().map_err(|_| JunctionError::JunctionClosed)?;
+        }
 
-        self.control_thread_handle.take().unwrap().join().unwrap();
+        sent
     }
 }
 
 /// Function types related to various kind of functions that can be stored and
 /// executed with Join Patterns.
 pub mod functions {
-    use super::*;
-
-    /// Types and Traits for functions which take one argument.
-    pub mod unary {
-        use super::*;
-
-        /// Trait to allow boxed up functions that take one `Message` and return
-        /// nothing to be cloned.
-        pub trait FnBoxClone: Fn(Message) -> () + Send {
-            fn clone_box(&self) -> Box<dyn FnBoxClone>;
-        }
-
-        impl<F> FnBoxClone for F
-        where
-            F: Fn(Message) -> () + Send + Clone + 'static,
-        {
-            /// Proxy function to be able to implement the `Clone` trait on
-            /// boxed up functions that take one `Message` and return nothing.
-            fn clone_box(&self) -> Box<dyn FnBoxClone> {
-                Box::new(self.clone())
-            }
-        }
-
-        impl Clone for Box<dyn FnBoxClone> {
-            fn clone(&self) -> Box<dyn FnBoxClone> {
-                (**self).clone_box()
-            }
-        }
-
-        /// Type alias for boxed up cloneable functions that take one `Message` and
-        /// return nothing. Mainly meant to increase readability of code.
-        pub type FnBox = Box<dyn FnBoxClone>;
-    }
-
-    /// Types and Traits for functions which take two arguments.
-    pub mod binary {
-        use super::*;
-
-        /// Trait to allow boxed up functions that take two `Message`s and return
-        /// nothing to be cloned.
-        pub trait FnBoxClone: Fn(Message, Message) -> () + Send {
-            fn clone_box(&self) -> Box<dyn FnBoxClone>;
-        }
-
-        impl<F> FnBoxClone for F
-        where
-            F: Fn(Message, Message) -> () + Send + Clone + 'static,
-        {
-            /// Proxy function to be able to implement the `Clone` trait on
-            /// boxed up functions that take two `Message`s and return nothing.
-            fn clone_box(&self) -> Box<dyn FnBoxClone> {
-                Box::new(self.clone())
-            }
-        }
-
-        impl Clone for Box<dyn FnBoxClone> {
-            fn clone(&self) -> Box<dyn FnBoxClone> {
-                (**self).clone_box()
-            }
-        }
-
-        /// Type alias for boxed up cloneable functions that take two `Message`s and
-        /// return nothing. Mainly meant to increase readability of code.
-        pub type FnBox = Box<dyn FnBoxClone>;
-    }
-
-    /// Types and Traits for functions which take three arguments.
-    pub mod ternary {
-        use super::*;
-
-        /// Trait to allow boxed up functions that take three `Message`s and return
-        /// nothing to be cloned.
-        pub trait FnBoxClone: Fn(Message, Message, Message) -> () + Send {
-            fn clone_box(&self) -> Box<dyn FnBoxClone>;
-        }
-
-        impl<F> FnBoxClone for F
-        where
-            F: Fn(Message, Message, Message) -> () + Send + Clone + 'static,
-        {
-            /// Proxy function to be able to implement the `Clone` trait on
-            /// boxed up functions that take three `Message`s and return nothing.
-            fn clone_box(&self) -> Box<dyn FnBoxClone> {
-                Box::new(self.clone())
-            }
-        }
-
-        impl Clone for Box<dyn FnBoxClone> {
-            fn clone(&self) -> Box<dyn FnBoxClone> {
-                (**self).clone_box()
-            }
-        }
-
-        /// Type alias for boxed up cloneable functions that take three `Message`s and
-        /// return nothing. Mainly meant to increase readability of code.
-        pub type FnBox = Box<dyn FnBoxClone>;
-    }
+    use std::any::Any;
+    use std::sync::Arc;
+
+    use crate::executor::Job;
+    use crate::store::Retrieval;
+    use crate::types::ids::JoinPatternId;
+
+    /// Type alias for boxed up functions that retrieve the messages consumed
+    /// by a firing Join Pattern and return the `Job` running its function body
+    /// on them. Mainly meant to increase readability of code.
+    pub type FnBox = Box<dyn Fn(&mut Retrieval) -> Job + Send>;
+
+    /// Type alias for boxed up predicates over the messages of a Join Pattern,
+    /// given by their positions in the queues of its channels, used as its
+    /// guard. Mainly meant to increase readability of code.
+    pub type GuardBox = Box<dyn Fn(&[usize]) -> bool + Send>;
+
+    /// Type alias for boxed up functions that hash the key of the message
+    /// at the given position in the queue of the channel at the given index
+    /// of a keyed Join Pattern. Mainly meant to increase readability of code.
+    pub type KeyBox = Box<dyn Fn(usize, usize) -> u64 + Send>;
+
+    /// Type alias for shared functions that are handed the ID of a Join
+    /// Pattern and the payload of a panic of its function body. Mainly meant
+    /// to increase readability of code.
+    pub type PanicHook = Arc<dyn Fn(JoinPatternId, &(dyn Any + Send)) + Send + Sync>;
 }
 
 /// Adds specific ID types for the various IDs that are used in the crate.
@@ -223,20 +210,30 @@ pub mod ids {
             ChannelId(value)
         }
 
-        /// Increment the internal value of the channel ID.
-        pub(crate) fn increment(&mut self) {
-            self.0 += 1;
+        /// Return the internal value of the channel ID.
+        pub(crate) fn value(&self) -> usize {
+            self.0
         }
     }
 
+    /// Globally synchronized counter to ensure that no two Join Patterns will
+    /// have the same ID.
+    static LATEST_JOIN_PATTERN_ID: AtomicUsize = AtomicUsize::new(0);
+
     /// ID to identify a Join Pattern within a Junction.
-    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    ///
+    /// `JoinPatternId`s are handed out in increasing order from a single
+    /// counter shared by all Junctions, at the time a Join Pattern is added
+    /// to its Junction. They are thus unique across all Junctions, but the
+    /// IDs of the Join Patterns of any one Junction are not consecutive, and
+    /// only reflect the order in which they were added as far as the adding
+    /// threads were not racing each other.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct JoinPatternId(usize);
 
     impl JoinPatternId {
-        /// Increment the internal value of the Join Pattern ID.
-        pub(crate) fn increment(&mut self) {
-            self.0 += 1;
+        pub(crate) fn new() -> JoinPatternId {
+            JoinPatternId(LATEST_JOIN_PATTERN_ID.fetch_add(1, Ordering::Relaxed))
         }
     }
 